@@ -0,0 +1,5 @@
+use bevy::{reflect::Reflect, prelude::Component};
+
+/// Marks the sprite drawn behind the board tiles
+#[derive(Debug, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Component, Reflect)]
+pub struct BoardBackground;
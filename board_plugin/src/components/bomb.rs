@@ -1,4 +1,7 @@
 use bevy::{reflect::Reflect, prelude::Component};
 
 #[derive(Debug, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Component, Reflect)]
-pub struct Bomb;
\ No newline at end of file
+pub struct Bomb {
+    /// Number of mines in the tile
+    pub count: u8,
+}
@@ -2,6 +2,6 @@ use bevy::{reflect::Reflect, prelude::Component};
 
 #[derive(Debug, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Component, Reflect)]
 pub struct BombNeighbor {
-    /// Number of neighbor bombs
+    /// Number of neighbor bombs, summed over every mine of multi-mine tiles
     pub count: u8,
-}
\ No newline at end of file
+}
@@ -2,12 +2,13 @@ use std::fmt::{self, Display, Formatter};
 use std::ops::{Add, Sub};
 use bevy::prelude::Component;
 use bevy::reflect::Reflect;
+use serde::{Deserialize, Serialize};
 
 // #[cfg_attr(feature = "debug", derive(bevy_inspector_egui::Inspectable))]
-#[derive(Debug, Default, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Component, Reflect)]
+#[derive(Debug, Default, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Component, Reflect, Serialize, Deserialize)]
 pub struct Coordinates {
-    pub x: u16,
-    pub y: u16,
+    pub x: i32,
+    pub y: i32,
 }
 
 impl Coordinates {
@@ -34,9 +35,10 @@ impl Add<(i8, i8)> for Coordinates {
     type Output = Self;
 
     fn add(self, (x, y): (i8, i8)) -> Self::Output {
-        let x = ((self.x as i16) + x as i16) as u16;
-        let y = ((self.y as i16) + y as i16) as u16;
-        Self { x, y }
+        Self {
+            x: self.x + x as i32,
+            y: self.y + y as i32,
+        }
     }
 }
 
@@ -46,8 +48,8 @@ impl Sub for Coordinates {
 
     fn sub(self, rhs: Self) -> Self::Output {
         Self {
-            x: self.x.saturating_sub(rhs.x),
-            y: self.y.saturating_sub(rhs.y),
+            x: self.x - rhs.x,
+            y: self.y - rhs.y,
         }
     }
 }
@@ -2,8 +2,12 @@ pub use coordinates::Coordinates;
 pub use bomb::Bomb;
 pub use bomb_neighbor::BombNeighbor;
 pub use uncover::Uncover;
+pub use board_background::BoardBackground;
+pub use themed::Themed;
 
 mod coordinates;
 mod bomb;
 mod bomb_neighbor;
-mod uncover;
\ No newline at end of file
+mod uncover;
+mod board_background;
+mod themed;
\ No newline at end of file
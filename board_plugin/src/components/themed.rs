@@ -0,0 +1,19 @@
+use bevy::{reflect::Reflect, prelude::Component};
+
+/// Part of the board theme a sprite or text is drawn with, so it is restyled in place when
+/// the theme changes
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash, Component, Reflect)]
+pub enum Themed {
+    Board,
+    Tile,
+    CoveredTile,
+    Flag,
+    /// Flag on a tile without mine, shown once the game is lost
+    WrongFlag,
+    Bomb,
+    /// Mine that ended the game
+    ExplodedBomb,
+    /// Mine count text
+    Count(u8),
+    QuestionMark,
+}
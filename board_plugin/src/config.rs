@@ -0,0 +1,167 @@
+use std::{fmt, fs, io::{self, Write}, path::{Path, PathBuf}};
+
+use serde::{de::DeserializeOwned, Serialize};
+
+/// Directory holding the game configuration files, created on first save
+pub fn config_dir() -> PathBuf {
+    dirs::config_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("minesweeper")
+}
+
+/// Directory holding the game data, such as statistics, created on first save
+pub fn data_dir() -> PathBuf {
+    dirs::data_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("minesweeper")
+}
+
+#[derive(Debug)]
+pub enum ConfigError {
+    Io(io::Error),
+    Parse(ron::error::SpannedError),
+    Serialize(ron::Error),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "{}", e),
+            Self::Parse(e) => write!(f, "invalid file: {}", e),
+            Self::Serialize(e) => write!(f, "failed to serialize: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+impl From<io::Error> for ConfigError {
+    fn from(e: io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+/// Reads a RON file
+pub fn load_ron<T: DeserializeOwned>(path: &Path) -> Result<T, ConfigError> {
+    let text = fs::read_to_string(path)?;
+    ron::from_str(&text).map_err(ConfigError::Parse)
+}
+
+/// Reads a RON struct field by field, for files edited by hand: a missing field or one with an
+/// invalid value keeps its default, and unknown fields are ignored. Returns the value along with
+/// why each skipped field was skipped. `T` must take its defaults from `#[serde(default)]`
+pub fn load_ron_fields<T: DeserializeOwned + Serialize + Default>(path: &Path) -> Result<(T, Vec<String>), ConfigError> {
+    let text = fs::read_to_string(path)?;
+    let Some(fields) = struct_fields(&text) else {
+        // Not a struct at all, the parser says why
+        return ron::from_str(&text).map(|value| (value, Vec::new())).map_err(ConfigError::Parse);
+    };
+    let defaults = ron::to_string(&T::default()).map_err(ConfigError::Serialize)?;
+    let known: Vec<_> = struct_fields(&defaults).unwrap_or_default().into_iter().map(|(name, _)| name).collect();
+
+    let mut kept = Vec::new();
+    let mut skipped = Vec::new();
+    for (name, value) in fields {
+        if !known.contains(&name) {
+            skipped.push(format!("unknown field `{}`", name));
+            continue;
+        }
+        let field = format!("{}: {}", name, value);
+        match ron::from_str::<T>(&format!("({})", field)) {
+            Ok(_) => kept.push(field),
+            Err(e) => skipped.push(format!("invalid `{}`: {}", name, e.code)),
+        }
+    }
+    let value = ron::from_str(&format!("({})", kept.join(", "))).map_err(ConfigError::Parse)?;
+    Ok((value, skipped))
+}
+
+/// Splits a RON struct into the source of its fields, `None` when the text is not a struct
+fn struct_fields(text: &str) -> Option<Vec<(&str, &str)>> {
+    let mut rest = skip_blank(text);
+    // The struct name is optional
+    rest = skip_blank(rest.trim_start_matches(|c: char| c.is_alphanumeric() || c == '_'));
+    rest = rest.strip_prefix('(')?;
+    let mut fields = Vec::new();
+    loop {
+        rest = skip_blank(rest);
+        if let Some(end) = rest.strip_prefix(')') {
+            return skip_blank(end).is_empty().then_some(fields);
+        }
+        let name_len = rest.find(|c: char| !(c.is_alphanumeric() || c == '_'))?;
+        let (name, after) = rest.split_at(name_len);
+        rest = skip_blank(after).strip_prefix(':')?;
+        if name.is_empty() {
+            return None;
+        }
+        let value_len = value_len(rest)?;
+        fields.push((name, rest[..value_len].trim()));
+        rest = &rest[value_len..];
+        rest = rest.strip_prefix(',').unwrap_or(rest);
+    }
+}
+
+/// Skips whitespace and comments
+fn skip_blank(mut text: &str) -> &str {
+    loop {
+        text = text.trim_start();
+        if let Some(comment) = text.strip_prefix("//") {
+            text = comment.find('\n').map_or("", |end| &comment[end..]);
+        } else if let Some(comment) = text.strip_prefix("/*") {
+            text = comment.find("*/").map_or("", |end| &comment[end + 2..]);
+        } else {
+            return text;
+        }
+    }
+}
+
+/// Length of the field value at the start of `text`, up to the comma or parenthesis closing it
+fn value_len(text: &str) -> Option<usize> {
+    let mut depth = 0usize;
+    let mut chars = text.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '(' | '[' | '{' => depth += 1,
+            ')' | ']' | '}' if depth == 0 => return Some(i),
+            ')' | ']' | '}' => depth -= 1,
+            ',' if depth == 0 => return Some(i),
+            '"' | '\'' => {
+                // Skip the string or char, escapes included
+                while let Some((_, s)) = chars.next() {
+                    match s {
+                        '\\' => {
+                            chars.next();
+                        }
+                        s if s == c => break,
+                        _ => (),
+                    }
+                }
+            }
+            '/' if matches!(chars.peek(), Some((_, '/' | '*'))) => {
+                let skipped = skip_blank(&text[i..]);
+                let end = text.len() - skipped.len();
+                while chars.peek().is_some_and(|&(j, _)| j < end) {
+                    chars.next();
+                }
+            }
+            _ => (),
+        }
+    }
+    None
+}
+
+/// Writes a RON file. The file is written next to its destination then renamed,
+/// so a crash never leaves a truncated file behind
+pub fn save_ron<T: Serialize>(path: &Path, value: &T) -> Result<(), ConfigError> {
+    let text = ron::ser::to_string_pretty(value, ron::ser::PrettyConfig::default())
+        .map_err(ConfigError::Serialize)?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let temp = path.with_extension("tmp");
+    let mut file = fs::File::create(&temp)?;
+    file.write_all(text.as_bytes())?;
+    file.sync_all()?;
+    fs::rename(&temp, path)?;
+    Ok(())
+}
@@ -0,0 +1,298 @@
+//! Daily challenge boards, the same for every player on a given day.
+//!
+//! The board of a day comes from a seed hashed from the date and the difficulty tag, through
+//! the seeded mine placement of `TileMap::set_bombs_seeded`. Players comparing their times
+//! rely on that placement never changing for a seed: `GOLDEN_LAYOUTS` records layouts it
+//! gave, and `check_golden_layouts`, run by the tests, fails as soon as one of them comes
+//! out differently.
+//! A deliberate change bumps `SEEDED_GENERATOR_VERSION`, which also changes every daily seed.
+
+use std::{
+    fmt::{self, Display, Formatter},
+    str::FromStr,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    minefield::{Generation, MinefieldOptions},
+    random::{hash_coords, hash_str},
+    resources::{tile::Tile, tilemap::{TileMap, SEEDED_GENERATOR_VERSION}, Difficulty},
+};
+
+/// A calendar day, in the proleptic Gregorian calendar
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Date {
+    pub year: i32,
+    pub month: u8,
+    pub day: u8,
+}
+
+impl Date {
+    /// Current day in UTC, so the daily board changes at the same time for everyone
+    pub fn today() -> Self {
+        let seconds = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs());
+        Self::from_days((seconds / 86_400) as i64)
+    }
+
+    /// Day `days` after 1970-01-01
+    pub fn from_days(days: i64) -> Self {
+        // Howard Hinnant's civil_from_days, counting in 400 year eras from 0000-03-01
+        let z = days + 719_468;
+        let era = z.div_euclid(146_097);
+        let doe = z.rem_euclid(146_097);
+        let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+        let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+        let mp = (5 * doy + 2) / 153;
+        let day = (doy - (153 * mp + 2) / 5 + 1) as u8;
+        let month = if mp < 10 { mp + 3 } else { mp - 9 } as u8;
+        let year = (yoe + era * 400 + i64::from(month <= 2)) as i32;
+        Self { year, month, day }
+    }
+
+    /// Days since 1970-01-01
+    pub fn days(&self) -> i64 {
+        let year = i64::from(self.year) - i64::from(self.month <= 2);
+        let era = year.div_euclid(400);
+        let yoe = year.rem_euclid(400);
+        let month = i64::from(self.month);
+        let doy = (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + i64::from(self.day) - 1;
+        let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+        era * 146_097 + doe - 719_468
+    }
+
+    pub fn previous(&self) -> Self {
+        Self::from_days(self.days() - 1)
+    }
+}
+
+impl Display for Date {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{:04}-{:02}-{:02}", self.year, self.month, self.day)
+    }
+}
+
+impl FromStr for Date {
+    type Err = String;
+
+    /// Parses a `YYYY-MM-DD` date
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("invalid date {}, expected YYYY-MM-DD", s);
+        let mut parts = s.trim().splitn(3, '-');
+        let mut next = || parts.next().ok_or_else(invalid);
+        let (year, month, day) = (next()?, next()?, next()?);
+        let date = Self {
+            year: year.parse().map_err(|_| invalid())?,
+            month: month.parse().map_err(|_| invalid())?,
+            day: day.parse().map_err(|_| invalid())?,
+        };
+        // Days past the end of the month come back as another date
+        match Self::from_days(date.days()) == date {
+            true => Ok(date),
+            false => Err(invalid()),
+        }
+    }
+}
+
+impl TryFrom<String> for Date {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl From<Date> for String {
+    fn from(date: Date) -> Self {
+        date.to_string()
+    }
+}
+
+/// Seed of the daily board of `date` on `difficulty`
+pub fn daily_seed(date: Date, difficulty: Difficulty) -> u64 {
+    let seed = hash_str(u64::from(SEEDED_GENERATOR_VERSION), difficulty.tag());
+    hash_coords(seed, date.days() as i32, 0)
+}
+
+/// Options of the daily board of `date` on `difficulty`, which starts with a safe area
+/// uncovered like the boards of the game
+pub fn daily_options(date: Date, difficulty: Difficulty) -> MinefieldOptions {
+    let (map_size, bomb_count) = difficulty.parameters();
+    MinefieldOptions {
+        map_size,
+        bomb_count,
+        max_bombs_per_tile: 1,
+        seed: daily_seed(date, difficulty),
+        generation: Generation::SafeStart,
+    }
+}
+
+/// Mines of a tile map, top row first: `*` for a mine, its count when a tile holds
+/// several, `.` for everything else
+pub fn layout_rows(tile_map: &TileMap) -> Vec<String> {
+    tile_map.iter().rev()
+        .map(|line| line.iter()
+            .map(|tile| match tile {
+                Tile::Bomb(1) => '*',
+                Tile::Bomb(n) => char::from_digit(u32::from(*n), 10).unwrap_or('+'),
+                _ => '.',
+            })
+            .collect())
+        .collect()
+}
+
+/// A layout the seeded mine placement gave, which it must keep giving
+pub struct GoldenLayout {
+    pub seed: u64,
+    pub map_size: (u16, u16),
+    pub bomb_count: u16,
+    pub max_bombs_per_tile: u8,
+    pub rows: &'static [&'static str],
+}
+
+impl GoldenLayout {
+    /// Layout the current placement gives for the same parameters
+    pub fn generate(&self) -> Vec<String> {
+        let mut tile_map = TileMap::empty(self.map_size.0, self.map_size.1);
+        tile_map.set_bombs_seeded(self.bomb_count, self.max_bombs_per_tile, self.seed, None);
+        layout_rows(&tile_map)
+    }
+}
+
+/// Layouts of version 1 of the seeded mine placement: the daily boards of 2024-01-01, then
+/// a multi-mine board
+pub const GOLDEN_LAYOUTS: &[GoldenLayout] = &[
+    GoldenLayout {
+        seed: 15630766508365026633,
+        map_size: (9, 9),
+        bomb_count: 10,
+        max_bombs_per_tile: 1,
+        rows: &[
+            ".....*.*.",
+            ".........",
+            "*........",
+            "....*..*.",
+            "........*",
+            "*........",
+            "...*...*.",
+            ".........",
+            ".*.......",
+        ],
+    },
+    GoldenLayout {
+        seed: 11580368747068763530,
+        map_size: (16, 16),
+        bomb_count: 40,
+        max_bombs_per_tile: 1,
+        rows: &[
+            ".*....*.........",
+            ".*..............",
+            ".*.........*..**",
+            ".*........**..*.",
+            ".*......*.....*.",
+            ".....**.........",
+            "................",
+            ".....*..........",
+            ".*.*...........*",
+            ".*......*.......",
+            "..*...*...**....",
+            "...*..*.........",
+            ".*..*...*...*...",
+            "..*.*..........*",
+            "*..........*....",
+            "....*..*.....*..",
+        ],
+    },
+    GoldenLayout {
+        seed: 9377430772400370291,
+        map_size: (30, 16),
+        bomb_count: 99,
+        max_bombs_per_tile: 1,
+        rows: &[
+            "..*.....*.*........*.**....**.",
+            "...*.*....*.........*....***.*",
+            ".**......**.*.........*....*..",
+            "...*...............*.......**.",
+            ".*.............*....**.*...*..",
+            "..*.***.*......*........*...*.",
+            "*..*..*....*...*..*.*.*..*....",
+            "......*..***...........*......",
+            "..*.**...*.*...*..............",
+            "................*...*...*.....",
+            "*.*........**..........*......",
+            "....**.*....*...*..*..........",
+            "..*.*..*.......*.........*....",
+            "....*.....*......*.*..........",
+            "..*.**..**.....*....*......*..",
+            "...*..*....*.*...*...**.......",
+        ],
+    },
+    GoldenLayout {
+        seed: 42,
+        map_size: (8, 8),
+        bomb_count: 20,
+        max_bombs_per_tile: 3,
+        rows: &[
+            ".....***",
+            "......*.",
+            ".2***.3.",
+            ".......*",
+            "........",
+            "...*2...",
+            "...*....",
+            "*2......",
+        ],
+    },
+];
+
+/// Daily seeds of version 1, by date and difficulty
+pub const GOLDEN_DAILY_SEEDS: &[(&str, Difficulty, u64)] = &[
+    ("2024-01-01", Difficulty::Beginner, 15630766508365026633),
+    ("2024-01-01", Difficulty::Intermediate, 11580368747068763530),
+    ("2024-01-01", Difficulty::Expert, 9377430772400370291),
+];
+
+/// Checks that every golden layout and daily seed still comes out the same, describing the
+/// first that doesn't. Returns the number of layouts checked
+pub fn check_golden_layouts() -> Result<usize, String> {
+    for &(date, difficulty, seed) in GOLDEN_DAILY_SEEDS {
+        let date: Date = date.parse()?;
+        let actual = daily_seed(date, difficulty);
+        if actual != seed {
+            return Err(format!("the daily seed of {} on {} is {} instead of {}", date, difficulty.label(), actual, seed));
+        }
+    }
+    for golden in GOLDEN_LAYOUTS {
+        let rows = golden.generate();
+        if rows != golden.rows {
+            return Err(format!(
+                "seed {} on {}x{} with {} mines gave\n{}\ninstead of\n{}",
+                golden.seed, golden.map_size.0, golden.map_size.1, golden.bomb_count,
+                rows.join("\n"), golden.rows.join("\n"),
+            ));
+        }
+    }
+    Ok(GOLDEN_LAYOUTS.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn golden_layouts_match() {
+        if let Err(e) = check_golden_layouts() {
+            panic!("{}", e);
+        }
+    }
+
+    #[test]
+    fn daily_seeds_differ_by_difficulty() {
+        let date = Date { year: 2024, month: 1, day: 1 };
+        let seeds: Vec<_> = Difficulty::ALL.iter().map(|&d| daily_seed(date, d)).collect();
+        assert!(seeds.iter().enumerate().all(|(i, seed)| !seeds[..i].contains(seed)));
+        assert_ne!(daily_seed(date.previous(), Difficulty::Beginner), seeds[0]);
+    }
+}
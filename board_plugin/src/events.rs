@@ -1,7 +1,12 @@
-use crate::components::Coordinates;
+use bevy::{ecs::system::SystemParam, prelude::EventWriter};
+
+use crate::{components::Coordinates, resources::GameResult};
 
 #[derive(Debug, Copy, Clone)]
-pub struct BoardCompletedEvent;
+pub struct BoardCompletedEvent {
+    /// Moves were undone during the game, in practice mode
+    pub undo_used: bool,
+}
 
 #[derive(Debug, Copy, Clone)]
 pub struct TileTriggerEvent(pub Coordinates);
@@ -11,3 +16,27 @@ pub struct BombExplosionEvent(pub Coordinates);
 
 #[derive(Debug, Copy, Clone)]
 pub struct TileMarkEvent(pub Coordinates);
+
+/// Sent when an endless run ends, with the number of tiles cleared
+#[derive(Debug, Copy, Clone)]
+pub struct EndlessRunEndedEvent(pub u64);
+
+/// Uncovers the neighbors of an uncovered number once all its bombs are flagged
+#[derive(Debug, Copy, Clone)]
+pub struct TileChordEvent(pub Coordinates);
+
+/// Sent once when a game on a bounded board ends, won or lost
+#[derive(Debug, Clone)]
+pub struct GameEndedEvent(pub GameResult);
+
+/// Reverts the last move, in practice mode
+#[derive(Debug, Copy, Clone)]
+pub struct UndoEvent;
+
+/// Writers for the actions a player can take on a tile, shared by every input method
+#[derive(SystemParam)]
+pub struct TileActionWriters<'w> {
+    pub trigger: EventWriter<'w, TileTriggerEvent>,
+    pub mark: EventWriter<'w, TileMarkEvent>,
+    pub chord: EventWriter<'w, TileChordEvent>,
+}
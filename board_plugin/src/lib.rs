@@ -1,26 +1,44 @@
 use bevy::prelude::*;
 use bevy::log;
-use resources::BoardOptions;
+use resources::{BoardOptions, BotOptions, EndlessOptions, InputMap, TouchOptions};
 
 use crate::components::Bomb;
 use crate::components::BombNeighbor;
 use crate::components::Uncover;
+use crate::components::BoardBackground;
+use crate::components::Themed;
 use crate::events::BoardCompletedEvent;
 use crate::events::BombExplosionEvent;
+use crate::events::EndlessRunEndedEvent;
 use crate::events::TileMarkEvent;
 use crate::events::TileTriggerEvent;
+use crate::events::TileChordEvent;
+use crate::events::UndoEvent;
+use crate::events::GameEndedEvent;
 
 mod bounds;
-mod components;
+pub mod components;
 pub mod resources;
 mod systems;
-mod events;
+pub mod events;
+mod random;
+pub mod config;
+pub mod daily;
+pub mod metrics;
+pub mod minefield;
+pub mod puzzle;
+pub mod player;
+pub mod theme;
 
 pub struct BoardPlugin;
 
 #[derive(Default, Debug, PartialEq, Eq, Hash, Copy, Clone, States, Reflect)]
 pub enum BoardState {
     Active,
+    /// Endless procedurally generated board
+    Endless,
+    /// Puzzle editor, see `resources::BoardEditor`
+    Editor,
     #[default]
     Inactive,
 }
@@ -29,11 +47,20 @@ impl Plugin for BoardPlugin {
     fn build(&self, app: &mut App) {
 
         app.register_type::<BoardOptions>();
+        app.register_type::<EndlessOptions>();
         app.register_type::<Bomb>();
         app.register_type::<BombNeighbor>();
         app.register_type::<Uncover>();
+        app.register_type::<BoardBackground>();
+        app.register_type::<Themed>();
+
+        app.add_asset::<theme::Theme>();
+        app.init_asset_loader::<theme::ThemeLoader>();
 
         app.add_state::<BoardState>();
+        app.init_resource::<InputMap>();
+        app.init_resource::<TouchOptions>();
+        app.init_resource::<BotOptions>();
 
         app.add_system(systems::spawn::create_board.in_schedule(OnEnter(BoardState::Active)));
         app.add_system(systems::spawn::despawn_board.in_schedule(OnExit(BoardState::Active)));
@@ -42,13 +69,67 @@ impl Plugin for BoardPlugin {
 
         app.add_system(systems::input::input_handling.in_set(active()));
         app.add_system(systems::uncover::trigger_event_handler.in_set(active()));
+        app.add_system(systems::uncover::start_first_move.in_set(active()));
         app.add_system(systems::uncover::uncover_tiles.in_set(active()));
         app.add_system(systems::mark::mark_tiles.in_set(active()));
+        app.add_system(systems::layout::relayout_board.in_set(active()));
+        app.add_system(systems::uncover::chord_event_handler.in_set(active()));
+        app.add_system(systems::cursor::cursor_keyboard_input.in_set(active()));
+        app.add_system(systems::cursor::update_cursor_highlight.in_set(active()));
+        app.add_system(systems::cursor::cursor_follow_camera.in_set(active()));
+        app.add_system(systems::gamepad::gamepad_cursor_input.in_set(active()));
+        app.add_system(systems::touch::touch_tile_input.in_set(active()));
+        app.add_system(systems::history::undo_input.in_set(active()));
+        app.add_system(systems::history::undo_moves.in_set(active()));
+        app.add_system(systems::game::update_game_timer.in_set(active()));
+        app.add_system(systems::game::count_clicks.in_set(active()));
+        app.add_system(systems::game::end_game.in_set(active()));
+        app.add_system(systems::game::show_mistakes.after(systems::game::end_game).in_set(active()));
+        app.add_system(systems::game::record_game_stats);
+        app.add_system(systems::theme::restyle_board);
+        app.add_system(systems::bot::bot_toggle_input.in_set(active()));
+        app.add_system(systems::bot::bot_play.in_set(active()));
+        #[cfg(feature = "rumble")]
+        app.add_system(systems::gamepad::gamepad_rumble);
+
+        app.add_system(systems::endless::create_endless_board.in_schedule(OnEnter(BoardState::Endless)));
+        app.add_system(systems::endless::despawn_endless_board.in_schedule(OnExit(BoardState::Endless)));
+
+        let endless = || OnUpdate(BoardState::Endless);
+
+        app.add_system(systems::endless::stream_chunks.in_set(endless()));
+        app.add_system(systems::endless::endless_input_handling.in_set(endless()));
+        app.add_system(systems::endless::endless_uncover_tiles.in_set(endless()));
+        app.add_system(systems::endless::endless_mark_tiles.in_set(endless()));
+
+        app.add_system(systems::editor::create_editor_board.in_schedule(OnEnter(BoardState::Editor)));
+        app.add_system(systems::editor::despawn_editor_board.in_schedule(OnExit(BoardState::Editor)));
+
+        let editor = || OnUpdate(BoardState::Editor);
+
+        app.add_systems((
+            systems::editor::editor_input_handling,
+            systems::editor::editor_actions,
+            systems::editor::draw_editor_board,
+        ).chain().in_set(editor()));
 
         app.add_event::<TileTriggerEvent>();
         app.add_event::<TileMarkEvent>();
+        app.add_event::<TileChordEvent>();
+        app.add_event::<UndoEvent>();
+        app.add_event::<GameEndedEvent>();
         app.add_event::<BombExplosionEvent>();
         app.add_event::<BoardCompletedEvent>();
+        app.add_event::<EndlessRunEndedEvent>();
+
+        for set in [active(), endless(), editor()] {
+            app.add_systems((
+                systems::camera::camera_zoom,
+                systems::camera::camera_pan,
+                systems::camera::camera_fit_board,
+                systems::touch::touch_camera_input,
+            ).in_set(set));
+        }
 
         log::info!("Loaded Board Plugin");
     }
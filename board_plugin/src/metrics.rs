@@ -0,0 +1,273 @@
+//! Competitive metrics: 3BV, ZiNi and efficiency measures.
+//!
+//! A board is split into 3BV units, the smallest set of cells that each need their own
+//! left click: every opening (a connected area of empty tiles with the numbers bordering
+//! it) and every number that borders no opening.
+
+use std::collections::VecDeque;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    components::Coordinates,
+    resources::{tile::Tile, tilemap::TileMap, ClickCounts},
+};
+
+/// Difficulty measures of a board, independent of how it is played
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BoardMetrics {
+    /// Minimum number of left clicks needed to clear the board without flagging
+    pub bbbv: u32,
+    pub openings: u32,
+    /// Numbers bordering no opening
+    pub islands: u32,
+    /// Clicks needed to clear the board using flags and chords, from a greedy search.
+    /// This is an upper bound of the exact ZiNi, which is too costly to compute
+    pub zini: u32,
+}
+
+impl BoardMetrics {
+    pub fn compute(tile_map: &TileMap) -> Self {
+        let units = Units::new(tile_map);
+        Self {
+            bbbv: (units.openings + units.islands) as u32,
+            openings: units.openings as u32,
+            islands: units.islands as u32,
+            zini: Simulation::new(tile_map, &units).greedy_zini(),
+        }
+    }
+}
+
+/// 3BV units solved so far on a partially uncovered board
+pub fn solved_bbbv(tile_map: &TileMap, is_uncovered: impl Fn(Coordinates) -> bool) -> u32 {
+    let units = Units::new(tile_map);
+    let mut solved = vec![false; units.openings + units.islands];
+    for (i, cell_units) in units.cells.iter().enumerate() {
+        let coords = units.coordinates(i);
+        // Uncovering a single empty tile opens its whole opening
+        let counts = match tile_map.get(coords) {
+            Some(Tile::Empty) => true,
+            Some(Tile::BombNeighbor(_)) => units.is_island(i),
+            _ => false,
+        };
+        if counts && is_uncovered(coords) {
+            for &unit in cell_units {
+                solved[unit] = true;
+            }
+        }
+    }
+    solved.iter().filter(|&&s| s).count() as u32
+}
+
+/// Metrics of a finished game, see `BoardMetrics`
+#[derive(Debug, Default, Copy, Clone, Serialize, Deserialize)]
+pub struct GameMetrics {
+    pub bbbv: u32,
+    /// 3BV units solved before the game ended, equal to `bbbv` on a win
+    pub solved_bbbv: u32,
+    pub zini: u32,
+    pub clicks: ClickCounts,
+    /// Solved 3BV per second
+    pub bbbv_per_second: f32,
+    /// Solved 3BV per click
+    pub ioe: f32,
+    /// ZiNi per click, on won games
+    pub zini_efficiency: f32,
+}
+
+impl GameMetrics {
+    pub fn new(board: &BoardMetrics, solved_bbbv: u32, clicks: ClickCounts, time: f32, won: bool) -> Self {
+        let ratio = |a: u32, b: f32| if b > 0. { a as f32 / b } else { 0. };
+        let total = clicks.total() as f32;
+        Self {
+            bbbv: board.bbbv,
+            solved_bbbv,
+            zini: board.zini,
+            clicks,
+            bbbv_per_second: ratio(solved_bbbv, time),
+            ioe: ratio(solved_bbbv, total),
+            zini_efficiency: if won { ratio(board.zini, total) } else { 0. },
+        }
+    }
+}
+
+/// 3BV units of each tile. Openings come first, then islands
+struct Units {
+    width: i32,
+    height: i32,
+    /// Units each tile belongs to. Numbers bordering several openings belong to all of them
+    cells: Vec<Vec<usize>>,
+    openings: usize,
+    islands: usize,
+}
+
+impl Units {
+    fn new(tile_map: &TileMap) -> Self {
+        let (width, height) = (tile_map.width() as i32, tile_map.height() as i32);
+        let mut units = Self {
+            width,
+            height,
+            cells: vec![Vec::new(); (width * height) as usize],
+            openings: 0,
+            islands: 0,
+        };
+
+        for i in 0..units.cells.len() {
+            let coords = units.coordinates(i);
+            if tile_map.get(coords) != Some(&Tile::Empty) || !units.cells[i].is_empty() {
+                continue;
+            }
+            let opening = units.openings;
+            units.openings += 1;
+            let mut queue = VecDeque::from([coords]);
+            units.cells[i].push(opening);
+            while let Some(current) = queue.pop_front() {
+                if tile_map.get(current) != Some(&Tile::Empty) {
+                    continue;
+                }
+                for neighbor in current.neighbors() {
+                    let Some(j) = units.index(neighbor) else { continue; };
+                    if tile_map.get(neighbor).is_some_and(Tile::is_bomb) || units.cells[j].contains(&opening) {
+                        continue;
+                    }
+                    units.cells[j].push(opening);
+                    queue.push_back(neighbor);
+                }
+            }
+        }
+
+        for i in 0..units.cells.len() {
+            let is_number = matches!(tile_map.get(units.coordinates(i)), Some(Tile::BombNeighbor(_)));
+            if is_number && units.cells[i].is_empty() {
+                units.cells[i].push(units.openings + units.islands);
+                units.islands += 1;
+            }
+        }
+        units
+    }
+
+    fn index(&self, coords: Coordinates) -> Option<usize> {
+        let in_bounds = (0..self.width).contains(&coords.x) && (0..self.height).contains(&coords.y);
+        in_bounds.then_some((coords.y * self.width + coords.x) as usize)
+    }
+
+    fn coordinates(&self, index: usize) -> Coordinates {
+        Coordinates {
+            x: index as i32 % self.width,
+            y: index as i32 / self.width,
+        }
+    }
+
+    fn is_island(&self, index: usize) -> bool {
+        self.cells[index].first().is_some_and(|&u| u >= self.openings)
+    }
+}
+
+/// Board being cleared by the greedy ZiNi search
+struct Simulation<'a> {
+    tile_map: &'a TileMap,
+    units: &'a Units,
+    uncovered: Vec<bool>,
+    flagged: Vec<bool>,
+    solved: Vec<bool>,
+}
+
+impl<'a> Simulation<'a> {
+    fn new(tile_map: &'a TileMap, units: &'a Units) -> Self {
+        let cells = units.cells.len();
+        Self {
+            tile_map,
+            units,
+            uncovered: vec![false; cells],
+            flagged: vec![false; cells],
+            solved: vec![false; units.openings + units.islands],
+        }
+    }
+
+    fn tile(&self, index: usize) -> Tile {
+        self.tile_map.get(self.units.coordinates(index)).copied().unwrap_or(Tile::Empty)
+    }
+
+    fn neighbors(&self, index: usize) -> impl Iterator<Item = usize> + '_ {
+        self.units.coordinates(index).neighbors().filter_map(|c| self.units.index(c))
+    }
+
+    /// Uncovers a safe tile, opening its whole opening if it is empty
+    fn uncover(&mut self, index: usize) {
+        let mut queue = VecDeque::from([index]);
+        while let Some(i) = queue.pop_front() {
+            if self.uncovered[i] {
+                continue;
+            }
+            self.uncovered[i] = true;
+            match self.tile(i) {
+                Tile::Empty => {
+                    for &unit in self.units.cells[i].iter() {
+                        self.solved[unit] = true;
+                    }
+                    let neighbors: Vec<_> = self.neighbors(i).collect();
+                    queue.extend(neighbors);
+                }
+                Tile::BombNeighbor(_) if self.units.is_island(i) => self.solved[self.units.cells[i][0]] = true,
+                _ => (),
+            }
+        }
+    }
+
+    /// Units uncovering the tile would solve
+    fn units_solved_by(&self, index: usize, units: &mut Vec<usize>) {
+        let solves = match self.tile(index) {
+            Tile::Empty => true,
+            Tile::BombNeighbor(_) => self.units.is_island(index),
+            Tile::Bomb(_) => false,
+        };
+        if !self.uncovered[index] && solves {
+            units.extend(self.units.cells[index].iter().filter(|&&u| !self.solved[u]));
+        }
+    }
+
+    /// Clicks saved by uncovering the number at `index` if needed, flagging its bombs and
+    /// chording it, along with the clicks it costs
+    fn chord_premium(&self, index: usize) -> (i32, u32) {
+        let mut units = Vec::new();
+        self.units_solved_by(index, &mut units);
+        let mut cost = 1 + u32::from(!self.uncovered[index]);
+        for neighbor in self.neighbors(index) {
+            match self.tile(neighbor) {
+                Tile::Bomb(n) if !self.flagged[neighbor] => cost += n as u32,
+                Tile::Bomb(_) => (),
+                _ => self.units_solved_by(neighbor, &mut units),
+            }
+        }
+        units.sort_unstable();
+        units.dedup();
+        (units.len() as i32 - cost as i32, cost)
+    }
+
+    fn chord(&mut self, index: usize) {
+        self.uncover(index);
+        let neighbors: Vec<_> = self.neighbors(index).collect();
+        for neighbor in neighbors {
+            match self.tile(neighbor) {
+                Tile::Bomb(_) => self.flagged[neighbor] = true,
+                _ => self.uncover(neighbor),
+            }
+        }
+    }
+
+    /// Repeatedly chords the number saving the most clicks, then left clicks every unit left
+    fn greedy_zini(mut self) -> u32 {
+        let mut clicks = 0;
+        loop {
+            let best = (0..self.uncovered.len())
+                .filter(|&i| matches!(self.tile(i), Tile::BombNeighbor(_)))
+                .map(|i| (i, self.chord_premium(i)))
+                .filter(|(_, (premium, _))| *premium > 0)
+                .max_by_key(|(i, (premium, _))| (*premium, std::cmp::Reverse(*i)));
+            let Some((index, (_, cost))) = best else { break; };
+            clicks += cost;
+            self.chord(index);
+        }
+        clicks + self.solved.iter().filter(|&&s| !s).count() as u32
+    }
+}
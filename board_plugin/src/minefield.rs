@@ -0,0 +1,463 @@
+//! Board rules without any rendering, for frontends and tools running outside of Bevy.
+//!
+//! `Minefield` follows the same rules as the `Board` resource, both going through the
+//! `TileMap` ones: flagged tiles can't be uncovered, empty tiles open their neighbors,
+//! chording needs as many flags around a number as its bomb count, and multi-mine boards
+//! are won by flagging every mine.
+
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::{self, Display, Formatter},
+    str::FromStr,
+};
+
+use bevy::reflect::Reflect;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    components::Coordinates,
+    player::solver,
+    random::hash_coords,
+    resources::{tile::Tile, tilemap::TileMap},
+};
+
+/// Boards tried by `Generation::NoGuess` before settling for one that needs guesses
+const NO_GUESS_ATTEMPTS: i32 = 1000;
+
+/// How mines are placed on a new minefield
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Serialize, Deserialize, Reflect)]
+#[serde(rename_all = "kebab-case")]
+pub enum Generation {
+    /// Mines anywhere, the first move may explode
+    #[default]
+    Random,
+    /// Mines anywhere, and the first empty tile starts uncovered
+    SafeStart,
+    /// Mines are placed again on the first reveal, away from the revealed tile and its neighbors
+    SafeFirstMove,
+    /// Like `SafeFirstMove`, trying layouts until one can be cleared from the first reveal
+    /// without guessing
+    NoGuess,
+}
+
+impl FromStr for Generation {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "random" => Ok(Self::Random),
+            "safe-start" => Ok(Self::SafeStart),
+            "safe-first-move" => Ok(Self::SafeFirstMove),
+            "no-guess" => Ok(Self::NoGuess),
+            _ => Err(format!("unknown generation mode {}, expected random, safe-start, safe-first-move or no-guess", s)),
+        }
+    }
+}
+
+impl Generation {
+    pub const ALL: [Generation; 4] = [Self::Random, Self::SafeStart, Self::SafeFirstMove, Self::NoGuess];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Random => "Random",
+            Self::SafeStart => "Safe start",
+            Self::SafeFirstMove => "Safe first move",
+            Self::NoGuess => "No guessing",
+        }
+    }
+}
+
+/// Parameters of a reproducible minefield
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MinefieldOptions {
+    pub map_size: (u16, u16),
+    pub bomb_count: u16,
+    pub max_bombs_per_tile: u8,
+    pub seed: u64,
+    pub generation: Generation,
+}
+
+/// A player action on a tile.
+///
+/// Written as a letter and the tile coordinates, `x` from the left and `y` from the
+/// bottom: `r 3 4` reveals, `f 5 6` cycles flags and `c 2 2` chords
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Move {
+    Reveal(Coordinates),
+    Flag(Coordinates),
+    Chord(Coordinates),
+}
+
+impl Move {
+    pub fn coordinates(&self) -> Coordinates {
+        match self {
+            Self::Reveal(c) | Self::Flag(c) | Self::Chord(c) => *c,
+        }
+    }
+}
+
+impl FromStr for Move {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parts: Vec<_> = s.split_whitespace().collect();
+        let [action, x, y] = parts[..] else {
+            return Err(format!("expected a move like `r 3 4`, got `{}`", s.trim()));
+        };
+        let coordinate = |v: &str| v.parse::<i32>().map_err(|_| format!("invalid coordinate `{}`", v));
+        let coords = Coordinates { x: coordinate(x)?, y: coordinate(y)? };
+        match action {
+            "r" => Ok(Self::Reveal(coords)),
+            "f" => Ok(Self::Flag(coords)),
+            "c" => Ok(Self::Chord(coords)),
+            _ => Err(format!("unknown action `{}`, expected r, f or c", action)),
+        }
+    }
+}
+
+impl Display for Move {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let (action, coords) = match self {
+            Self::Reveal(c) => ('r', c),
+            Self::Flag(c) => ('f', c),
+            Self::Chord(c) => ('c', c),
+        };
+        write!(f, "{} {} {}", action, coords.x, coords.y)
+    }
+}
+
+/// State of a game on a `Minefield`
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum GameStatus {
+    Playing,
+    Won,
+    Lost,
+}
+
+/// What a player can see of a tile
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Cell {
+    Covered,
+    /// Covered tile with the given flag count
+    Flagged(u8),
+    Uncovered(Tile),
+}
+
+/// A tile map being played
+#[derive(Debug, Clone)]
+pub struct Minefield {
+    tile_map: TileMap,
+    uncovered: HashSet<Coordinates>,
+    /// Flag count on each marked tile
+    flags: HashMap<Coordinates, u8>,
+    status: GameStatus,
+    exploded: Option<Coordinates>,
+    /// How to place the mines again on the first reveal, see `Generation::SafeFirstMove`
+    first_move: Option<(Generation, u64)>,
+}
+
+impl Minefield {
+    pub fn new(tile_map: TileMap) -> Self {
+        Self {
+            tile_map,
+            uncovered: HashSet::new(),
+            flags: HashMap::new(),
+            status: GameStatus::Playing,
+            exploded: None,
+            first_move: None,
+        }
+    }
+
+    /// Generates a minefield from a seed. The same options always give the same game
+    pub fn generate(options: &MinefieldOptions) -> Self {
+        let (width, height) = options.map_size;
+        let mut tile_map = TileMap::empty(width.max(1), height.max(1));
+        tile_map.set_bombs_seeded(options.bomb_count, options.max_bombs_per_tile, options.seed, None);
+        let mut field = Self::new(tile_map);
+        match options.generation {
+            Generation::Random => (),
+            Generation::SafeStart => {
+                if let Some(start) = field.safe_start() {
+                    field.reveal(start);
+                }
+            }
+            Generation::SafeFirstMove | Generation::NoGuess => field.first_move = Some((options.generation, options.seed)),
+        }
+        field
+    }
+
+    pub fn tile_map(&self) -> &TileMap {
+        &self.tile_map
+    }
+
+    pub fn width(&self) -> u16 {
+        self.tile_map.width()
+    }
+
+    pub fn height(&self) -> u16 {
+        self.tile_map.height()
+    }
+
+    pub fn status(&self) -> GameStatus {
+        self.status
+    }
+
+    /// Bomb uncovered by the player, on a lost game
+    pub fn exploded(&self) -> Option<Coordinates> {
+        self.exploded
+    }
+
+    pub fn in_bounds(&self, coords: Coordinates) -> bool {
+        self.tile_map.get(coords).is_some()
+    }
+
+    /// Tile as seen by the player, `None` outside of the map
+    pub fn cell(&self, coords: Coordinates) -> Option<Cell> {
+        let tile = *self.tile_map.get(coords)?;
+        Some(if self.uncovered.contains(&coords) {
+            Cell::Uncovered(tile)
+        } else {
+            match self.flags.get(&coords) {
+                Some(&n) => Cell::Flagged(n),
+                None => Cell::Covered,
+            }
+        })
+    }
+
+    pub fn is_uncovered(&self, coords: Coordinates) -> bool {
+        self.uncovered.contains(&coords)
+    }
+
+    pub fn flag_count(&self) -> u32 {
+        self.flags.values().map(|&n| n as u32).sum()
+    }
+
+    /// Marked tiles with their flag count
+    pub fn flags(&self) -> impl Iterator<Item = (Coordinates, u8)> + '_ {
+        self.flags.iter().map(|(&coords, &n)| (coords, n))
+    }
+
+    pub fn uncovered_count(&self) -> usize {
+        self.uncovered.len()
+    }
+
+    /// First empty tile of the map, which the board uncovers itself on a safe start
+    pub fn safe_start(&self) -> Option<Coordinates> {
+        self.tile_map.iter().enumerate().find_map(|(y, line)| {
+            let x = line.iter().position(|t| *t == Tile::Empty)?;
+            Some(Coordinates { x: x as i32, y: y as i32 })
+        })
+    }
+
+    /// Applies a move. Returns whether anything changed
+    pub fn play(&mut self, action: Move) -> bool {
+        match action {
+            Move::Reveal(coords) => self.reveal(coords),
+            Move::Flag(coords) => self.cycle_flag(coords).is_some(),
+            Move::Chord(coords) => self.chord(coords),
+        }
+    }
+
+    /// Uncovers a covered, unflagged tile. Returns whether anything changed
+    pub fn reveal(&mut self, coords: Coordinates) -> bool {
+        if !self.is_revealable(coords) {
+            return false;
+        }
+        if let Some((generation, seed)) = self.first_move.take() {
+            self.tile_map = match generation {
+                Generation::NoGuess => self.no_guess_layout(seed, coords),
+                _ => self.layout_around(seed, coords),
+            };
+        }
+        self.uncover_from([coords]);
+        true
+    }
+
+    /// New mine layout with the same parameters, keeping `safe` and its neighbors clear
+    fn layout_around(&self, seed: u64, safe: Coordinates) -> TileMap {
+        let mut tile_map = TileMap::empty(self.width(), self.height());
+        tile_map.set_bombs_seeded(self.tile_map.bomb_count(), self.tile_map.max_bombs_per_tile(), seed, Some(safe));
+        tile_map
+    }
+
+    /// First layout, among `NO_GUESS_ATTEMPTS`, the solver clears from `start` without guessing
+    fn no_guess_layout(&self, seed: u64, start: Coordinates) -> TileMap {
+        let mut tile_map = self.layout_around(seed, start);
+        for attempt in 1..NO_GUESS_ATTEMPTS {
+            let mut candidate = Self::new(tile_map);
+            candidate.reveal(start);
+            if solver::solves_without_guessing(&candidate) {
+                return candidate.tile_map;
+            }
+            tile_map = self.layout_around(hash_coords(seed, attempt, 0), start);
+        }
+        tile_map
+    }
+
+    /// Cycles the flag count of a covered tile, from unmarked up to the maximum mines per
+    /// tile and back. Returns the new flag count
+    pub fn cycle_flag(&mut self, coords: Coordinates) -> Option<u8> {
+        if self.status != GameStatus::Playing || !self.in_bounds(coords) || self.uncovered.contains(&coords) {
+            return None;
+        }
+        let previous = self.flags.get(&coords).copied().unwrap_or(0);
+        let flags = if previous >= self.tile_map.max_bombs_per_tile() { 0 } else { previous + 1 };
+        match flags {
+            0 => self.flags.remove(&coords),
+            n => self.flags.insert(coords, n),
+        };
+        self.update_completion();
+        Some(flags)
+    }
+
+    /// Uncovers the covered neighbors of a number with exactly as many flags around it as
+    /// its bomb count. Returns whether anything changed
+    pub fn chord(&mut self, coords: Coordinates) -> bool {
+        if self.status != GameStatus::Playing || !self.uncovered.contains(&coords) {
+            return false;
+        }
+        let flags = |c| self.flags.get(&c).copied().unwrap_or(0);
+        let targets = self.tile_map.chord_targets(coords, flags, |c| self.is_revealable(c));
+        if targets.is_empty() {
+            return false;
+        }
+        self.uncover_from(targets);
+        true
+    }
+
+    fn is_revealable(&self, coords: Coordinates) -> bool {
+        self.status == GameStatus::Playing
+            && self.in_bounds(coords)
+            && !self.uncovered.contains(&coords)
+            && !self.flags.contains_key(&coords)
+    }
+
+    fn uncover_from(&mut self, start: impl IntoIterator<Item = Coordinates>) {
+        for coords in self.tile_map.uncover_order(start, |c| self.is_revealable(c)) {
+            self.uncovered.insert(coords);
+            if let Some(Tile::Bomb(_)) = self.tile_map.get(coords) {
+                self.status = GameStatus::Lost;
+                self.exploded = Some(coords);
+            }
+        }
+        self.update_completion();
+    }
+
+    fn update_completion(&mut self) {
+        if self.status == GameStatus::Playing && self.is_complete() {
+            self.status = GameStatus::Won;
+        }
+    }
+
+    fn is_complete(&self) -> bool {
+        let covered = self.tile_map.width() as usize * self.tile_map.height() as usize - self.uncovered.len();
+        let flags = self.flags.iter().map(|(&coords, &n)| (coords, n));
+        self.tile_map.is_cleared(covered, |c| !self.uncovered.contains(&c), flags)
+    }
+}
+
+/// Text view of the board as the player sees it, top row first. Tiles are separated by
+/// spaces: `#` covered, `F` flagged (`F2` for two flags), `.` empty, numbers as is, and
+/// once the game is over `*` for mines (`*2` for two) with `X` for the one that exploded
+impl Display for Minefield {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let over = self.status != GameStatus::Playing;
+        for y in (0..self.height() as i32).rev() {
+            let line: Vec<String> = (0..self.width() as i32)
+                .map(|x| {
+                    let coords = Coordinates { x, y };
+                    let tile = self.tile_map.get(coords).copied().unwrap_or(Tile::Empty);
+                    match self.cell(coords) {
+                        _ if self.exploded == Some(coords) => "X".to_owned(),
+                        Some(Cell::Uncovered(_)) | Some(Cell::Covered) if over && tile.is_bomb() => match tile.bomb_count() {
+                            1 => "*".to_owned(),
+                            n => format!("*{}", n),
+                        },
+                        Some(Cell::Flagged(1)) => "F".to_owned(),
+                        Some(Cell::Flagged(n)) => format!("F{}", n),
+                        Some(Cell::Uncovered(Tile::BombNeighbor(n))) => n.to_string(),
+                        Some(Cell::Uncovered(_)) => ".".to_owned(),
+                        _ => "#".to_owned(),
+                    }
+                })
+                .map(|token| format!("{:>2}", token))
+                .collect();
+            writeln!(f, "{}", line.join(" "))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn c(x: i32, y: i32) -> Coordinates {
+        Coordinates { x, y }
+    }
+
+    /// Minefield of the given size with mines on the given tiles
+    fn field(width: u16, height: u16, bombs: &[(Coordinates, u8)]) -> Minefield {
+        let mut tile_map = TileMap::empty(width, height);
+        tile_map.set_bombs_at(bombs.iter().copied());
+        Minefield::new(tile_map)
+    }
+
+    #[test]
+    fn reveal_opens_empty_tiles() {
+        let mut field = field(4, 4, &[(c(3, 3), 1)]);
+        assert!(field.reveal(c(0, 0)));
+        assert_eq!(field.uncovered_count(), 15);
+        assert_eq!(field.status(), GameStatus::Won);
+        assert!(!field.reveal(c(0, 0)));
+    }
+
+    #[test]
+    fn flags_keep_tiles_covered() {
+        let mut field = field(4, 4, &[(c(3, 3), 1)]);
+        field.cycle_flag(c(0, 3));
+        field.reveal(c(0, 0));
+        assert_eq!(field.cell(c(0, 3)), Some(Cell::Flagged(1)));
+        assert_eq!(field.status(), GameStatus::Playing);
+        assert!(!field.reveal(c(0, 3)));
+    }
+
+    #[test]
+    fn chord_needs_as_many_flags_as_mines() {
+        let mut field = field(3, 3, &[(c(0, 0), 1)]);
+        field.reveal(c(1, 1));
+        assert_eq!(field.uncovered_count(), 1);
+        assert!(!field.chord(c(1, 1)));
+
+        field.cycle_flag(c(0, 0));
+        assert!(field.chord(c(1, 1)));
+        assert_eq!(field.uncovered_count(), 8);
+        assert_eq!(field.status(), GameStatus::Won);
+    }
+
+    #[test]
+    fn chord_on_a_wrong_flag_explodes() {
+        let mut field = field(3, 3, &[(c(0, 0), 1)]);
+        field.reveal(c(1, 1));
+        field.cycle_flag(c(2, 2));
+        assert!(field.chord(c(1, 1)));
+        assert_eq!(field.status(), GameStatus::Lost);
+        assert_eq!(field.exploded(), Some(c(0, 0)));
+    }
+
+    #[test]
+    fn multi_mine_boards_are_won_by_flags() {
+        let mut field = field(3, 3, &[(c(0, 0), 2), (c(2, 2), 1)]);
+        field.cycle_flag(c(0, 0));
+        field.cycle_flag(c(2, 2));
+        assert_eq!(field.status(), GameStatus::Playing);
+
+        field.cycle_flag(c(1, 0));
+        field.cycle_flag(c(0, 0));
+        assert_eq!(field.status(), GameStatus::Playing);
+        field.cycle_flag(c(1, 0));
+        field.cycle_flag(c(1, 0));
+        assert_eq!(field.status(), GameStatus::Won);
+        assert_eq!(field.uncovered_count(), 0);
+    }
+}
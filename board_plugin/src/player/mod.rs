@@ -0,0 +1,225 @@
+//! Automated players, choosing moves from what a human would see of the board
+
+use std::{
+    fmt::{self, Display, Formatter},
+    str::FromStr,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    components::Coordinates,
+    minefield::{Cell, Minefield, Move},
+    resources::tile::Tile,
+};
+
+pub use random::RandomPlayer;
+pub use single_cell::SingleCellPlayer;
+pub use solver::{Analysis, SolverPlayer};
+
+mod random;
+mod single_cell;
+pub mod solver;
+
+/// Visible state of a board: sizes, mine count and every tile as the player sees it
+#[derive(Debug, Clone)]
+pub struct BoardView {
+    width: u16,
+    height: u16,
+    bomb_count: u16,
+    max_bombs_per_tile: u8,
+    cells: Vec<Cell>,
+}
+
+impl BoardView {
+    pub fn new(width: u16, height: u16, bomb_count: u16, max_bombs_per_tile: u8, cell: impl Fn(Coordinates) -> Cell) -> Self {
+        let cells = (0..height as i32)
+            .flat_map(|y| (0..width as i32).map(move |x| Coordinates { x, y }))
+            .map(cell)
+            .collect();
+        Self { width, height, bomb_count, max_bombs_per_tile, cells }
+    }
+
+    pub fn width(&self) -> u16 {
+        self.width
+    }
+
+    pub fn height(&self) -> u16 {
+        self.height
+    }
+
+    pub fn bomb_count(&self) -> u16 {
+        self.bomb_count
+    }
+
+    pub fn max_bombs_per_tile(&self) -> u8 {
+        self.max_bombs_per_tile
+    }
+
+    pub fn cell(&self, coords: Coordinates) -> Option<Cell> {
+        self.index(coords).map(|i| self.cells[i])
+    }
+
+    /// Every tile coordinates, row by row from the bottom
+    pub fn coordinates(&self) -> impl Iterator<Item = Coordinates> + '_ {
+        (0..self.cells.len()).map(|i| self.coordinates_at(i))
+    }
+
+    /// Covered tiles without any flag, the ones that can be revealed
+    pub fn unknown_tiles(&self) -> impl Iterator<Item = Coordinates> + '_ {
+        self.coordinates().filter(|&c| self.cell(c) == Some(Cell::Covered))
+    }
+
+    pub fn flag_count(&self) -> u32 {
+        self.cells.iter()
+            .map(|c| match c {
+                Cell::Flagged(n) => *n as u32,
+                _ => 0,
+            })
+            .sum()
+    }
+
+    /// Number shown by an uncovered tile, 0 for empty tiles
+    pub fn number(&self, coords: Coordinates) -> Option<u8> {
+        match self.cell(coords)? {
+            Cell::Uncovered(Tile::BombNeighbor(n)) => Some(n),
+            Cell::Uncovered(Tile::Empty) => Some(0),
+            _ => None,
+        }
+    }
+
+    /// Chance a random unknown tile holds a mine, from the unflagged mine count alone
+    pub fn mine_density(&self) -> f32 {
+        let unknown = self.unknown_tiles().count();
+        let mines = (self.bomb_count as i64 - self.flag_count() as i64).max(0);
+        if unknown == 0 { 0. } else { (mines as f32 / unknown as f32).min(1.) }
+    }
+
+    pub(crate) fn index(&self, coords: Coordinates) -> Option<usize> {
+        let in_bounds = (0..self.width as i32).contains(&coords.x) && (0..self.height as i32).contains(&coords.y);
+        in_bounds.then(|| coords.y as usize * self.width as usize + coords.x as usize)
+    }
+
+    pub(crate) fn coordinates_at(&self, index: usize) -> Coordinates {
+        Coordinates {
+            x: (index % self.width as usize) as i32,
+            y: (index / self.width as usize) as i32,
+        }
+    }
+}
+
+impl Minefield {
+    /// What the player sees of the minefield
+    pub fn view(&self) -> BoardView {
+        let tile_map = self.tile_map();
+        BoardView::new(
+            tile_map.width(),
+            tile_map.height(),
+            tile_map.bomb_count(),
+            tile_map.max_bombs_per_tile(),
+            |c| self.cell(c).unwrap_or(Cell::Covered),
+        )
+    }
+}
+
+/// A move chosen by a player
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Decision {
+    pub action: Move,
+    /// Estimated chance the move explodes, 0 when the player knows it is safe
+    pub risk: f32,
+}
+
+impl Decision {
+    /// Move known to be safe
+    pub fn safe(action: Move) -> Self {
+        Self { action, risk: 0. }
+    }
+
+    pub fn is_guess(&self) -> bool {
+        self.risk > 0.
+    }
+}
+
+/// Something choosing moves from the visible board state
+pub trait Player: Send + Sync {
+    /// Next move to play, `None` when the player has nothing left to do
+    fn next_move(&mut self, view: &BoardView) -> Option<Decision>;
+}
+
+/// Built-in players
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PlayerKind {
+    /// Reveals random tiles
+    Random,
+    /// Applies the rules of a single number at a time, guessing at random otherwise
+    SingleCell,
+    /// Solves the whole board, guessing the tile least likely to hold a mine when stuck
+    #[default]
+    Solver,
+}
+
+impl PlayerKind {
+    pub const ALL: [PlayerKind; 3] = [Self::Random, Self::SingleCell, Self::Solver];
+
+    /// Creates a player, `seed` drives any random choice it makes
+    pub fn create(&self, seed: u64) -> Box<dyn Player> {
+        match self {
+            Self::Random => Box::new(RandomPlayer::new(seed)),
+            Self::SingleCell => Box::new(SingleCellPlayer::new(seed)),
+            Self::Solver => Box::new(SolverPlayer::default()),
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Random => "random",
+            Self::SingleCell => "single-cell",
+            Self::Solver => "solver",
+        }
+    }
+}
+
+impl Display for PlayerKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(self.label())
+    }
+}
+
+impl FromStr for PlayerKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL.into_iter()
+            .find(|k| k.label() == s)
+            .ok_or_else(|| format!("unknown player {}, expected random, single-cell or solver", s))
+    }
+}
+
+/// Flag moves still to play to bring tiles to their known mine count, one flag at a time
+#[derive(Debug, Default, Clone)]
+struct PendingFlags(Vec<(Coordinates, u8)>);
+
+impl PendingFlags {
+    fn push(&mut self, coords: Coordinates, mines: u8) {
+        if !self.0.iter().any(|(c, _)| *c == coords) {
+            self.0.push((coords, mines));
+        }
+    }
+
+    /// Next flag move, dropping tiles the view shows as done or uncovered
+    fn next(&mut self, view: &BoardView) -> Option<Decision> {
+        while let Some(&(coords, mines)) = self.0.last() {
+            let flags = match view.cell(coords) {
+                Some(Cell::Covered) => 0,
+                Some(Cell::Flagged(n)) => n,
+                _ => mines,
+            };
+            if flags < mines {
+                return Some(Decision::safe(Move::Flag(coords)));
+            }
+            self.0.pop();
+        }
+        None
+    }
+}
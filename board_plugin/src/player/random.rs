@@ -0,0 +1,31 @@
+use crate::{minefield::Move, random::SeededRng};
+
+use super::{BoardView, Decision, Player};
+
+/// Reveals a random covered tile on every move
+#[derive(Debug, Clone)]
+pub struct RandomPlayer {
+    rng: SeededRng,
+}
+
+impl RandomPlayer {
+    pub fn new(seed: u64) -> Self {
+        Self { rng: SeededRng::new(seed) }
+    }
+
+    /// Random unknown tile to reveal, shared with the players guessing at random
+    pub(crate) fn guess(rng: &mut SeededRng, view: &BoardView) -> Option<Decision> {
+        let tiles: Vec<_> = view.unknown_tiles().collect();
+        if tiles.is_empty() {
+            return None;
+        }
+        let coords = tiles[rng.below(tiles.len() as u64) as usize];
+        Some(Decision { action: Move::Reveal(coords), risk: view.mine_density() })
+    }
+}
+
+impl Player for RandomPlayer {
+    fn next_move(&mut self, view: &BoardView) -> Option<Decision> {
+        Self::guess(&mut self.rng, view)
+    }
+}
@@ -0,0 +1,56 @@
+use crate::{
+    minefield::{Cell, Move},
+    random::SeededRng,
+};
+
+use super::{BoardView, Decision, PendingFlags, Player, RandomPlayer};
+
+/// Looks at one number at a time: chords it once all its mines are flagged, flags its
+/// covered neighbors when they must all be mines, and guesses at random otherwise
+#[derive(Debug, Clone)]
+pub struct SingleCellPlayer {
+    rng: SeededRng,
+    pending_flags: PendingFlags,
+}
+
+impl SingleCellPlayer {
+    pub fn new(seed: u64) -> Self {
+        Self { rng: SeededRng::new(seed), pending_flags: PendingFlags::default() }
+    }
+}
+
+impl Player for SingleCellPlayer {
+    fn next_move(&mut self, view: &BoardView) -> Option<Decision> {
+        if let Some(decision) = self.pending_flags.next(view) {
+            return Some(decision);
+        }
+        let max = view.max_bombs_per_tile();
+        for coords in view.coordinates() {
+            let Some(number) = view.number(coords) else { continue; };
+            let mut unknown = Vec::new();
+            let mut flags = 0;
+            for neighbor in coords.neighbors() {
+                match view.cell(neighbor) {
+                    Some(Cell::Covered) => unknown.push(neighbor),
+                    Some(Cell::Flagged(n)) => flags += n as usize,
+                    _ => (),
+                }
+            }
+            if unknown.is_empty() {
+                continue;
+            }
+            let remaining = number as usize;
+            if flags == remaining {
+                return Some(Decision::safe(Move::Chord(coords)));
+            }
+            // Every covered neighbor holds as many mines as a tile can
+            if flags + unknown.len() * max as usize == remaining {
+                for neighbor in unknown {
+                    self.pending_flags.push(neighbor, max);
+                }
+                return self.pending_flags.next(view);
+            }
+        }
+        RandomPlayer::guess(&mut self.rng, view)
+    }
+}
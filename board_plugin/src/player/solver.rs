@@ -0,0 +1,574 @@
+//! Mine probabilities of every covered tile, from all the numbers and the mine count.
+//!
+//! Covered tiles next to a number form the frontier. The frontier is split into groups of
+//! tiles sharing numbers, and every mine arrangement of each group satisfying its numbers is
+//! enumerated. Arrangements are then weighted by the ways the remaining mines fit in the
+//! tiles away from the frontier, so the mine count is taken into account.
+//! Flags are trusted: a flagged tile is assumed to hold exactly as many mines as its flags.
+
+use std::collections::VecDeque;
+
+use crate::{
+    components::Coordinates,
+    minefield::{Cell, GameStatus, Minefield, Move},
+};
+
+use super::{BoardView, Decision, PendingFlags, Player};
+
+/// Search nodes allowed per group before falling back to an estimate
+const NODE_BUDGET: usize = 200_000;
+
+/// Result of the analysis of a board view
+#[derive(Debug, Clone)]
+pub struct Analysis {
+    /// Chance each unknown tile holds at least one mine, `None` for other tiles
+    risks: Vec<Option<f64>>,
+    /// Mine count of unknown tiles holding the same number of mines in every arrangement
+    known_mines: Vec<(Coordinates, u8)>,
+    width: u16,
+    /// Some groups were too large to enumerate and their risks are estimates
+    pub approximate: bool,
+}
+
+impl Analysis {
+    pub fn risk(&self, coords: Coordinates) -> Option<f64> {
+        self.index(coords).and_then(|i| self.risks[i])
+    }
+
+    /// Unknown tiles with no chance of holding a mine
+    pub fn safe_tiles(&self) -> impl Iterator<Item = Coordinates> + '_ {
+        self.unknown().filter(|(_, risk)| *risk <= 0.).map(|(c, _)| c)
+    }
+
+    /// Unknown tiles certain to hold mines, with their mine count
+    pub fn known_mines(&self) -> &[(Coordinates, u8)] {
+        &self.known_mines
+    }
+
+    /// Unknown tile least likely to hold a mine, with its risk
+    pub fn safest_tile(&self) -> Option<(Coordinates, f64)> {
+        self.unknown().min_by(|a, b| a.1.total_cmp(&b.1))
+    }
+
+    fn unknown(&self) -> impl Iterator<Item = (Coordinates, f64)> + '_ {
+        self.risks.iter().enumerate().filter_map(|(i, risk)| {
+            let coords = Coordinates { x: (i % self.width as usize) as i32, y: (i / self.width as usize) as i32 };
+            risk.map(|r| (coords, r))
+        })
+    }
+
+    fn index(&self, coords: Coordinates) -> Option<usize> {
+        let x = usize::try_from(coords.x).ok().filter(|&x| x < self.width as usize)?;
+        let index = usize::try_from(coords.y).ok()? * self.width as usize + x;
+        (index < self.risks.len()).then_some(index)
+    }
+}
+
+/// Number constraint: the unknown tiles around it hold exactly `mines` mines
+struct Constraint {
+    tiles: Vec<usize>,
+    mines: i32,
+}
+
+/// Arrangements of a group of frontier tiles, counted by their total mine count
+struct Group {
+    /// Tiles of the group, as board indices
+    tiles: Vec<usize>,
+    /// Arrangement count for each total mine count
+    counts: Vec<f64>,
+    /// For each tile of the group, arrangements where it holds a mine, for each total mine count
+    mine_counts: Vec<Vec<f64>>,
+    /// Smallest and largest mine count of each tile over all arrangements
+    bounds: Vec<(u8, u8)>,
+}
+
+/// Analyzes a board view, see the module documentation
+pub fn analyze(view: &BoardView) -> Analysis {
+    let max = view.max_bombs_per_tile().max(1);
+    let size = view.width() as usize * view.height() as usize;
+    let unknown: Vec<bool> = (0..size).map(|i| view.cell(view.coordinates_at(i)) == Some(Cell::Covered)).collect();
+
+    let mut constraints = Vec::new();
+    for (i, _) in unknown.iter().enumerate().filter(|(_, u)| !**u) {
+        let coords = view.coordinates_at(i);
+        let Some(number) = view.number(coords) else { continue; };
+        let mut tiles = Vec::new();
+        let mut flags = 0;
+        for neighbor in coords.neighbors() {
+            match view.cell(neighbor) {
+                Some(Cell::Covered) => tiles.extend(view.index(neighbor)),
+                Some(Cell::Flagged(n)) => flags += n as i32,
+                _ => (),
+            }
+        }
+        if !tiles.is_empty() {
+            tiles.sort_unstable();
+            constraints.push(Constraint { tiles, mines: number as i32 - flags });
+        }
+    }
+    // Settling what simple deductions decide keeps the groups left to enumerate small
+    let determined = propagate(&mut constraints, max, size);
+
+    let mut approximate = false;
+    let mut groups = Vec::new();
+    let mut in_group = vec![false; size];
+    for tiles in frontier_groups(size, &constraints) {
+        for &t in tiles.iter() {
+            in_group[t] = true;
+        }
+        match enumerate(&tiles, &constraints, max) {
+            Some(group) => groups.push(group),
+            None => approximate = true,
+        }
+    }
+
+    let interior: Vec<usize> = (0..size).filter(|&i| unknown[i] && !in_group[i] && determined[i].is_none()).collect();
+    let settled: i64 = determined.iter().flatten().map(|&v| v as i64).sum();
+    let remaining = view.bomb_count() as i64 - view.flag_count() as i64 - settled;
+    let ways = InteriorWays::new(interior.len(), max);
+
+    let mut risks = vec![None; size];
+    let mut known_mines = Vec::new();
+    for (tile, value) in determined.iter().enumerate() {
+        match value {
+            Some(0) => risks[tile] = Some(0.),
+            Some(mines) => {
+                risks[tile] = Some(1.);
+                known_mines.push((view.coordinates_at(tile), *mines));
+            }
+            None => (),
+        }
+    }
+    let totals = convolve_all(groups.iter().map(|g| &g.counts));
+    // Weight of each frontier mine count, from the ways the other mines fit in the interior
+    let mut weight = |frontier: usize| ways.ln_ways(remaining - frontier as i64);
+    let mut scale = Scale::new(&totals, &mut weight);
+    if scale.total <= 0. {
+        // Flags or numbers disagree with the mine count, rely on the numbers alone
+        scale = Scale::new(&totals, &mut |_| Some(0.));
+    }
+
+    for (g, group) in groups.iter().enumerate() {
+        let others = convolve_all(groups.iter().enumerate().filter(|(o, _)| *o != g).map(|(_, o)| &o.counts));
+        for (t, &tile) in group.tiles.iter().enumerate() {
+            let mut mine_weight = 0.;
+            for (k, &count) in group.mine_counts[t].iter().enumerate() {
+                if count == 0. {
+                    continue;
+                }
+                for (j, &other) in others.iter().enumerate() {
+                    mine_weight += count * other * scale.factor(k + j);
+                }
+            }
+            let risk = if scale.total > 0. { mine_weight / scale.total } else { 0.5 };
+            risks[tile] = Some(risk);
+            let (low, high) = group.bounds[t];
+            if low == high && low > 0 {
+                known_mines.push((view.coordinates_at(tile), low));
+            }
+        }
+    }
+
+    // Interior tiles all share the same risk
+    let mut interior_weight = 0.;
+    for (k, &count) in totals.iter().enumerate() {
+        let mines = remaining - k as i64;
+        let Some(all) = ways.ln_ways(mines) else { continue; };
+        // No way to leave a given tile empty when every interior tile is needed
+        let empty_share = ways.ln_ways_without_one(mines).map_or(0., |empty| (empty - all).exp());
+        interior_weight += count * scale.factor(k) * (1. - empty_share);
+    }
+    let interior_risk = if scale.total > 0. { interior_weight / scale.total } else { view.mine_density() as f64 };
+    for &tile in interior.iter() {
+        risks[tile] = Some(interior_risk.clamp(0., 1.));
+    }
+
+    // Tiles of groups too large to enumerate get a local estimate
+    for (i, &u) in unknown.iter().enumerate() {
+        if u && risks[i].is_none() {
+            risks[i] = Some(local_estimate(i, &constraints, max));
+        }
+    }
+
+    Analysis { risks, known_mines, width: view.width(), approximate }
+}
+
+/// Can the game be won from its current state by playing only moves the analysis proves safe?
+pub fn solves_without_guessing(field: &Minefield) -> bool {
+    solve_without_guessing(field).status() == GameStatus::Won
+}
+
+/// Plays the moves the analysis proves safe until the game is won or none is left
+pub fn solve_without_guessing(field: &Minefield) -> Minefield {
+    let mut field = field.clone();
+    while field.status() == GameStatus::Playing {
+        let analysis = analyze(&field.view());
+        let safe: Vec<_> = analysis.safe_tiles().collect();
+        if !safe.is_empty() {
+            for coords in safe {
+                field.reveal(coords);
+            }
+            continue;
+        }
+        // Flags only matter on multi-mine boards, which are won by flagging every mine
+        let mut flagged = false;
+        for &(coords, mines) in analysis.known_mines() {
+            while matches!(field.cell(coords), Some(Cell::Covered) | Some(Cell::Flagged(_)))
+                && field.cell(coords) != Some(Cell::Flagged(mines))
+            {
+                field.cycle_flag(coords);
+                flagged = true;
+            }
+        }
+        if !flagged {
+            break;
+        }
+    }
+    field
+}
+
+/// Applies the deductions needing a single number at a time, along with numbers whose
+/// tiles include all the tiles of another. Returns the mine count of the tiles it decided,
+/// which are removed from the constraints
+fn propagate(constraints: &mut Vec<Constraint>, max: u8, size: usize) -> Vec<Option<u8>> {
+    let mut determined = vec![None; size];
+    loop {
+        for Constraint { tiles, mines } in constraints.iter_mut() {
+            tiles.retain(|&t| match determined[t] {
+                Some(value) => {
+                    *mines -= value as i32;
+                    false
+                }
+                None => true,
+            });
+        }
+        constraints.retain(|c| !c.tiles.is_empty());
+
+        let mut changed = false;
+        for constraint in constraints.iter() {
+            let value = match constraint.mines {
+                0 => 0,
+                mines if mines == constraint.tiles.len() as i32 * max as i32 => max,
+                _ => continue,
+            };
+            for &t in constraint.tiles.iter() {
+                if determined[t].is_none() {
+                    determined[t] = Some(value);
+                    changed = true;
+                }
+            }
+        }
+        if !changed && !reduce_subsets(constraints, size) {
+            return determined;
+        }
+    }
+}
+
+/// When the tiles of a number all surround another number too, the other tiles of the
+/// latter hold the difference of their mine counts. Returns whether a constraint changed
+fn reduce_subsets(constraints: &mut [Constraint], size: usize) -> bool {
+    let mut by_tile = vec![Vec::new(); size];
+    for (c, constraint) in constraints.iter().enumerate() {
+        for &t in constraint.tiles.iter() {
+            by_tile[t].push(c);
+        }
+    }
+    let mut changed = false;
+    for a in 0..constraints.len() {
+        let Some(&first) = constraints[a].tiles.first() else { continue; };
+        for &b in by_tile[first].iter() {
+            let (small, large) = (&constraints[a].tiles, &constraints[b].tiles);
+            if a == b || small.len() > large.len() || !small.iter().all(|t| large.binary_search(t).is_ok()) {
+                continue;
+            }
+            let (tiles, mines) = (small.clone(), constraints[a].mines);
+            constraints[b].tiles.retain(|t| tiles.binary_search(t).is_err());
+            constraints[b].mines -= mines;
+            changed = true;
+        }
+    }
+    changed
+}
+
+/// Frontier tiles grouped by the constraints linking them
+fn frontier_groups(size: usize, constraints: &[Constraint]) -> Vec<Vec<usize>> {
+    let mut parent: Vec<usize> = (0..size).collect();
+    fn find(parent: &mut [usize], i: usize) -> usize {
+        let mut root = i;
+        while parent[root] != root {
+            root = parent[root];
+        }
+        parent[i] = root;
+        root
+    }
+    let mut frontier = vec![false; size];
+    for constraint in constraints {
+        let first = constraint.tiles[0];
+        for &t in constraint.tiles.iter() {
+            frontier[t] = true;
+            let (a, b) = (find(&mut parent, first), find(&mut parent, t));
+            parent[a] = b;
+        }
+    }
+    let mut groups: Vec<Vec<usize>> = Vec::new();
+    let mut group_of_root = vec![usize::MAX; size];
+    for t in (0..size).filter(|&t| frontier[t]) {
+        let root = find(&mut parent, t);
+        if group_of_root[root] == usize::MAX {
+            group_of_root[root] = groups.len();
+            groups.push(Vec::new());
+        }
+        groups[group_of_root[root]].push(t);
+    }
+    groups
+}
+
+/// Enumerates the arrangements of a group, `None` when it takes too many search nodes
+fn enumerate(tiles: &[usize], constraints: &[Constraint], max: u8) -> Option<Group> {
+    let group: Vec<&Constraint> = constraints.iter().filter(|c| tiles.contains(&c.tiles[0])).collect();
+    // Visit tiles neighbors first, so numbers get all their tiles assigned early and prune
+    let mut order = Vec::with_capacity(tiles.len());
+    let mut queue = VecDeque::from([tiles[0]]);
+    while let Some(tile) = queue.pop_front() {
+        if order.contains(&tile) {
+            continue;
+        }
+        order.push(tile);
+        for constraint in group.iter().filter(|c| c.tiles.contains(&tile)) {
+            queue.extend(constraint.tiles.iter().filter(|t| !order.contains(t)));
+        }
+    }
+    let tiles = &order[..];
+    let position = |tile: usize| tiles.iter().position(|&t| t == tile);
+    let local: Vec<(Vec<usize>, i32)> = group.iter()
+        .map(|c| (c.tiles.iter().filter_map(|&t| position(t)).collect(), c.mines))
+        .collect();
+    let mut tile_constraints = vec![Vec::new(); tiles.len()];
+    for (c, (members, _)) in local.iter().enumerate() {
+        for &m in members {
+            tile_constraints[m].push(c);
+        }
+    }
+
+    let mut search = Search {
+        constraints: &local,
+        tile_constraints: &tile_constraints,
+        max,
+        values: vec![0; tiles.len()],
+        sums: vec![0; local.len()],
+        unassigned: local.iter().map(|(m, _)| m.len() as i32).collect(),
+        nodes: 0,
+        group: Group {
+            tiles: tiles.to_vec(),
+            counts: Vec::new(),
+            mine_counts: vec![Vec::new(); tiles.len()],
+            bounds: vec![(u8::MAX, 0); tiles.len()],
+        },
+    };
+    search.assign(0).then_some(search.group)
+}
+
+struct Search<'a> {
+    constraints: &'a [(Vec<usize>, i32)],
+    tile_constraints: &'a [Vec<usize>],
+    max: u8,
+    values: Vec<u8>,
+    sums: Vec<i32>,
+    unassigned: Vec<i32>,
+    nodes: usize,
+    group: Group,
+}
+
+impl Search<'_> {
+    /// Tries every value of tile `t` and the following ones. Returns false once out of budget
+    fn assign(&mut self, t: usize) -> bool {
+        self.nodes += 1;
+        if self.nodes > NODE_BUDGET {
+            return false;
+        }
+        if t == self.values.len() {
+            self.record();
+            return true;
+        }
+        for value in 0..=self.max {
+            self.values[t] = value;
+            for &c in self.tile_constraints[t].iter() {
+                self.sums[c] += value as i32;
+                self.unassigned[c] -= 1;
+            }
+            let feasible = self.tile_constraints[t].iter().all(|&c| {
+                let target = self.constraints[c].1;
+                self.sums[c] <= target && self.sums[c] + self.unassigned[c] * self.max as i32 >= target
+            });
+            let within_budget = !feasible || self.assign(t + 1);
+            for &c in self.tile_constraints[t].iter() {
+                self.sums[c] -= value as i32;
+                self.unassigned[c] += 1;
+            }
+            if !within_budget {
+                return false;
+            }
+        }
+        self.values[t] = 0;
+        true
+    }
+
+    fn record(&mut self) {
+        let total: usize = self.values.iter().map(|&v| v as usize).sum();
+        let group = &mut self.group;
+        if group.counts.len() <= total {
+            group.counts.resize(total + 1, 0.);
+        }
+        group.counts[total] += 1.;
+        for (t, &value) in self.values.iter().enumerate() {
+            let (low, high) = &mut group.bounds[t];
+            *low = (*low).min(value);
+            *high = (*high).max(value);
+            if value > 0 {
+                let counts = &mut group.mine_counts[t];
+                if counts.len() <= total {
+                    counts.resize(total + 1, 0.);
+                }
+                counts[total] += 1.;
+            }
+        }
+    }
+}
+
+/// Arrangement counts of several groups together, by total mine count
+fn convolve_all<'a>(counts: impl Iterator<Item = &'a Vec<f64>>) -> Vec<f64> {
+    counts.fold(vec![1.], |total, counts| {
+        let mut result = vec![0.; total.len() + counts.len().max(1) - 1];
+        for (i, &a) in total.iter().enumerate() {
+            for (j, &b) in counts.iter().enumerate() {
+                result[i + j] += a * b;
+            }
+        }
+        result
+    })
+}
+
+/// Relative weight of each frontier mine count, scaled to avoid overflows
+struct Scale {
+    factors: Vec<f64>,
+    total: f64,
+}
+
+impl Scale {
+    fn new(totals: &[f64], ln_weight: &mut impl FnMut(usize) -> Option<f64>) -> Self {
+        let ln: Vec<Option<f64>> = (0..totals.len()).map(&mut *ln_weight).collect();
+        let top = ln.iter().flatten().copied().fold(f64::NEG_INFINITY, f64::max);
+        let factors: Vec<f64> = ln.iter().map(|w| w.map_or(0., |w| (w - top).exp())).collect();
+        let total = totals.iter().zip(factors.iter()).map(|(c, f)| c * f).sum();
+        Self { factors, total }
+    }
+
+    fn factor(&self, frontier_mines: usize) -> f64 {
+        self.factors.get(frontier_mines).copied().unwrap_or(0.)
+    }
+}
+
+/// Ways to place mines in the tiles away from the frontier, as natural logarithms
+struct InteriorWays {
+    tiles: usize,
+    max: u8,
+    /// `ln_factorials[n]` is ln(n!), for classic boards
+    ln_factorials: Vec<f64>,
+    /// Ways to fill `tiles` and `tiles - 1` tiles with each mine count, for multi-mine boards
+    multi: Option<(Vec<f64>, Vec<f64>)>,
+}
+
+impl InteriorWays {
+    fn new(tiles: usize, max: u8) -> Self {
+        let mut ln_factorials = vec![0.; tiles + 1];
+        for n in 1..=tiles {
+            ln_factorials[n] = ln_factorials[n - 1] + (n as f64).ln();
+        }
+        let multi = (max > 1).then(|| {
+            // Coefficients of (1 + x + ... + x^max)^n, built one tile at a time
+            let mut ways = vec![0.];
+            let mut previous = ways.clone();
+            for _ in 0..tiles {
+                previous = ways.clone();
+                let mut next = vec![f64::NEG_INFINITY; ways.len() + max as usize];
+                for (r, &w) in ways.iter().enumerate() {
+                    for v in 0..=max as usize {
+                        next[r + v] = ln_add(next[r + v], w);
+                    }
+                }
+                ways = next;
+            }
+            (ways, previous)
+        });
+        Self { tiles, max, ln_factorials, multi }
+    }
+
+    fn ln_ways(&self, mines: i64) -> Option<f64> {
+        self.ln_ways_in(self.tiles, mines)
+    }
+
+    /// Ways where one given interior tile holds no mine
+    fn ln_ways_without_one(&self, mines: i64) -> Option<f64> {
+        if self.tiles == 0 {
+            return None;
+        }
+        self.ln_ways_in(self.tiles - 1, mines)
+    }
+
+    fn ln_ways_in(&self, tiles: usize, mines: i64) -> Option<f64> {
+        let mines = usize::try_from(mines).ok()?;
+        if mines > tiles * self.max as usize {
+            return None;
+        }
+        match &self.multi {
+            None => Some(self.ln_factorials[tiles] - self.ln_factorials[mines] - self.ln_factorials[tiles - mines]),
+            Some((all, without_one)) => {
+                let ways = if tiles == self.tiles { all } else { without_one };
+                ways.get(mines).copied().filter(|w| w.is_finite())
+            }
+        }
+    }
+}
+
+fn ln_add(a: f64, b: f64) -> f64 {
+    if a == f64::NEG_INFINITY {
+        return b;
+    }
+    let (high, low) = if a > b { (a, b) } else { (b, a) };
+    high + (low - high).exp().ln_1p()
+}
+
+/// Risk of a tile from the numbers around it alone, for groups too large to enumerate
+fn local_estimate(tile: usize, constraints: &[Constraint], max: u8) -> f64 {
+    constraints.iter()
+        .filter(|c| c.tiles.contains(&tile))
+        .map(|c| (c.mines as f64 / (c.tiles.len() * max as usize) as f64).clamp(0., 1.))
+        .fold(0., f64::max)
+}
+
+/// Reveals the tiles the analysis proves safe, flags the ones it proves to be mines, and
+/// otherwise reveals the tile least likely to hold a mine
+#[derive(Debug, Default, Clone)]
+pub struct SolverPlayer {
+    pending_flags: PendingFlags,
+}
+
+impl Player for SolverPlayer {
+    fn next_move(&mut self, view: &BoardView) -> Option<Decision> {
+        if let Some(decision) = self.pending_flags.next(view) {
+            return Some(decision);
+        }
+        let analysis = analyze(view);
+        if let Some(coords) = analysis.safe_tiles().next() {
+            return Some(Decision::safe(Move::Reveal(coords)));
+        }
+        for &(coords, mines) in analysis.known_mines() {
+            self.pending_flags.push(coords, mines);
+        }
+        if let Some(decision) = self.pending_flags.next(view) {
+            return Some(decision);
+        }
+        let (coords, risk) = analysis.safest_tile()?;
+        Some(Decision { action: Move::Reveal(coords), risk: risk as f32 })
+    }
+}
@@ -0,0 +1,270 @@
+//! Handcrafted boards starting with some tiles revealed or flagged, to be cleared without
+//! guessing.
+//!
+//! A puzzle file is a RON `Puzzle`, its layout drawn top row first with one character per
+//! tile: `.` for a covered safe tile, `o` for a revealed one, `*` for a covered mine and `F`
+//! for a flagged mine. Revealing an empty tile opens its neighbors as it does in a game.
+//! `PUZZLE_PACK` holds the puzzles shipped with the game, which `check_puzzle_pack` and the
+//! tests prove can all be cleared by the solver without a single guess.
+
+use std::{collections::HashSet, path::Path};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    components::Coordinates,
+    config::{self, ConfigError},
+    minefield::{GameStatus, Minefield},
+    player::solver,
+    resources::{tile::Tile, tilemap::TileMap},
+};
+
+/// A board layout and the tiles it starts with revealed or flagged
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Puzzle {
+    pub name: String,
+    /// Rows of the layout, top row first, see the module documentation
+    pub rows: Vec<String>,
+}
+
+impl Puzzle {
+    pub fn load(path: &Path) -> Result<Self, ConfigError> {
+        config::load_ron(path)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), ConfigError> {
+        config::save_ron(path, self)
+    }
+
+    pub fn width(&self) -> u16 {
+        self.rows.first().map_or(0, |r| r.chars().count() as u16)
+    }
+
+    pub fn height(&self) -> u16 {
+        self.rows.len() as u16
+    }
+
+    pub fn bomb_count(&self) -> usize {
+        self.rows.iter().flat_map(|r| r.chars()).filter(|c| matches!(c, '*' | 'F')).count()
+    }
+
+    /// Tiles of the puzzle, or why the layout is not a valid puzzle
+    pub fn layout(&self) -> Result<PuzzleLayout, String> {
+        let (width, height) = (self.width(), self.height());
+        if width == 0 {
+            return Err(format!("puzzle {} has no tiles", self.name));
+        }
+        let mut layout = PuzzleLayout::empty(width, height);
+        let mut bombs = Vec::new();
+        for (row, line) in self.rows.iter().enumerate() {
+            if line.chars().count() != width as usize {
+                return Err(format!("row {} of puzzle {} is not {} tiles wide", row + 1, self.name, width));
+            }
+            let y = (height as usize - 1 - row) as i32;
+            for (x, c) in line.chars().enumerate() {
+                let coords = Coordinates { x: x as i32, y };
+                match c {
+                    '.' => (),
+                    'o' => {
+                        layout.revealed.insert(coords);
+                    }
+                    '*' => bombs.push((coords, 1)),
+                    'F' => {
+                        bombs.push((coords, 1));
+                        layout.flagged.insert(coords);
+                    }
+                    _ => return Err(format!("unknown tile `{}` in row {} of puzzle {}", c, row + 1, self.name)),
+                }
+            }
+        }
+        layout.tile_map.set_bombs_at(bombs);
+        Ok(layout)
+    }
+
+    /// Game in the start state of the puzzle, or why the layout is not a valid puzzle
+    pub fn minefield(&self) -> Result<Minefield, String> {
+        self.layout()?.minefield().map_err(|e| format!("puzzle {}: {}", self.name, e))
+    }
+
+    /// What the solver makes of the puzzle
+    pub fn verdict(&self) -> Result<Verdict, String> {
+        Ok(Verdict::of(&self.minefield()?))
+    }
+
+    /// Can the puzzle be cleared from its start state without guessing?
+    pub fn is_no_guess(&self) -> Result<bool, String> {
+        Ok(self.verdict()? == Verdict::NoGuess)
+    }
+}
+
+/// Mines of a puzzle with the tiles it starts with revealed or flagged, as edited
+#[derive(Debug, Clone)]
+pub struct PuzzleLayout {
+    pub tile_map: TileMap,
+    /// Safe tiles uncovered at the start
+    pub revealed: HashSet<Coordinates>,
+    /// Mines flagged at the start
+    pub flagged: HashSet<Coordinates>,
+}
+
+impl PuzzleLayout {
+    pub fn empty(width: u16, height: u16) -> Self {
+        Self {
+            tile_map: TileMap::empty(width.max(1), height.max(1)),
+            revealed: HashSet::new(),
+            flagged: HashSet::new(),
+        }
+    }
+
+    /// Adds or removes a mine, which clears any start mark of the tile. Returns whether
+    /// anything changed
+    pub fn toggle_mine(&mut self, coords: Coordinates) -> bool {
+        let Some(tile) = self.tile_map.get(coords) else { return false; };
+        let was_bomb = tile.is_bomb();
+        let bombs: Vec<_> = self.bombs()
+            .filter(|&c| c != coords)
+            .chain((!was_bomb).then_some(coords))
+            .map(|c| (c, 1))
+            .collect();
+        self.tile_map.set_bombs_at(bombs);
+        self.revealed.remove(&coords);
+        self.flagged.remove(&coords);
+        true
+    }
+
+    /// Toggles the start mark of a tile: revealed for a safe tile, flagged for a mine.
+    /// Returns whether anything changed
+    pub fn toggle_mark(&mut self, coords: Coordinates) -> bool {
+        let Some(tile) = self.tile_map.get(coords) else { return false; };
+        let marks = match tile.is_bomb() {
+            true => &mut self.flagged,
+            false => &mut self.revealed,
+        };
+        if !marks.remove(&coords) {
+            marks.insert(coords);
+        }
+        true
+    }
+
+    /// Tiles holding a mine
+    pub fn bombs(&self) -> impl Iterator<Item = Coordinates> + '_ {
+        self.tile_map.bomb_tiles()
+    }
+
+    /// Layout as a puzzle
+    pub fn puzzle(&self, name: impl Into<String>) -> Puzzle {
+        let rows = (0..self.tile_map.height() as i32).rev()
+            .map(|y| (0..self.tile_map.width() as i32)
+                .map(|x| {
+                    let coords = Coordinates { x, y };
+                    let bomb = self.tile_map.get(coords).is_some_and(Tile::is_bomb);
+                    match (bomb, self.flagged.contains(&coords), self.revealed.contains(&coords)) {
+                        (true, true, _) => 'F',
+                        (true, false, _) => '*',
+                        (false, _, true) => 'o',
+                        (false, _, false) => '.',
+                    }
+                })
+                .collect())
+            .collect();
+        Puzzle { name: name.into(), rows }
+    }
+
+    /// Game in the start state of the layout, or why it is not a valid puzzle
+    pub fn minefield(&self) -> Result<Minefield, String> {
+        let mut field = Minefield::new(self.tile_map.clone());
+        for &coords in &self.flagged {
+            field.cycle_flag(coords);
+        }
+        for &coords in &self.revealed {
+            field.reveal(coords);
+        }
+        match field.status() {
+            GameStatus::Playing => Ok(field),
+            _ => Err("the start state already clears the board".to_owned()),
+        }
+    }
+}
+
+/// What the solver makes of a puzzle
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Verdict {
+    /// Clears without a single guess, so the mines can only be where they are
+    NoGuess,
+    /// Safe tiles the solver can't uncover without guessing
+    NeedsGuess(usize),
+}
+
+impl Verdict {
+    pub fn of(field: &Minefield) -> Self {
+        let solved = solver::solve_without_guessing(field);
+        match solved.status() {
+            GameStatus::Won => Self::NoGuess,
+            _ => {
+                let tile_map = solved.tile_map();
+                let safe = tile_map.width() as usize * tile_map.height() as usize - tile_map.bomb_tile_count();
+                Self::NeedsGuess(safe - solved.uncovered_count())
+            }
+        }
+    }
+}
+
+/// A puzzle shipped with the game
+pub struct PackEntry {
+    /// Stable name the completion of the puzzle is saved under
+    pub id: &'static str,
+    source: &'static str,
+}
+
+impl PackEntry {
+    pub fn puzzle(&self) -> Result<Puzzle, String> {
+        ron::from_str(self.source).map_err(|e| format!("invalid puzzle {}: {}", self.id, e))
+    }
+}
+
+macro_rules! pack_entry {
+    ($id:literal) => {
+        PackEntry { id: $id, source: include_str!(concat!("../puzzles/", $id, ".ron")) }
+    };
+}
+
+/// Puzzles shipped with the game, easiest first
+pub const PUZZLE_PACK: &[PackEntry] = &[
+    pack_entry!("first-steps"),
+    pack_entry!("one-two"),
+    pack_entry!("corner"),
+    pack_entry!("flagged"),
+    pack_entry!("corridor"),
+    pack_entry!("islands"),
+];
+
+/// Checks that every puzzle of the pack is valid and clears without guessing, describing the
+/// first that doesn't. Returns the number of puzzles checked
+pub fn check_puzzle_pack() -> Result<usize, String> {
+    for entry in PUZZLE_PACK {
+        if !entry.puzzle()?.is_no_guess()? {
+            return Err(format!("puzzle {} needs a guess", entry.id));
+        }
+    }
+    Ok(PUZZLE_PACK.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pack_puzzles_clear_without_guessing() {
+        for entry in PUZZLE_PACK {
+            let field = entry.puzzle().and_then(|puzzle| puzzle.minefield()).unwrap_or_else(|e| panic!("{}", e));
+            assert_eq!(field.status(), GameStatus::Playing, "puzzle {} is over from the start", entry.id);
+            assert!(solver::solves_without_guessing(&field), "puzzle {} needs a guess", entry.id);
+        }
+    }
+
+    #[test]
+    fn pack_ids_are_unique() {
+        let ids: HashSet<_> = PUZZLE_PACK.iter().map(|entry| entry.id).collect();
+        assert_eq!(ids.len(), PUZZLE_PACK.len());
+    }
+}
@@ -0,0 +1,56 @@
+/// Deterministic hash of a seed and a pair of coordinates.
+///
+/// Used wherever generation has to be reproducible from a seed alone, without
+/// depending on the `rand` crate's algorithms which may change between versions.
+pub fn hash_coords(seed: u64, x: i32, y: i32) -> u64 {
+    let mut h = splitmix64(seed);
+    h = splitmix64(h ^ (x as u32 as u64));
+    splitmix64(h ^ ((y as u32 as u64) << 32))
+}
+
+/// Deterministic hash of a seed and a string, see `hash_coords`
+pub fn hash_str(seed: u64, s: &str) -> u64 {
+    s.bytes().fold(splitmix64(seed), |h, b| splitmix64(h ^ u64::from(b)))
+}
+
+/// Maps a hash to a float in `[0, 1)`
+pub fn unit_float(hash: u64) -> f32 {
+    // Keep the 24 high bits, which is all the precision an f32 mantissa holds
+    (hash >> 40) as f32 / (1u64 << 24) as f32
+}
+
+/// Small deterministic random generator, stable across versions of the `rand` crate
+#[derive(Debug, Clone)]
+pub struct SeededRng {
+    state: u64,
+}
+
+impl SeededRng {
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        splitmix64(self.state)
+    }
+
+    /// Uniform integer in `[0, bound)`
+    pub fn below(&mut self, bound: u64) -> u64 {
+        // Rejection sampling keeps the distribution uniform for any bound
+        let zone = u64::MAX - u64::MAX % bound;
+        loop {
+            let value = self.next_u64();
+            if value < zone {
+                return value % bound;
+            }
+        }
+    }
+}
+
+fn splitmix64(x: u64) -> u64 {
+    let mut z = x.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
@@ -1,9 +1,19 @@
-use bevy::{window::Window, prelude::{Vec2, Resource, Entity}, utils::HashMap, log};
+use bevy::{prelude::{Vec2, Resource, Entity, Camera, GlobalTransform}, utils::{HashMap, HashSet}};
 
-use crate::{bounds::Bounds2, components::Coordinates};
+use crate::{bounds::Bounds2, components::Coordinates, minefield::Cell, player::BoardView};
 
-use super::tilemap::TileMap;
+use super::{tile::Tile, tilemap::TileMap, BoardChange, MoveHistory};
 
+/// Player mark on a covered tile
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum TileMark {
+    #[default]
+    None,
+    /// Flag count of the tile
+    Flags(u8),
+    /// Question mark, which counts as no flag and doesn't keep the tile from being uncovered
+    Question,
+}
 
 #[derive(Debug, Resource)]
 pub struct Board {
@@ -11,29 +21,44 @@ pub struct Board {
     pub bounds: Bounds2,
     pub tile_size: f32,
     pub entity: Entity,
+    /// Tile entities, covered or not
+    pub tiles: HashMap<Coordinates, Entity>,
     pub covered_tiles: HashMap<Coordinates, Entity>,
-    pub marked_tiles: Vec<Coordinates>,
+    /// Flag count on each marked tile
+    pub marked_tiles: HashMap<Coordinates, u8>,
+    /// Tiles marked with a question mark
+    pub questioned_tiles: HashSet<Coordinates>,
+    pub history: MoveHistory,
 }
 
 impl Board {
 
-    pub fn mouse_position(&self, window: &Window, position: Vec2) -> Option<Coordinates> {
-        let window_size = Vec2::new(window.width(), window.height());
-        let position = position - window_size / 2.;
+    /// Tile under a cursor position, going through the camera so any pan or zoom is accounted for
+    pub fn mouse_position(
+        &self,
+        camera: &Camera,
+        camera_transform: &GlobalTransform,
+        position: Vec2,
+    ) -> Option<Coordinates> {
+        let position = camera.viewport_to_world_2d(camera_transform, position)?;
+        self.world_position(position)
+    }
 
+    /// Coordinates of the tile at the given world position
+    pub fn world_position(&self, position: Vec2) -> Option<Coordinates> {
         if !self.bounds.in_bounds(position) {
             return None;
         }
 
         let coordinates = position - self.bounds.position;
         Some(Coordinates {
-            x: (coordinates.x / self.tile_size) as u16,
-            y: (coordinates.y / self.tile_size) as u16,
+            x: (coordinates.x / self.tile_size) as i32,
+            y: (coordinates.y / self.tile_size) as i32,
         })
     }
 
     pub fn get_covered_tile(&self, coords: &Coordinates) -> Option<&Entity> {
-        if self.marked_tiles.contains(coords) {
+        if self.marked_tiles.contains_key(coords) {
             None
         } else {
             self.covered_tiles.get(coords)
@@ -41,41 +66,112 @@ impl Board {
     }
 
     pub fn try_uncover_tile(&mut self, coords: &Coordinates) -> Option<Entity> {
-        if self.marked_tiles.contains(coords) {
-            self.unmark_tile(coords)?;
-        }
-        self.covered_tiles.remove(coords)
+        let entity = self.covered_tiles.remove(coords)?;
+        let mark = self.mark(coords);
+        self.set_mark(*coords, TileMark::None);
+        self.history.record(BoardChange::Uncovered { coords: *coords, mark });
+        Some(entity)
     }
 
-    pub fn adjacent_covered_tiles(&self, coords: &Coordinates) -> impl Iterator<Item = &Entity> {
-        coords.neighbors().filter_map(|c| self.get_covered_tile(&c))
+    /// Covers an uncovered tile again with a new cover entity, restoring its mark
+    pub fn recover_tile(&mut self, coords: Coordinates, cover: Entity, mark: TileMark) {
+        self.covered_tiles.insert(coords, cover);
+        self.set_mark(coords, mark);
+    }
+
+    pub fn mark(&self, coords: &Coordinates) -> TileMark {
+        match self.marked_tiles.get(coords) {
+            Some(&flags) => TileMark::Flags(flags),
+            None if self.questioned_tiles.contains(coords) => TileMark::Question,
+            None => TileMark::None,
+        }
     }
 
-    fn unmark_tile(&mut self, coords: &Coordinates) -> Option<Coordinates> {
-        let pos = match self.marked_tiles.iter().position(|a| a == coords) {
-            Some(p) => p,
-            None => {
-                log::error!("Failed to unmark tile at {}", coords);
-                return None;
+    /// Sets the mark of a covered tile, without recording a move
+    pub fn set_mark(&mut self, coords: Coordinates, mark: TileMark) {
+        self.marked_tiles.remove(&coords);
+        self.questioned_tiles.remove(&coords);
+        match mark {
+            TileMark::None | TileMark::Flags(0) => (),
+            TileMark::Flags(n) => {
+                self.marked_tiles.insert(coords, n);
             }
-        };
-        Some(self.marked_tiles.remove(pos))
+            TileMark::Question => {
+                self.questioned_tiles.insert(coords);
+            }
+        }
     }
 
-    pub fn try_toggle_mark(&mut self, coords: &Coordinates) -> Option<(Entity, bool)> {
+    /// Cover entities to remove when revealing `start`, see `TileMap::uncover_order`
+    pub fn uncover_targets(&self, start: impl IntoIterator<Item = Coordinates>) -> Vec<Entity> {
+        let order = self.tile_map.uncover_order(start, |c| self.get_covered_tile(&c).is_some());
+        order.iter().filter_map(|c| self.covered_tiles.get(c)).copied().collect()
+    }
+
+    /// Cover entities to remove when chording on `coords`: the tile must be an uncovered
+    /// number with exactly as many flags around it as its bomb count
+    pub fn chord_tiles(&self, coords: &Coordinates) -> Vec<Entity> {
+        if self.covered_tiles.contains_key(coords) {
+            return Vec::new();
+        }
+        let flags = |c| self.marked_tiles.get(&c).copied().unwrap_or(0);
+        let targets = self.tile_map.chord_targets(*coords, flags, |c| self.get_covered_tile(&c).is_some());
+        self.uncover_targets(targets)
+    }
+
+    /// Cycles the mark of a covered tile: flag counts from one up to the maximum mines per
+    /// tile, then a question mark when enabled, then unmarked. Returns the tile cover entity
+    /// and its new mark
+    pub fn try_cycle_mark(&mut self, coords: &Coordinates, question_marks: bool) -> Option<(Entity, TileMark)> {
         let entity = *self.covered_tiles.get(coords)?;
-        let mark = if self.marked_tiles.contains(coords) {
-            self.unmark_tile(coords)?;
-            false
-        } else {
-            self.marked_tiles.push(*coords);
-            true
+        let previous = self.mark(coords);
+        let mark = match previous {
+            TileMark::None => TileMark::Flags(1),
+            TileMark::Flags(n) if n < self.tile_map.max_bombs_per_tile() => TileMark::Flags(n + 1),
+            TileMark::Flags(_) if question_marks => TileMark::Question,
+            _ => TileMark::None,
         };
+        self.set_mark(*coords, mark);
+        self.history.record(BoardChange::Marked { coords: *coords, previous });
         Some((entity, mark))
     }
 
+    /// What the player sees of the board
+    pub fn view(&self) -> BoardView {
+        let tile_map = &self.tile_map;
+        BoardView::new(tile_map.width(), tile_map.height(), tile_map.bomb_count(), tile_map.max_bombs_per_tile(), |c| {
+            match (self.covered_tiles.contains_key(&c), self.marked_tiles.get(&c)) {
+                (true, Some(&flags)) => Cell::Flagged(flags),
+                (true, None) => Cell::Covered,
+                (false, _) => Cell::Uncovered(tile_map.get(c).copied().unwrap_or(Tile::Empty)),
+            }
+        })
+    }
+
     pub fn is_complete(&self) -> bool {
-        self.tile_map.bomb_count() as usize == self.covered_tiles.len()
+        let flags = self.marked_tiles.iter().map(|(&coords, &flags)| (coords, flags));
+        self.tile_map.is_cleared(self.covered_tiles.len(), |c| self.covered_tiles.contains_key(&c), flags)
     }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::systems::testing;
 
+    #[test]
+    fn uncovered_mine_is_not_a_win() {
+        // Mine, 1, empty: uncovering the mine then the number leaves one covered tile, as many
+        // as there are mine tiles, but a safe one
+        let mut tile_map = TileMap::empty(3, 1);
+        tile_map.set_bombs_at([(Coordinates { x: 0, y: 0 }, 1)]);
+        let mut board = testing::board(tile_map, 10.);
+        board.try_uncover_tile(&Coordinates { x: 0, y: 0 });
+        board.try_uncover_tile(&Coordinates { x: 1, y: 0 });
+        assert!(!board.is_complete());
+
+        board.recover_tile(Coordinates { x: 0, y: 0 }, Entity::from_raw(0), TileMark::None);
+        board.try_uncover_tile(&Coordinates { x: 2, y: 0 });
+        assert!(board.is_complete());
+    }
 }
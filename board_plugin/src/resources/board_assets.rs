@@ -1,5 +1,7 @@
 use bevy::{prelude::*, render::texture::DEFAULT_IMAGE_HANDLE};
 
+use crate::components::Themed;
+
 
 #[derive(Debug, Clone)]
 pub struct SpriteMaterial {
@@ -16,8 +18,11 @@ impl Default for SpriteMaterial {
     }
 }
 
+/// Colors, textures and font the board is drawn with, usually from a `theme::Theme` asset.
+/// Must be used as a resource
 #[derive(Debug, Clone, Resource)]
 pub struct BoardAssets {
+    /// Name of the theme
     pub label: String,
     pub board_material: SpriteMaterial,
     pub tile_material: SpriteMaterial,
@@ -26,6 +31,27 @@ pub struct BoardAssets {
     pub bomb_counter_colors: Vec<Color>,
     pub flag_material: SpriteMaterial,
     pub bomb_material: SpriteMaterial,
+    pub wrong_flag_material: SpriteMaterial,
+    pub exploded_material: SpriteMaterial,
+}
+
+/// Plain colors and the default font, until a theme is loaded
+impl Default for BoardAssets {
+    fn default() -> Self {
+        let material = |color: Color| SpriteMaterial { color, ..Default::default() };
+        Self {
+            label: String::new(),
+            board_material: material(Color::WHITE),
+            tile_material: material(Color::DARK_GRAY),
+            covered_tile_material: material(Color::GRAY),
+            bomb_counter_font: Default::default(),
+            bomb_counter_colors: Self::default_colors(),
+            flag_material: material(Color::YELLOW),
+            bomb_material: material(Color::BLACK),
+            wrong_flag_material: material(Color::ORANGE),
+            exploded_material: material(Color::RED),
+        }
+    }
 }
 
 impl BoardAssets {
@@ -36,13 +62,40 @@ impl BoardAssets {
             Color::YELLOW,
             Color::ORANGE,
             Color::PURPLE,
+            Color::RED,
+            Color::CYAN,
+            Color::PINK,
         ]
     }
     pub fn bomb_counter_color(&self, count: u8) -> Color {
         let count = count.saturating_sub(1) as usize;
         match self.bomb_counter_colors.get(count) {
-            None => Color::WHITE,
+            // Multi-mine boards go past any palette, spread the remaining counts over the hue wheel
+            None => Color::hsl((count * 47 % 360) as f32, 0.8, 0.6),
             Some(c) => *c,
         }
     }
+
+    /// Sprite material of a themed sprite, none for texts
+    pub fn material(&self, part: Themed) -> Option<&SpriteMaterial> {
+        match part {
+            Themed::Board => Some(&self.board_material),
+            Themed::Tile => Some(&self.tile_material),
+            Themed::CoveredTile => Some(&self.covered_tile_material),
+            Themed::Flag => Some(&self.flag_material),
+            Themed::WrongFlag => Some(&self.wrong_flag_material),
+            Themed::Bomb => Some(&self.bomb_material),
+            Themed::ExplodedBomb => Some(&self.exploded_material),
+            Themed::Count(_) | Themed::QuestionMark => None,
+        }
+    }
+
+    /// Color of a themed text, none for sprites. Question marks take the flag color
+    pub fn text_color(&self, part: Themed) -> Option<Color> {
+        match part {
+            Themed::Count(count) => Some(self.bomb_counter_color(count)),
+            Themed::QuestionMark => Some(self.flag_material.color),
+            _ => None,
+        }
+    }
 }
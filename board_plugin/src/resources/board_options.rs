@@ -1,6 +1,10 @@
+use std::str::FromStr;
+
 use bevy::{prelude::{Vec3,Resource}, reflect::Reflect};
 use serde::{Deserialize, Serialize};
 
+use crate::minefield::Generation;
+
 /// Tile size options
 #[derive(Debug, Clone, Serialize, Deserialize, Reflect)]
 pub enum TileSize {
@@ -31,6 +35,27 @@ impl Default for BoardPosition {
     }
 }
 
+/// What revealing an uncovered number does
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Serialize, Deserialize, Reflect)]
+pub enum ChordMode {
+    /// Revealing an uncovered number chords it, like the chord action
+    #[default]
+    Reveal,
+    /// Only the chord action chords
+    ChordOnly,
+}
+
+impl ChordMode {
+    pub const ALL: [ChordMode; 2] = [Self::Reveal, Self::ChordOnly];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Reveal => "Reveal or chord action",
+            Self::ChordOnly => "Chord action only",
+        }
+    }
+}
+
 /// Board generation options. Must be used as a resource
 // We use serde to allow saving option presets and loading them at runtime
 #[derive(Resource, Debug, Clone, Serialize, Deserialize, Reflect)]
@@ -39,14 +64,27 @@ pub struct BoardOptions {
     pub map_size: (u16, u16),
     /// bomb count
     pub bomb_count: u16,
+    /// Maximum number of mines a single tile can hold, more than 1 enables the multi-mine variant.
+    /// Boards hold at most `tilemap::MAX_BOMBS_PER_TILE`
+    pub max_bombs_per_tile: u8,
     /// Board world position
     pub position: BoardPosition,
     /// Tile world size
     pub tile_size: TileSize,
     /// Padding between tiles
     pub tile_padding: f32,
-    /// Does the board generate a safe place to start
-    pub safe_start: bool,
+    /// How mines are placed, and what keeps the first move safe if anything
+    pub generation: Generation,
+    /// Does marking a flagged tile again put a question mark on it
+    #[serde(default)]
+    pub question_marks: bool,
+    #[serde(default)]
+    pub chord: ChordMode,
+    /// Practice mode, where moves can be undone
+    pub practice_mode: bool,
+    /// Seed of the mine layout, random when missing
+    #[serde(default)]
+    pub seed: Option<u64>,
 }
 
 impl Default for BoardOptions {
@@ -54,10 +92,86 @@ impl Default for BoardOptions {
         Self {
             map_size: (15, 15),
             bomb_count: 30,
+            max_bombs_per_tile: 1,
             position: Default::default(),
             tile_size: Default::default(),
             tile_padding: 0.,
-            safe_start: false,
+            generation: Generation::Random,
+            question_marks: false,
+            chord: ChordMode::Reveal,
+            practice_mode: false,
+            seed: None,
         }
     }
-}
\ No newline at end of file
+}
+/// Standard difficulty presets
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize, Reflect)]
+pub enum Difficulty {
+    Beginner,
+    Intermediate,
+    Expert,
+}
+
+impl Difficulty {
+    pub const ALL: [Difficulty; 3] = [Self::Beginner, Self::Intermediate, Self::Expert];
+
+    /// Map size and bomb count of the preset
+    pub fn parameters(&self) -> ((u16, u16), u16) {
+        match self {
+            Self::Beginner => ((9, 9), 10),
+            Self::Intermediate => ((16, 16), 40),
+            Self::Expert => ((30, 16), 99),
+        }
+    }
+
+    /// Name of the preset that never changes, which daily seeds are hashed from
+    pub fn tag(&self) -> &'static str {
+        match self {
+            Self::Beginner => "beginner",
+            Self::Intermediate => "intermediate",
+            Self::Expert => "expert",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Beginner => "Beginner",
+            Self::Intermediate => "Intermediate",
+            Self::Expert => "Expert",
+        }
+    }
+}
+
+impl FromStr for Difficulty {
+    type Err = String;
+
+    /// Parses a preset label, ignoring case
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL.into_iter()
+            .find(|d| d.label().eq_ignore_ascii_case(s))
+            .ok_or_else(|| format!("unknown difficulty {}, expected beginner, intermediate or expert", s))
+    }
+}
+
+impl BoardOptions {
+    /// Preset matching the board parameters, if any
+    pub fn difficulty(&self) -> Option<Difficulty> {
+        if self.max_bombs_per_tile > 1 {
+            return None;
+        }
+        Difficulty::ALL.into_iter()
+            .find(|d| d.parameters() == (self.map_size, self.bomb_count))
+    }
+
+    /// Name under which games on this board are grouped, in statistics for instance
+    pub fn difficulty_label(&self) -> String {
+        match self.difficulty() {
+            Some(difficulty) => difficulty.label().to_owned(),
+            None if self.max_bombs_per_tile > 1 => format!(
+                "Custom {}x{} {} (up to {} per tile)",
+                self.map_size.0, self.map_size.1, self.bomb_count, self.max_bombs_per_tile,
+            ),
+            None => format!("Custom {}x{} {}", self.map_size.0, self.map_size.1, self.bomb_count),
+        }
+    }
+}
@@ -0,0 +1,24 @@
+use bevy::prelude::Resource;
+use serde::{Deserialize, Serialize};
+
+use crate::player::PlayerKind;
+
+/// Bot playing the active board on screen. Must be used as a resource
+#[derive(Debug, Clone, Resource, Serialize, Deserialize)]
+pub struct BotOptions {
+    /// Is the bot playing
+    pub enabled: bool,
+    pub player: PlayerKind,
+    /// Time between two bot moves, in seconds
+    pub move_interval: f32,
+}
+
+impl Default for BotOptions {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            player: PlayerKind::Solver,
+            move_interval: 0.25,
+        }
+    }
+}
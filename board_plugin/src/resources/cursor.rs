@@ -0,0 +1,11 @@
+use bevy::prelude::Resource;
+
+use crate::components::Coordinates;
+
+/// Tile selected by the keyboard cursor
+#[derive(Debug, Default, Copy, Clone, Resource)]
+pub struct TileCursor {
+    pub position: Coordinates,
+    /// The cursor is only drawn once it has been used
+    pub visible: bool,
+}
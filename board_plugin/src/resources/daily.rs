@@ -0,0 +1,147 @@
+use std::{collections::BTreeMap, path::{Path, PathBuf}};
+
+use bevy::{log, prelude::Resource};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    config::{self, ConfigError},
+    daily::Date,
+    resources::{tilemap::SEEDED_GENERATOR_VERSION, Difficulty},
+};
+
+/// Daily board being played, its game counting as the official attempt of the day if it is
+/// the first one. Must be used as a resource
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Resource)]
+pub struct DailyChallenge {
+    pub date: Date,
+    pub difficulty: Difficulty,
+    /// First game on this board, the only one recorded
+    pub official: bool,
+}
+
+/// Outcome of an official daily attempt
+#[derive(Debug, Copy, Clone, Serialize, Deserialize)]
+pub struct DailyOutcome {
+    pub won: bool,
+    /// Game time, in seconds
+    pub time: f32,
+}
+
+/// First game played on a daily board
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DailyAttempt {
+    /// Version of the seeded generator the board came from
+    pub generator: u32,
+    /// Missing while the game is played, and for good once it was abandoned
+    pub outcome: Option<DailyOutcome>,
+}
+
+impl DailyAttempt {
+    pub fn won(&self) -> bool {
+        self.outcome.is_some_and(|o| o.won)
+    }
+}
+
+/// Official daily attempts of one difficulty
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DailyHistory {
+    pub attempts: BTreeMap<Date, DailyAttempt>,
+    pub best_streak: u32,
+}
+
+impl DailyHistory {
+    /// Days in a row won up to `today`. A streak still stands on a day not played yet
+    pub fn streak(&self, today: Date) -> u32 {
+        let mut day = match self.attempts.contains_key(&today) {
+            true => today,
+            false => today.previous(),
+        };
+        let mut streak = 0;
+        while self.attempts.get(&day).is_some_and(DailyAttempt::won) {
+            streak += 1;
+            day = day.previous();
+        }
+        streak
+    }
+}
+
+/// Official daily attempts by difficulty, saved after each change. Must be used as a resource
+#[derive(Debug, Default, Clone, Resource, Serialize, Deserialize)]
+pub struct DailyResults {
+    /// Attempts by difficulty tag, the same key as the daily seed
+    pub difficulties: BTreeMap<String, DailyHistory>,
+    #[serde(skip)]
+    path: PathBuf,
+}
+
+impl DailyResults {
+    pub fn default_path() -> PathBuf {
+        config::data_dir().join("daily.ron")
+    }
+
+    /// Loads the results saved at `path`, starting from no results if there are none
+    pub fn load(path: impl Into<PathBuf>) -> Self {
+        let path = path.into();
+        let mut results: Self = match config::load_ron(&path) {
+            Ok(results) => results,
+            Err(ConfigError::Io(e)) if e.kind() == std::io::ErrorKind::NotFound => Self::default(),
+            Err(e) => {
+                log::warn!("Failed to load daily results from {}: {}", path.display(), e);
+                // Keep the unreadable file around rather than overwriting it on the next save
+                if let Err(e) = std::fs::rename(&path, path.with_extension("ron.bak")) {
+                    log::warn!("Failed to back up {}: {}", path.display(), e);
+                }
+                Self::default()
+            }
+        };
+        // Results were first saved under the difficulty label
+        for difficulty in Difficulty::ALL {
+            if let Some(history) = results.difficulties.remove(difficulty.label()) {
+                results.difficulties.entry(difficulty.tag().to_owned()).or_insert(history);
+            }
+        }
+        Self { path, ..results }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn save(&self) -> Result<(), ConfigError> {
+        config::save_ron(&self.path, self)
+    }
+
+    pub fn history(&self, difficulty: Difficulty) -> Option<&DailyHistory> {
+        self.difficulties.get(difficulty.tag())
+    }
+
+    pub fn attempt(&self, difficulty: Difficulty, date: Date) -> Option<&DailyAttempt> {
+        self.history(difficulty)?.attempts.get(&date)
+    }
+
+    /// Days in a row won on `difficulty` up to `today`
+    pub fn streak(&self, difficulty: Difficulty, today: Date) -> u32 {
+        self.history(difficulty).map_or(0, |h| h.streak(today))
+    }
+
+    /// Starts a game on the daily board of `date`. Only the first one is official: returns
+    /// whether this one is. Quitting it before the end still uses the attempt up
+    pub fn start(&mut self, difficulty: Difficulty, date: Date) -> bool {
+        let history = self.difficulties.entry(difficulty.tag().to_owned()).or_default();
+        if history.attempts.contains_key(&date) {
+            return false;
+        }
+        history.attempts.insert(date, DailyAttempt { generator: SEEDED_GENERATOR_VERSION, outcome: None });
+        true
+    }
+
+    /// Records the end of the official attempt on the daily board of `date`
+    pub fn finish(&mut self, difficulty: Difficulty, date: Date, outcome: DailyOutcome) -> bool {
+        let Some(history) = self.difficulties.get_mut(difficulty.tag()) else { return false; };
+        let Some(attempt) = history.attempts.get_mut(&date).filter(|a| a.outcome.is_none()) else { return false; };
+        attempt.outcome = Some(outcome);
+        history.best_streak = history.best_streak.max(history.streak(date));
+        true
+    }
+}
@@ -0,0 +1,117 @@
+use std::path::{Path, PathBuf};
+
+use bevy::{log, prelude::*};
+
+use crate::{
+    bounds::Bounds2,
+    components::Coordinates,
+    config::{self, ConfigError},
+    puzzle::{Puzzle, PuzzleLayout, Verdict},
+};
+
+/// Puzzle being edited. It outlives the editor board, so a test game can go back to it.
+/// Must be used as a resource
+#[derive(Debug, Resource)]
+pub struct BoardEditor {
+    pub name: String,
+    pub layout: PuzzleLayout,
+    /// File the puzzle is saved to
+    path: PathBuf,
+    /// Solver verdict on the layout, or why it is not a valid puzzle, once checked
+    pub verdict: Option<Result<Verdict, String>>,
+    /// Outcome of the last save, until the next edit
+    pub message: Option<String>,
+}
+
+impl BoardEditor {
+    pub fn default_path() -> PathBuf {
+        config::data_dir().join("puzzles").join("custom.ron")
+    }
+
+    /// Edits the puzzle saved at `path`, or a new empty one of the given size if there is none
+    pub fn load(path: impl Into<PathBuf>, (width, height): (u16, u16)) -> Self {
+        let path = path.into();
+        let empty = |name: &str| (name.to_owned(), PuzzleLayout::empty(width, height));
+        let (name, layout) = match Puzzle::load(&path) {
+            Ok(puzzle) => match puzzle.layout() {
+                Ok(layout) => (puzzle.name, layout),
+                Err(e) => {
+                    log::warn!("Failed to load {}: {}", path.display(), e);
+                    empty(&puzzle.name)
+                }
+            },
+            Err(ConfigError::Io(e)) if e.kind() == std::io::ErrorKind::NotFound => empty("Custom puzzle"),
+            Err(e) => {
+                log::warn!("Failed to load {}: {}", path.display(), e);
+                empty("Custom puzzle")
+            }
+        };
+        Self { name, layout, path, verdict: None, message: None }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn puzzle(&self) -> Puzzle {
+        self.layout.puzzle(&self.name)
+    }
+
+    /// Adds or removes a mine, see `PuzzleLayout::toggle_mine`
+    pub fn toggle_mine(&mut self, coords: Coordinates) {
+        if self.layout.toggle_mine(coords) {
+            self.edited();
+        }
+    }
+
+    /// Toggles the start mark of a tile, see `PuzzleLayout::toggle_mark`
+    pub fn toggle_mark(&mut self, coords: Coordinates) {
+        if self.layout.toggle_mark(coords) {
+            self.edited();
+        }
+    }
+
+    /// Forgets the verdict and the last message, both about the layout before this edit
+    fn edited(&mut self) {
+        self.verdict = None;
+        self.message = None;
+    }
+
+    /// Runs the solver on the layout
+    pub fn check(&mut self) -> &Result<Verdict, String> {
+        self.verdict.insert(self.layout.minefield().map(|field| Verdict::of(&field)))
+    }
+
+    pub fn save(&mut self) -> Result<(), ConfigError> {
+        let result = self.puzzle().save(&self.path);
+        self.message = Some(match &result {
+            Ok(()) => format!("Saved to {}", self.path.display()),
+            Err(e) => format!("Failed to save {}: {}", self.path.display(), e),
+        });
+        result
+    }
+}
+
+/// Editor board spawned for the `BoardEditor`, while the editor is open. Must be used as a
+/// resource
+#[derive(Debug, Resource)]
+pub struct EditorBoard {
+    pub bounds: Bounds2,
+    pub tile_size: f32,
+    pub tile_padding: f32,
+    pub entity: Entity,
+}
+
+impl EditorBoard {
+    /// Coordinates of the tile at the given world position
+    pub fn world_position(&self, position: Vec2) -> Option<Coordinates> {
+        if !self.bounds.in_bounds(position) {
+            return None;
+        }
+        let coordinates = position - self.bounds.position;
+        Some(Coordinates {
+            x: (coordinates.x / self.tile_size) as i32,
+            y: (coordinates.y / self.tile_size) as i32,
+        })
+    }
+}
@@ -0,0 +1,215 @@
+use bevy::{prelude::*, utils::HashMap, log};
+use serde::{Deserialize, Serialize};
+
+use crate::{components::Coordinates, random};
+
+use super::tile::Tile;
+
+/// Side length, in tiles, of an endless board chunk
+pub const CHUNK_SIZE: i32 = 16;
+
+// Below this density empty areas percolate, and a single reveal could cascade forever
+const MIN_BOMB_DENSITY: f32 = 0.12;
+const MAX_BOMB_DENSITY: f32 = 0.5;
+// Hard limit on a single cascade, as a last resort against unlucky seeds
+const MAX_CASCADE: usize = 1 << 16;
+
+/// Endless board generation options. Must be used as a resource
+#[derive(Resource, Debug, Clone, Serialize, Deserialize, Reflect)]
+pub struct EndlessOptions {
+    /// Generation seed, a given seed always yields the same board
+    pub seed: u64,
+    /// Probability for a tile to be a bomb
+    pub bomb_density: f32,
+    /// Number of extra chunks kept spawned around the visible area
+    pub chunk_margin: i32,
+}
+
+impl Default for EndlessOptions {
+    fn default() -> Self {
+        Self {
+            seed: rand::random(),
+            bomb_density: 0.18,
+            chunk_margin: 1,
+        }
+    }
+}
+
+/// Player state of a generated chunk
+#[derive(Debug, Clone)]
+struct Chunk {
+    tiles: Vec<Tile>,
+    uncovered: Vec<bool>,
+    flagged: Vec<bool>,
+}
+
+/// Unbounded board, generated chunk by chunk from a seed
+#[derive(Debug, Resource)]
+pub struct EndlessBoard {
+    pub options: EndlessOptions,
+    pub tile_size: f32,
+    pub tile_padding: f32,
+    pub entity: Entity,
+    /// Spawned chunk entities
+    pub spawned_chunks: HashMap<IVec2, Entity>,
+    /// Cover entities of the covered tiles in spawned chunks
+    pub covered_tiles: HashMap<Coordinates, Entity>,
+    /// Number of safe tiles uncovered during this run, used as its score
+    pub cleared: u64,
+    /// Is the run still going?
+    pub alive: bool,
+    chunks: HashMap<IVec2, Chunk>,
+}
+
+impl EndlessBoard {
+    /// Board of the given options. A bomb density out of the playable range is brought back
+    /// into it, with a warning
+    pub fn new(mut options: EndlessOptions, tile_size: f32, tile_padding: f32, entity: Entity) -> Self {
+        let density = options.bomb_density;
+        if !(MIN_BOMB_DENSITY..=MAX_BOMB_DENSITY).contains(&density) {
+            options.bomb_density = match density.is_nan() {
+                true => EndlessOptions::default().bomb_density,
+                false => density.clamp(MIN_BOMB_DENSITY, MAX_BOMB_DENSITY),
+            };
+            log::warn!(
+                "Endless bomb density {} is outside of {}..={}, using {}",
+                density, MIN_BOMB_DENSITY, MAX_BOMB_DENSITY, options.bomb_density,
+            );
+        }
+        Self {
+            options,
+            tile_size,
+            tile_padding,
+            entity,
+            spawned_chunks: HashMap::new(),
+            covered_tiles: HashMap::new(),
+            cleared: 0,
+            alive: true,
+            chunks: HashMap::new(),
+        }
+    }
+
+    /// Chunk containing the given tile
+    pub fn chunk_of(coords: Coordinates) -> IVec2 {
+        IVec2::new(coords.x.div_euclid(CHUNK_SIZE), coords.y.div_euclid(CHUNK_SIZE))
+    }
+
+    /// Coordinates of every tile of a chunk
+    pub fn chunk_tiles(chunk: IVec2) -> impl Iterator<Item = Coordinates> {
+        let origin = chunk * CHUNK_SIZE;
+        (0..CHUNK_SIZE).flat_map(move |y| {
+            (0..CHUNK_SIZE).map(move |x| Coordinates { x: origin.x + x, y: origin.y + y })
+        })
+    }
+
+    fn local_index(coords: Coordinates) -> usize {
+        (coords.y.rem_euclid(CHUNK_SIZE) * CHUNK_SIZE + coords.x.rem_euclid(CHUNK_SIZE)) as usize
+    }
+
+    /// World position of a tile center, relative to the board entity
+    pub fn tile_position(&self, coords: Coordinates) -> Vec2 {
+        Vec2::new(coords.x as f32, coords.y as f32) * self.tile_size
+    }
+
+    /// Tile under a world position
+    pub fn world_to_coordinates(&self, position: Vec2) -> Coordinates {
+        let position = (position / self.tile_size).round();
+        Coordinates { x: position.x as i32, y: position.y as i32 }
+    }
+
+    /// Chunks overlapping the given world area, extended by the chunk margin
+    pub fn chunks_in_area(&self, min: Vec2, max: Vec2) -> (IVec2, IVec2) {
+        let (min, max) = (self.world_to_coordinates(min.min(max)), self.world_to_coordinates(min.max(max)));
+        let margin = IVec2::splat(self.options.chunk_margin);
+        (Self::chunk_of(min) - margin, Self::chunk_of(max) + margin)
+    }
+
+    /// Bomb layout is a pure function of the seed, so chunks can be generated
+    /// in any order and neighbor counts agree across chunk borders
+    fn is_bomb_at(&self, coords: Coordinates) -> bool {
+        // Keep the area around the origin clear for a safe start
+        if coords.x.abs() <= 1 && coords.y.abs() <= 1 {
+            return false;
+        }
+        let hash = random::hash_coords(self.options.seed, coords.x, coords.y);
+        random::unit_float(hash) < self.options.bomb_density
+    }
+
+    fn generate_tile(&self, coords: Coordinates) -> Tile {
+        if self.is_bomb_at(coords) {
+            return Tile::Bomb(1);
+        }
+        match coords.neighbors().filter(|&n| self.is_bomb_at(n)).count() {
+            0 => Tile::Empty,
+            n => Tile::BombNeighbor(n as u8),
+        }
+    }
+
+    fn chunk_mut(&mut self, chunk: IVec2) -> &mut Chunk {
+        if !self.chunks.contains_key(&chunk) {
+            let tiles = Self::chunk_tiles(chunk).map(|c| self.generate_tile(c)).collect();
+            let size = (CHUNK_SIZE * CHUNK_SIZE) as usize;
+            self.chunks.insert(chunk, Chunk {
+                tiles,
+                uncovered: vec![false; size],
+                flagged: vec![false; size],
+            });
+        }
+        self.chunks.get_mut(&chunk).unwrap()
+    }
+
+    /// Tile at the given coordinates, generating its chunk if needed
+    pub fn tile(&mut self, coords: Coordinates) -> Tile {
+        self.chunk_mut(Self::chunk_of(coords)).tiles[Self::local_index(coords)]
+    }
+
+    pub fn is_uncovered(&self, coords: Coordinates) -> bool {
+        self.chunks
+            .get(&Self::chunk_of(coords))
+            .is_some_and(|c| c.uncovered[Self::local_index(coords)])
+    }
+
+    pub fn is_flagged(&self, coords: Coordinates) -> bool {
+        self.chunks
+            .get(&Self::chunk_of(coords))
+            .is_some_and(|c| c.flagged[Self::local_index(coords)])
+    }
+
+    /// Toggles the flag of a covered tile, returns the new flag state
+    pub fn try_toggle_mark(&mut self, coords: Coordinates) -> Option<bool> {
+        let index = Self::local_index(coords);
+        let chunk = self.chunk_mut(Self::chunk_of(coords));
+        if chunk.uncovered[index] {
+            return None;
+        }
+        chunk.flagged[index] = !chunk.flagged[index];
+        Some(chunk.flagged[index])
+    }
+
+    /// Uncovers a tile, cascading through empty tiles. Returns every newly uncovered tile
+    pub fn uncover(&mut self, coords: Coordinates) -> Vec<Coordinates> {
+        let mut uncovered = Vec::new();
+        let mut stack = vec![coords];
+        while let Some(coords) = stack.pop() {
+            if uncovered.len() >= MAX_CASCADE {
+                log::warn!("Cascade stopped after {} tiles", MAX_CASCADE);
+                break;
+            }
+            let index = Self::local_index(coords);
+            let chunk = self.chunk_mut(Self::chunk_of(coords));
+            if chunk.uncovered[index] || chunk.flagged[index] {
+                continue;
+            }
+            chunk.uncovered[index] = true;
+            let tile = chunk.tiles[index];
+            uncovered.push(coords);
+            if !tile.is_bomb() {
+                self.cleared += 1;
+            }
+            if tile == Tile::Empty {
+                stack.extend(coords.neighbors());
+            }
+        }
+        uncovered
+    }
+}
@@ -0,0 +1,107 @@
+use bevy::{
+    prelude::Resource,
+    tasks::{AsyncComputeTaskPool, Task},
+};
+use futures_lite::future;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    components::Coordinates,
+    metrics::{BoardMetrics, GameMetrics},
+    minefield::{Minefield, MinefieldOptions},
+    resources::tilemap::TileMap,
+};
+
+/// Time spent on the current board. Starts with the first move and stops when the game ends
+#[derive(Debug, Default, Copy, Clone, Resource)]
+pub struct GameTimer {
+    /// Elapsed game time, in seconds
+    pub elapsed: f32,
+    pub started: bool,
+    pub finished: bool,
+}
+
+/// Metrics of the current board, computed in the background from its creation as ZiNi is slow
+/// on large boards. Must be used as a resource
+#[derive(Debug, Resource)]
+pub struct BoardMetricsTask {
+    task: Option<Task<BoardMetrics>>,
+    metrics: Option<BoardMetrics>,
+}
+
+impl BoardMetricsTask {
+    pub fn spawn(tile_map: &TileMap) -> Self {
+        let tile_map = tile_map.clone();
+        let task = AsyncComputeTaskPool::get().spawn(async move { BoardMetrics::compute(&tile_map) });
+        Self { task: Some(task), metrics: None }
+    }
+
+    /// Metrics of the board, once computed
+    pub fn poll(&mut self) -> Option<BoardMetrics> {
+        if let Some(metrics) = self.task.as_mut().and_then(|task| future::block_on(future::poll_once(task))) {
+            self.task = None;
+            self.metrics = Some(metrics);
+        }
+        self.metrics
+    }
+}
+
+/// Game whose mines are placed on the first move, played in the background up to that move
+/// as a no-guess layout may take many attempts. Must be used as a resource
+#[derive(Debug, Resource)]
+pub struct FirstMoveTask {
+    task: Task<Minefield>,
+}
+
+impl FirstMoveTask {
+    pub fn spawn(options: MinefieldOptions, coords: Coordinates) -> Self {
+        let task = AsyncComputeTaskPool::get().spawn(async move {
+            let mut field = Minefield::generate(&options);
+            field.reveal(coords);
+            field
+        });
+        Self { task }
+    }
+
+    /// The game after the first move, once its layout is generated
+    pub fn poll(&mut self) -> Option<Minefield> {
+        future::block_on(future::poll_once(&mut self.task))
+    }
+}
+
+/// Tile actions taken during the current game, whatever the input device
+#[derive(Debug, Default, Copy, Clone, Resource, Serialize, Deserialize)]
+pub struct ClickCounts {
+    /// Reveals
+    pub left: u32,
+    /// Flag changes
+    pub right: u32,
+    pub chord: u32,
+    /// Actions among these taken by a bot
+    #[serde(default)]
+    pub bot: u32,
+}
+
+impl ClickCounts {
+    pub fn total(&self) -> u32 {
+        self.left + self.right + self.chord
+    }
+}
+
+/// Outcome of a finished game on a bounded board
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GameResult {
+    pub won: bool,
+    /// Game time, in seconds
+    pub time: f32,
+    /// Difficulty label of the board, see `BoardOptions::difficulty_label`
+    pub difficulty: String,
+    /// Played in practice mode, where moves can be undone
+    pub practice: bool,
+    /// Moves were undone during the game
+    pub undo_used: bool,
+    /// A bot made some of the moves
+    #[serde(default)]
+    pub bot: bool,
+    pub metrics: GameMetrics,
+}
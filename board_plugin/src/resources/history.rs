@@ -0,0 +1,68 @@
+use crate::components::Coordinates;
+
+use super::TileMark;
+
+/// Change made to the board by a move, with what is needed to revert it
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum BoardChange {
+    /// A tile was uncovered, losing its mark if it had any
+    Uncovered { coords: Coordinates, mark: TileMark },
+    /// The mark of a covered tile changed from `previous`
+    Marked { coords: Coordinates, previous: TileMark },
+}
+
+/// Moves played on a board, each being the list of changes it made
+#[derive(Debug, Default, Clone)]
+pub struct MoveHistory {
+    /// Moves can only be undone in practice mode
+    pub undo_enabled: bool,
+    /// Set once a move has been undone, so results can tell
+    pub undo_used: bool,
+    moves: Vec<Vec<BoardChange>>,
+}
+
+impl MoveHistory {
+    pub fn new(undo_enabled: bool) -> Self {
+        Self {
+            undo_enabled,
+            ..Default::default()
+        }
+    }
+
+    /// Starts a new move. Changes are recorded into the last move until the next one
+    /// starts, so cascades spanning several frames stay a single move
+    pub fn begin_move(&mut self) {
+        if !self.moves.last().is_some_and(Vec::is_empty) {
+            self.moves.push(Vec::new());
+        }
+    }
+
+    pub fn record(&mut self, change: BoardChange) {
+        match self.moves.last_mut() {
+            Some(changes) => changes.push(change),
+            None => self.moves.push(vec![change]),
+        }
+    }
+
+    /// Removes the last move, returning its changes in the order they were made
+    pub fn pop_move(&mut self) -> Option<Vec<BoardChange>> {
+        if !self.undo_enabled {
+            return None;
+        }
+        while self.moves.last().is_some_and(Vec::is_empty) {
+            self.moves.pop();
+        }
+        let changes = self.moves.pop()?;
+        self.undo_used = true;
+        Some(changes)
+    }
+
+    /// Number of moves played, ignoring undone ones
+    pub fn len(&self) -> usize {
+        self.moves.iter().filter(|m| !m.is_empty()).count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
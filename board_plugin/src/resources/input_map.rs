@@ -0,0 +1,397 @@
+use std::{collections::BTreeMap, fmt, path::PathBuf};
+
+use bevy::{ecs::system::SystemParam, log, prelude::*};
+use serde::{Deserialize, Serialize};
+
+use crate::config::{self, ConfigError};
+
+/// Abstract action the player can bind physical inputs to
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum InputAction {
+    /// Uncovers a tile, or chords an uncovered number
+    Reveal,
+    Flag,
+    Chord,
+    /// Reverts the last move, in practice mode
+    Undo,
+    CursorUp,
+    CursorDown,
+    CursorLeft,
+    CursorRight,
+    /// Held with a direction, jumps to the board edge
+    CursorJump,
+    /// Moves the cursor to the next covered, unflagged tile
+    NextCovered,
+    /// Held while moving the pointer, drags the camera
+    PanCamera,
+    FitCamera,
+    Restart,
+    /// Starts a new game in practice mode
+    Practice,
+    Endless,
+    Stop,
+    Pause,
+    Controls,
+    Statistics,
+    /// Starts or stops the bot playing the board
+    Bot,
+    /// Starts the daily challenge on the current difficulty
+    Daily,
+    /// Opens the puzzle pack
+    Puzzles,
+    /// Opens or leaves the puzzle editor
+    Editor,
+    /// Runs the solver on the puzzle being edited
+    CheckPuzzle,
+    SavePuzzle,
+}
+
+impl InputAction {
+    pub const ALL: [InputAction; 25] = [
+        Self::Reveal,
+        Self::Flag,
+        Self::Chord,
+        Self::Undo,
+        Self::CursorUp,
+        Self::CursorDown,
+        Self::CursorLeft,
+        Self::CursorRight,
+        Self::CursorJump,
+        Self::NextCovered,
+        Self::PanCamera,
+        Self::FitCamera,
+        Self::Restart,
+        Self::Practice,
+        Self::Endless,
+        Self::Stop,
+        Self::Pause,
+        Self::Controls,
+        Self::Statistics,
+        Self::Bot,
+        Self::Daily,
+        Self::Puzzles,
+        Self::Editor,
+        Self::CheckPuzzle,
+        Self::SavePuzzle,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Reveal => "Reveal",
+            Self::Flag => "Flag",
+            Self::Chord => "Chord",
+            Self::Undo => "Undo",
+            Self::CursorUp => "Cursor up",
+            Self::CursorDown => "Cursor down",
+            Self::CursorLeft => "Cursor left",
+            Self::CursorRight => "Cursor right",
+            Self::CursorJump => "Jump to edge",
+            Self::NextCovered => "Next covered tile",
+            Self::PanCamera => "Pan camera",
+            Self::FitCamera => "Fit camera",
+            Self::Restart => "New game",
+            Self::Practice => "New practice game",
+            Self::Endless => "Endless run",
+            Self::Stop => "Stop game",
+            Self::Pause => "Pause",
+            Self::Controls => "Controls",
+            Self::Statistics => "Statistics",
+            Self::Bot => "Bot",
+            Self::Daily => "Daily challenge",
+            Self::Puzzles => "Puzzles",
+            Self::Editor => "Puzzle editor",
+            Self::CheckPuzzle => "Check puzzle",
+            Self::SavePuzzle => "Save puzzle",
+        }
+    }
+}
+
+/// Keyboard modifier required by a binding. Left and right keys are equivalent
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Modifier {
+    Shift,
+    Control,
+    Alt,
+    Super,
+}
+
+impl Modifier {
+    pub const ALL: [Modifier; 4] = [Self::Shift, Self::Control, Self::Alt, Self::Super];
+
+    pub fn keys(&self) -> [KeyCode; 2] {
+        match self {
+            Self::Shift => [KeyCode::LShift, KeyCode::RShift],
+            Self::Control => [KeyCode::LControl, KeyCode::RControl],
+            Self::Alt => [KeyCode::LAlt, KeyCode::RAlt],
+            Self::Super => [KeyCode::LWin, KeyCode::RWin],
+        }
+    }
+}
+
+/// Physical input a binding listens to
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum PhysicalInput {
+    Key(KeyCode),
+    Mouse(MouseButton),
+    Gamepad(GamepadButtonType),
+}
+
+/// A physical input, with the keyboard modifiers that must be held for it to count
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct InputBinding {
+    pub input: PhysicalInput,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub modifiers: Vec<Modifier>,
+}
+
+impl InputBinding {
+    pub fn new(input: PhysicalInput) -> Self {
+        Self { input, modifiers: Vec::new() }
+    }
+
+    pub fn with(mut self, modifier: Modifier) -> Self {
+        self.modifiers.push(modifier);
+        self
+    }
+}
+
+impl From<PhysicalInput> for InputBinding {
+    fn from(input: PhysicalInput) -> Self {
+        Self::new(input)
+    }
+}
+
+impl From<KeyCode> for InputBinding {
+    fn from(key: KeyCode) -> Self {
+        Self::new(PhysicalInput::Key(key))
+    }
+}
+
+impl From<MouseButton> for InputBinding {
+    fn from(button: MouseButton) -> Self {
+        Self::new(PhysicalInput::Mouse(button))
+    }
+}
+
+impl From<GamepadButtonType> for InputBinding {
+    fn from(button: GamepadButtonType) -> Self {
+        Self::new(PhysicalInput::Gamepad(button))
+    }
+}
+
+impl fmt::Display for InputBinding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for modifier in self.modifiers.iter() {
+            write!(f, "{:?}+", modifier)?;
+        }
+        match self.input {
+            PhysicalInput::Key(key) => write!(f, "{:?}", key),
+            PhysicalInput::Mouse(button) => write!(f, "Mouse {:?}", button),
+            PhysicalInput::Gamepad(button) => write!(f, "Pad {:?}", button),
+        }
+    }
+}
+
+/// Analog stick settings of the tile cursor
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StickOptions {
+    /// Stick deflection below which the stick is considered centered
+    pub dead_zone: f32,
+    /// Time a stick is held before the cursor starts repeating, in seconds
+    pub repeat_delay: f32,
+    /// Time between repeated moves once the stick starts repeating, in seconds
+    pub repeat_interval: f32,
+    /// Shortest time between repeated moves, reached by holding the stick
+    pub min_interval: f32,
+}
+
+impl Default for StickOptions {
+    fn default() -> Self {
+        Self {
+            dead_zone: 0.5,
+            repeat_delay: 0.3,
+            repeat_interval: 0.12,
+            min_interval: 0.02,
+        }
+    }
+}
+
+/// Bindings of every input action. Must be used as a resource
+#[derive(Debug, Clone, Resource, Serialize, Deserialize)]
+#[serde(default)]
+pub struct InputMap {
+    pub bindings: BTreeMap<InputAction, Vec<InputBinding>>,
+    pub stick: StickOptions,
+}
+
+impl Default for InputMap {
+    fn default() -> Self {
+        use InputAction::*;
+        use KeyCode as K;
+        use GamepadButtonType as G;
+
+        let bindings = [
+            (Reveal, vec![K::Space.into(), K::Return.into(), MouseButton::Left.into(), G::South.into()]),
+            (Flag, vec![
+                K::F.into(),
+                K::M.into(),
+                MouseButton::Right.into(),
+                // For one button mice and touchpads
+                InputBinding::from(MouseButton::Left).with(Modifier::Control),
+                G::East.into(),
+            ]),
+            (Chord, vec![K::X.into(), G::West.into()]),
+            (Undo, vec![InputBinding::from(K::Z).with(Modifier::Control), K::Back.into(), G::Select.into()]),
+            (CursorUp, vec![K::Up.into(), K::W.into(), K::K.into(), G::DPadUp.into()]),
+            (CursorDown, vec![K::Down.into(), K::S.into(), K::J.into(), G::DPadDown.into()]),
+            (CursorLeft, vec![K::Left.into(), K::A.into(), K::H.into(), G::DPadLeft.into()]),
+            (CursorRight, vec![K::Right.into(), K::D.into(), K::L.into(), G::DPadRight.into()]),
+            (CursorJump, vec![K::LShift.into(), K::RShift.into(), G::LeftTrigger.into(), G::RightTrigger.into()]),
+            (NextCovered, vec![K::N.into(), K::Tab.into(), G::North.into()]),
+            (PanCamera, vec![MouseButton::Middle.into()]),
+            (FitCamera, vec![K::Home.into()]),
+            (Restart, vec![K::G.into()]),
+            (Practice, vec![K::P.into()]),
+            (Endless, vec![K::E.into()]),
+            (Stop, vec![K::C.into()]),
+            (Pause, vec![K::Escape.into(), G::Start.into()]),
+            (Controls, vec![K::F1.into()]),
+            (Statistics, vec![K::F2.into()]),
+            (Bot, vec![K::B.into()]),
+            (Daily, vec![K::T.into()]),
+            (Puzzles, vec![K::F3.into()]),
+            (Editor, vec![K::F4.into()]),
+            (CheckPuzzle, vec![K::V.into()]),
+            (SavePuzzle, vec![InputBinding::from(K::S).with(Modifier::Control)]),
+        ];
+        Self {
+            bindings: bindings.into_iter().collect(),
+            stick: StickOptions::default(),
+        }
+    }
+}
+
+impl InputMap {
+    pub fn path() -> PathBuf {
+        config::config_dir().join("input.ron")
+    }
+
+    /// Loads the bindings from the config file, falling back to the default bindings
+    pub fn load() -> Self {
+        match config::load_ron_fields::<Self>(&Self::path()) {
+            Ok((mut map, skipped)) => {
+                for reason in skipped {
+                    log::warn!("Ignoring {} in {}", reason, Self::path().display());
+                }
+                // Actions added since the file was saved get their default bindings
+                for (action, bindings) in Self::default().bindings {
+                    map.bindings.entry(action).or_insert(bindings);
+                }
+                map
+            }
+            Err(ConfigError::Io(e)) if e.kind() == std::io::ErrorKind::NotFound => Self::default(),
+            Err(e) => {
+                log::warn!("Failed to load input bindings from {}: {}", Self::path().display(), e);
+                Self::default()
+            }
+        }
+    }
+
+    pub fn save(&self) -> Result<(), ConfigError> {
+        config::save_ron(&Self::path(), self)
+    }
+
+    pub fn bindings(&self, action: InputAction) -> &[InputBinding] {
+        self.bindings.get(&action).map(Vec::as_slice).unwrap_or_default()
+    }
+
+    /// Replaces the bindings of `action` on the device of `binding`, keeping the other devices
+    pub fn rebind(&mut self, action: InputAction, binding: InputBinding) {
+        let same_device = |b: &InputBinding| std::mem::discriminant(&b.input) == std::mem::discriminant(&binding.input);
+        let bindings = self.bindings.entry(action).or_default();
+        bindings.retain(|b| !same_device(b));
+        bindings.push(binding);
+    }
+
+    /// Whether a binding of another action uses the same input with more of the held modifiers,
+    /// in which case the more specific binding wins
+    fn shadowed(&self, binding: &InputBinding, held: &[Modifier]) -> bool {
+        self.bindings.values().flatten().any(|other| {
+            other.input == binding.input
+                && other.modifiers.len() > binding.modifiers.len()
+                && other.modifiers.iter().all(|m| held.contains(m))
+        })
+    }
+}
+
+/// Input state of every device, queried through the input map
+#[derive(SystemParam)]
+pub struct ActionInput<'w> {
+    pub map: Res<'w, InputMap>,
+    keys: Res<'w, Input<KeyCode>>,
+    mouse: Res<'w, Input<MouseButton>>,
+    gamepads: Res<'w, Gamepads>,
+    gamepad_buttons: Res<'w, Input<GamepadButton>>,
+}
+
+impl ActionInput<'_> {
+    fn held_modifiers(&self) -> Vec<Modifier> {
+        Modifier::ALL.into_iter()
+            .filter(|m| self.keys.any_pressed(m.keys()))
+            .collect()
+    }
+
+    fn check(&self, action: InputAction, input: impl Fn(&PhysicalInput) -> bool) -> bool {
+        let held = self.held_modifiers();
+        self.map.bindings(action).iter().any(|binding| {
+            input(&binding.input)
+                && binding.modifiers.iter().all(|m| held.contains(m))
+                && !self.shadowed(binding, &held)
+        })
+    }
+
+    fn shadowed(&self, binding: &InputBinding, held: &[Modifier]) -> bool {
+        // Gamepad bindings ignore keyboard modifiers
+        !matches!(binding.input, PhysicalInput::Gamepad(_)) && self.map.shadowed(binding, held)
+    }
+
+    pub fn key_just_pressed(&self, action: InputAction) -> bool {
+        self.check(action, |i| matches!(i, PhysicalInput::Key(k) if self.keys.just_pressed(*k)))
+    }
+
+    pub fn key_pressed(&self, action: InputAction) -> bool {
+        self.check(action, |i| matches!(i, PhysicalInput::Key(k) if self.keys.pressed(*k)))
+    }
+
+    pub fn mouse_just_pressed(&self, action: InputAction) -> bool {
+        self.check(action, |i| matches!(i, PhysicalInput::Mouse(b) if self.mouse.just_pressed(*b)))
+    }
+
+    pub fn mouse_pressed(&self, action: InputAction) -> bool {
+        self.check(action, |i| matches!(i, PhysicalInput::Mouse(b) if self.mouse.pressed(*b)))
+    }
+
+    pub fn gamepads(&self) -> impl Iterator<Item = Gamepad> + '_ {
+        self.gamepads.iter()
+    }
+
+    pub fn gamepad_just_pressed(&self, gamepad: Gamepad, action: InputAction) -> bool {
+        self.check(action, |i| matches!(i,
+            PhysicalInput::Gamepad(t) if self.gamepad_buttons.just_pressed(GamepadButton::new(gamepad, *t))
+        ))
+    }
+
+    pub fn gamepad_pressed(&self, gamepad: Gamepad, action: InputAction) -> bool {
+        self.check(action, |i| matches!(i,
+            PhysicalInput::Gamepad(t) if self.gamepad_buttons.pressed(GamepadButton::new(gamepad, *t))
+        ))
+    }
+
+    /// Whether the action was just triggered on any device
+    pub fn just_pressed(&self, action: InputAction) -> bool {
+        self.key_just_pressed(action)
+            || self.mouse_just_pressed(action)
+            || self.gamepads().any(|g| self.gamepad_just_pressed(g, action))
+    }
+}
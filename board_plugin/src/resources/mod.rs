@@ -1,9 +1,33 @@
-pub(crate) mod tile;
-pub(crate) mod tilemap;
+pub mod tile;
+pub mod tilemap;
 mod board_options;
 mod board_assets;
 mod board;
+mod endless;
+mod cursor;
+mod touch;
+mod input_map;
+mod history;
+mod game;
+mod stats;
+mod bot;
+mod daily;
+mod puzzle;
+mod editor;
+mod settings;
 
 pub use board_options::*;
 pub use board_assets::*;
 pub use board::*;
+pub use endless::*;
+pub use cursor::*;
+pub use touch::*;
+pub use input_map::*;
+pub use history::*;
+pub use game::*;
+pub use stats::*;
+pub use bot::*;
+pub use daily::*;
+pub use puzzle::*;
+pub use editor::*;
+pub use settings::*;
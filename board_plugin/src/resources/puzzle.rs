@@ -0,0 +1,84 @@
+use std::{collections::BTreeMap, path::{Path, PathBuf}};
+
+use bevy::{log, prelude::Resource};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    config::{self, ConfigError},
+    minefield::Minefield,
+};
+
+/// Board to spawn in place of a random one, with the tiles uncovered or flagged in the
+/// minefield already so. Must be used as a resource
+#[derive(Debug, Clone, Resource)]
+pub struct BoardStart(pub Minefield);
+
+/// Puzzle of the pack being played, recorded once cleared. Must be used as a resource
+#[derive(Debug, Clone, PartialEq, Eq, Resource)]
+pub struct PuzzleChallenge {
+    /// Id of the puzzle in the pack
+    pub id: String,
+    pub name: String,
+}
+
+/// Puzzle of the pack cleared at least once
+#[derive(Debug, Copy, Clone, Serialize, Deserialize)]
+pub struct PuzzleCompletion {
+    /// Best time, in seconds
+    pub best_time: f32,
+    pub solves: u32,
+}
+
+/// Puzzles of the pack cleared, saved after each change. Must be used as a resource
+#[derive(Debug, Default, Clone, Resource, Serialize, Deserialize)]
+pub struct PuzzleResults {
+    /// Completions by puzzle id
+    #[serde(default)]
+    pub completed: BTreeMap<String, PuzzleCompletion>,
+    #[serde(skip)]
+    path: PathBuf,
+}
+
+impl PuzzleResults {
+    pub fn default_path() -> PathBuf {
+        config::data_dir().join("puzzles.ron")
+    }
+
+    /// Loads the results saved at `path`, starting from no results if there are none
+    pub fn load(path: impl Into<PathBuf>) -> Self {
+        let path = path.into();
+        let results = match config::load_ron(&path) {
+            Ok(results) => results,
+            Err(ConfigError::Io(e)) if e.kind() == std::io::ErrorKind::NotFound => Self::default(),
+            Err(e) => {
+                log::warn!("Failed to load puzzle results from {}: {}", path.display(), e);
+                // Keep the unreadable file around rather than overwriting it on the next save
+                if let Err(e) = std::fs::rename(&path, path.with_extension("ron.bak")) {
+                    log::warn!("Failed to back up {}: {}", path.display(), e);
+                }
+                Self::default()
+            }
+        };
+        Self { path, ..results }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn save(&self) -> Result<(), ConfigError> {
+        config::save_ron(&self.path, self)
+    }
+
+    pub fn completion(&self, id: &str) -> Option<&PuzzleCompletion> {
+        self.completed.get(id)
+    }
+
+    /// Records a clear of the puzzle `id` in `time` seconds
+    pub fn complete(&mut self, id: &str, time: f32) {
+        let completion = self.completed.entry(id.to_owned())
+            .or_insert(PuzzleCompletion { best_time: time, solves: 0 });
+        completion.best_time = completion.best_time.min(time);
+        completion.solves += 1;
+    }
+}
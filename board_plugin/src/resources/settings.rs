@@ -0,0 +1,165 @@
+use std::path::{Path, PathBuf};
+
+use bevy::{log, prelude::Resource};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    config::{self, ConfigError},
+    minefield::Generation,
+};
+
+use super::{tilemap::MAX_BOMBS_PER_TILE, BoardOptions, ChordMode, TileSize};
+
+/// Largest width or height of a board set from the settings
+pub const MAX_SIDE: u16 = 100;
+
+/// Player settings, saved to the config directory and applied at startup. Must be used as a
+/// resource
+#[derive(Debug, Clone, Resource, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    /// Tile map size of a new game
+    pub map_size: (u16, u16),
+    /// Bomb count of a new game
+    pub bomb_count: u16,
+    pub max_bombs_per_tile: u8,
+    pub generation: Generation,
+    pub tile_size: TileSize,
+    pub tile_padding: f32,
+    pub question_marks: bool,
+    pub chord: ChordMode,
+    /// Name of the board theme
+    pub theme: String,
+    /// Sound effects volume, between 0 and 1
+    pub volume: f32,
+    #[serde(skip)]
+    path: PathBuf,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            map_size: (20, 20),
+            bomb_count: 40,
+            max_bombs_per_tile: 1,
+            generation: Generation::SafeStart,
+            tile_size: TileSize::default(),
+            tile_padding: 2.,
+            question_marks: false,
+            chord: ChordMode::Reveal,
+            theme: "Default".to_owned(),
+            volume: 0.5,
+            path: PathBuf::new(),
+        }
+    }
+}
+
+impl Settings {
+    pub fn default_path() -> PathBuf {
+        config::config_dir().join("settings.ron")
+    }
+
+    /// Loads the settings saved at `path`. Missing or invalid settings keep their default
+    pub fn load(path: impl Into<PathBuf>) -> Self {
+        let path = path.into();
+        let settings = match config::load_ron_fields::<Self>(&path) {
+            Ok((settings, skipped)) => {
+                for reason in skipped {
+                    log::warn!("Ignoring {} in {}", reason, path.display());
+                }
+                settings.checked(&path)
+            }
+            Err(ConfigError::Io(e)) if e.kind() == std::io::ErrorKind::NotFound => Self::default(),
+            Err(e) => {
+                log::warn!("Failed to load settings from {}: {}", path.display(), e);
+                Self::default()
+            }
+        };
+        Self { path, ..settings }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn save(&self) -> Result<(), ConfigError> {
+        config::save_ron(&self.path, self)
+    }
+
+    /// Goes back to the default settings, still saved to the same file
+    pub fn reset(&mut self) {
+        *self = Self { path: std::mem::take(&mut self.path), ..Self::default() };
+    }
+
+    /// Largest bomb count the map can hold, leaving a tile free
+    pub fn max_bomb_count(&self) -> u16 {
+        let tiles = self.map_size.0 as u32 * self.map_size.1 as u32 * self.max_bombs_per_tile as u32;
+        tiles.saturating_sub(1).clamp(1, u16::MAX as u32) as u16
+    }
+
+    /// Settings read from a file, with the values out of range back to their default
+    fn checked(mut self, path: &Path) -> Self {
+        let defaults = Self::default();
+        let reset = |field: &str| log::warn!("Ignoring out of range `{}` in {}", field, path.display());
+        let sides = 1..=MAX_SIDE;
+        if !sides.contains(&self.map_size.0) || !sides.contains(&self.map_size.1) {
+            reset("map_size");
+            self.map_size = defaults.map_size;
+        }
+        if !(1..=MAX_BOMBS_PER_TILE).contains(&self.max_bombs_per_tile) {
+            reset("max_bombs_per_tile");
+            self.max_bombs_per_tile = defaults.max_bombs_per_tile;
+        }
+        if self.bomb_count == 0 || self.bomb_count > self.max_bomb_count() {
+            reset("bomb_count");
+            self.bomb_count = defaults.bomb_count.min(self.max_bomb_count());
+        }
+        if !(0. ..=1.).contains(&self.volume) {
+            reset("volume");
+            self.volume = defaults.volume;
+        }
+        if self.tile_padding < 0. {
+            reset("tile_padding");
+            self.tile_padding = defaults.tile_padding;
+        }
+        self
+    }
+
+    /// Sets the board of the next game, and the rules applying to the current one
+    pub fn apply(&self, options: &mut BoardOptions) {
+        options.map_size = self.map_size;
+        options.bomb_count = self.bomb_count;
+        options.max_bombs_per_tile = self.max_bombs_per_tile;
+        options.generation = self.generation;
+        options.tile_size = self.tile_size.clone();
+        options.tile_padding = self.tile_padding;
+        options.question_marks = self.question_marks;
+        options.chord = self.chord;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn out_of_range_values_are_reset() {
+        let settings = Settings {
+            map_size: (MAX_SIDE + 1, 10),
+            bomb_count: u16::MAX,
+            max_bombs_per_tile: MAX_BOMBS_PER_TILE + 1,
+            volume: 2.,
+            ..Settings::default()
+        };
+        let checked = settings.checked(Path::new("settings.ron"));
+        let defaults = Settings::default();
+        assert_eq!(checked.map_size, defaults.map_size);
+        assert_eq!(checked.max_bombs_per_tile, defaults.max_bombs_per_tile);
+        assert_eq!(checked.bomb_count, defaults.bomb_count);
+        assert_eq!(checked.volume, defaults.volume);
+
+        let settings = Settings { map_size: (MAX_SIDE, 1), max_bombs_per_tile: MAX_BOMBS_PER_TILE, ..Settings::default() };
+        let checked = settings.clone().checked(Path::new("settings.ron"));
+        assert_eq!((checked.map_size, checked.max_bombs_per_tile), (settings.map_size, settings.max_bombs_per_tile));
+    }
+}
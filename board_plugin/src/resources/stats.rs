@@ -0,0 +1,106 @@
+use std::{collections::BTreeMap, path::{Path, PathBuf}};
+
+use bevy::{log, prelude::Resource};
+use serde::{Deserialize, Serialize};
+
+use crate::config::{self, ConfigError};
+
+use super::GameResult;
+
+/// Number of best times kept per difficulty
+const BEST_TIMES: usize = 10;
+/// Width of the win time distribution buckets, in seconds
+pub const TIME_BUCKET: u32 = 10;
+
+/// Statistics of the games played on one difficulty
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DifficultyStats {
+    pub played: u32,
+    pub won: u32,
+    pub current_streak: u32,
+    pub best_streak: u32,
+    /// Fastest winning times in seconds, fastest first
+    pub best_times: Vec<f32>,
+    /// Number of wins by winning time, keyed by the start of each `TIME_BUCKET` seconds bucket
+    pub time_distribution: BTreeMap<u32, u32>,
+}
+
+impl DifficultyStats {
+    /// Ratio of games won, between 0 and 1
+    pub fn win_rate(&self) -> f32 {
+        match self.played {
+            0 => 0.,
+            played => self.won as f32 / played as f32,
+        }
+    }
+
+    fn record(&mut self, result: &GameResult) {
+        self.played += 1;
+        if !result.won {
+            self.current_streak = 0;
+            return;
+        }
+        self.won += 1;
+        self.current_streak += 1;
+        self.best_streak = self.best_streak.max(self.current_streak);
+
+        let index = self.best_times.partition_point(|&t| t <= result.time);
+        self.best_times.insert(index, result.time);
+        self.best_times.truncate(BEST_TIMES);
+
+        let bucket = result.time as u32 / TIME_BUCKET * TIME_BUCKET;
+        *self.time_distribution.entry(bucket).or_default() += 1;
+    }
+}
+
+/// Statistics of every difficulty played, saved after each game. Must be used as a resource
+#[derive(Debug, Default, Clone, Resource, Serialize, Deserialize)]
+pub struct Stats {
+    /// Statistics by difficulty label
+    pub difficulties: BTreeMap<String, DifficultyStats>,
+    #[serde(skip)]
+    path: PathBuf,
+}
+
+impl Stats {
+    pub fn default_path() -> PathBuf {
+        config::data_dir().join("stats.ron")
+    }
+
+    /// Loads the statistics saved at `path`, starting from empty statistics if there are none
+    pub fn load(path: impl Into<PathBuf>) -> Self {
+        let path = path.into();
+        let stats = match config::load_ron(&path) {
+            Ok(stats) => stats,
+            Err(ConfigError::Io(e)) if e.kind() == std::io::ErrorKind::NotFound => Self::default(),
+            Err(e) => {
+                log::warn!("Failed to load statistics from {}: {}", path.display(), e);
+                // Keep the unreadable file around rather than overwriting it on the next save
+                if let Err(e) = std::fs::rename(&path, path.with_extension("ron.bak")) {
+                    log::warn!("Failed to back up {}: {}", path.display(), e);
+                }
+                Self::default()
+            }
+        };
+        Self { path, ..stats }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn save(&self) -> Result<(), ConfigError> {
+        config::save_ron(&self.path, self)
+    }
+
+    /// Adds a game to the statistics. Practice games are left out, their moves can be undone,
+    /// and so are games a bot took part in
+    pub fn record(&mut self, result: &GameResult) -> bool {
+        if result.practice || result.undo_used || result.bot {
+            return false;
+        }
+        self.difficulties.entry(result.difficulty.clone()).or_default().record(result);
+        true
+    }
+}
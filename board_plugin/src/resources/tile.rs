@@ -1,13 +1,14 @@
 use bevy::reflect::{Reflect, FromReflect};
+use serde::{Deserialize, Serialize};
 #[cfg(feature = "debug")]
 use colored::Colorize;
 
 /// Enum describing a Minesweeper tile
-#[derive(Debug, Copy, Clone, Eq, PartialEq, Reflect, FromReflect)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Reflect, FromReflect, Serialize, Deserialize)]
 pub enum Tile {
-    /// Is a bomb
-    Bomb,
-    /// Is a bomb neighbor
+    /// Is a bomb, holding the given number of mines (always 1 on classic boards)
+    Bomb(u8),
+    /// Is a bomb neighbor, with the total mine count of its neighborhood
     BombNeighbor(u8),
     /// Empty tile
     Empty,
@@ -16,7 +17,15 @@ pub enum Tile {
 impl Tile {
     /// Is the tile a bomb?
     pub const fn is_bomb(&self) -> bool {
-        matches!(self, Self::Bomb)
+        matches!(self, Self::Bomb(_))
+    }
+
+    /// Number of mines held by the tile
+    pub const fn bomb_count(&self) -> u8 {
+        match self {
+            Self::Bomb(n) => *n,
+            _ => 0,
+        }
     }
 
     #[cfg(feature = "debug")]
@@ -24,15 +33,24 @@ impl Tile {
         format!(
             "{}",
             match self {
-                Tile::Bomb => "*".bright_red(),
+                Tile::Bomb(1) => "*".bright_red(),
+                Tile::Bomb(n) => count_char(*n).to_string().bright_red().bold(),
                 Tile::BombNeighbor(v) => match v {
                     1 => "1".cyan(),
                     2 => "2".green(),
                     3 => "3".yellow(),
-                    _ => v.to_string().red(),
+                    _ => count_char(*v).to_string().red(),
                 },
                 Tile::Empty => " ".normal(),
             }
         )
     }
 }
+
+/// Single character representation of a count, so multi-bomb boards stay aligned
+pub fn count_char(count: u8) -> char {
+    match count {
+        0..=9 => (b'0' + count) as char,
+        _ => (b'A' + (count - 10)) as char,
+    }
+}
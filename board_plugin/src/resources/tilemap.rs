@@ -1,13 +1,21 @@
-use crate::{resources::tile::Tile, components::Coordinates};
-use std::ops::{Deref, DerefMut};
+use crate::{resources::tile::Tile, components::Coordinates, random::SeededRng};
+use std::{collections::{HashSet, VecDeque}, ops::Deref};
 use bevy::prelude::*;
 use rand::{thread_rng, Rng};
 
+/// Version of the seeded mine placement of `TileMap::set_bombs_seeded`. Anything changing
+/// the layout of a seed bumps it, along with the golden layouts of `daily`
+pub const SEEDED_GENERATOR_VERSION: u32 = 1;
+/// Most mines a single tile can hold. Eight full neighbors then still fit the `u8` of a number
+pub const MAX_BOMBS_PER_TILE: u8 = 5;
 
 /// Base tile map
 #[derive(Resource, Debug, Clone, Reflect)]
 pub struct TileMap {
     bomb_count: u16,
+    /// Tiles holding at least one mine, kept with the layout
+    bomb_tile_count: usize,
+    max_bombs_per_tile: u8,
     height: u16,
     width: u16,
     map: Vec<Vec<Tile>>,
@@ -17,11 +25,12 @@ impl TileMap {
     /// Generates an empty map
     pub fn empty(width: u16, height: u16) -> Self {
         let map = (0..height)
-            .into_iter()
-            .map(|_| (0..width).into_iter().map(|_| Tile::Empty).collect())
+            .map(|_| (0..width).map(|_| Tile::Empty).collect())
             .collect();
         Self {
             bomb_count: 0,
+            bomb_tile_count: 0,
+            max_bombs_per_tile: 1,
             height,
             width,
             map,
@@ -29,24 +38,90 @@ impl TileMap {
     }
 
     pub fn set_bombs(&mut self, bomb_count: u16) {
+        self.set_bombs_stacked(bomb_count, 1);
+    }
+
+    /// Places `bomb_count` mines, allowing up to `max_per_tile` of them in a single tile,
+    /// at most `MAX_BOMBS_PER_TILE`
+    pub fn set_bombs_stacked(&mut self, bomb_count: u16, max_per_tile: u8) {
+        let mut rng = thread_rng();
+        self.place_bombs(bomb_count, max_per_tile, |_| true, |bound| rng.gen_range(0..bound));
+    }
+
+    /// Places mines like `set_bombs_stacked`, from a seed. The same seed always gives the
+    /// same layout. No mine is placed around `safe`, so a first move there opens an area
+    pub fn set_bombs_seeded(&mut self, bomb_count: u16, max_per_tile: u8, seed: u64, safe: Option<Coordinates>) {
+        let mut rng = SeededRng::new(seed);
+        let allowed = |coords: Coordinates| {
+            safe.is_none_or(|s| (coords.x - s.x).abs() > 1 || (coords.y - s.y).abs() > 1)
+        };
+        self.place_bombs(bomb_count, max_per_tile, allowed, |bound| rng.below(bound as u64) as u16);
+    }
+
+    fn place_bombs(
+        &mut self,
+        bomb_count: u16,
+        max_per_tile: u8,
+        allowed: impl Fn(Coordinates) -> bool,
+        mut random: impl FnMut(u16) -> u16,
+    ) {
+        let max_per_tile = max_per_tile.clamp(1, MAX_BOMBS_PER_TILE);
+        self.max_bombs_per_tile = max_per_tile;
+        let capacity = (0..self.height)
+            .flat_map(|y| (0..self.width).map(move |x| Coordinates { x: x as i32, y: y as i32 }))
+            .filter(|&c| allowed(c))
+            .count() * max_per_tile as usize;
+        // Never loop forever looking for room that does not exist
+        let bomb_count = bomb_count.min(capacity.min(u16::MAX as usize) as u16);
         self.bomb_count = bomb_count;
         let mut remaining_bombs = bomb_count;
-        let mut rng = thread_rng();
         // Place bombs
         while remaining_bombs > 0 {
-            let (x, y) = (
-                rng.gen_range(0..self.width) as usize,
-                rng.gen_range(0..self.height) as usize,
-            );
-            if let Tile::Empty = self[y][x] {
-                self[y][x] = Tile::Bomb;
-                remaining_bombs -= 1;
+            let (x, y) = (random(self.width) as usize, random(self.height) as usize);
+            if !allowed(Coordinates { x: x as i32, y: y as i32 }) {
+                continue;
+            }
+            match self.map[y][x] {
+                Tile::Empty => {
+                    self.map[y][x] = Tile::Bomb(1);
+                    remaining_bombs -= 1;
+                }
+                Tile::Bomb(n) if n < max_per_tile => {
+                    self.map[y][x] = Tile::Bomb(n + 1);
+                    remaining_bombs -= 1;
+                }
+                _ => (),
             }
         }
-        // Place bomb neighbors
+        self.place_neighbors();
+    }
+
+    /// Places mines on the given tiles, with their count up to `MAX_BOMBS_PER_TILE`, in place
+    /// of the current layout
+    pub fn set_bombs_at(&mut self, bombs: impl IntoIterator<Item = (Coordinates, u8)>) {
+        for tile in self.map.iter_mut().flatten() {
+            *tile = Tile::Empty;
+        }
+        for (coords, count) in bombs {
+            if coords.x < 0 || coords.y < 0 || count == 0 {
+                continue;
+            }
+            if let Some(tile) = self.map.get_mut(coords.y as usize).and_then(|l| l.get_mut(coords.x as usize)) {
+                *tile = Tile::Bomb(count.min(MAX_BOMBS_PER_TILE));
+            }
+        }
+        let tiles = self.map.iter().flatten();
+        self.bomb_count = tiles.clone().map(|t| u16::from(t.bomb_count())).sum();
+        self.max_bombs_per_tile = tiles.map(Tile::bomb_count).max().unwrap_or_default().max(1);
+        self.place_neighbors();
+    }
+
+    /// Numbers every tile next to a mine
+    fn place_neighbors(&mut self) {
+        self.bomb_tile_count = self.map.iter().flatten().filter(|t| t.is_bomb()).count();
         for y in 0..self.height {
             for x in 0..self.width {
-                let coords = Coordinates { x, y };
+                let coords = Coordinates { x: x as i32, y: y as i32 };
                 if self.is_bomb_at(coords) {
                     continue;
                 }
@@ -54,23 +129,30 @@ impl TileMap {
                 if num == 0 {
                     continue;
                 }
-                let tile = &mut self[y as usize][x as usize];
+                let tile = &mut self.map[y as usize][x as usize];
                 *tile = Tile::BombNeighbor(num);
             }
         }
     }
 
-    fn is_bomb_at(&self, coords: Coordinates) -> bool {
-        if coords.x >= self.width || coords.y >= self.height {
-            return false;
+    /// Tile at the given coordinates, if they are inside the map
+    pub fn get(&self, coords: Coordinates) -> Option<&Tile> {
+        if coords.x < 0 || coords.y < 0 {
+            return None;
         }
-        self.map[coords.y as usize][coords.x as usize].is_bomb()
+        self.map.get(coords.y as usize)?.get(coords.x as usize)
+    }
+
+    fn is_bomb_at(&self, coords: Coordinates) -> bool {
+        self.get(coords).is_some_and(Tile::is_bomb)
     }
 
     fn bomb_count_at(&self, coords: Coordinates) -> u8 {
-        coords.neighbors()
-            .filter(|&n| self.is_bomb_at(n))
-            .count() as u8
+        let count: u16 = coords.neighbors()
+            .filter_map(|n| self.get(n))
+            .map(|t| u16::from(t.bomb_count()))
+            .sum();
+        count.min(u8::MAX.into()) as u8
     }
 
 
@@ -80,7 +162,7 @@ impl TileMap {
             "Map ({}, {}) with {} bombs:\n",
             self.width, self.height, self.bomb_count
         );
-        let line: String = (0..=(self.width + 1)).into_iter().map(|_| '-').collect();
+        let line: String = (0..=(self.width + 1)).map(|_| '-').collect();
         buffer = format!("{}{}\n", buffer, line);
         for line in self.iter().rev() {
             buffer = format!("{}|", buffer);
@@ -106,6 +188,88 @@ impl TileMap {
     pub fn bomb_count(&self) -> u16 {
         self.bomb_count
     }
+
+    // Getter for `max_bombs_per_tile`
+    pub fn max_bombs_per_tile(&self) -> u8 {
+        self.max_bombs_per_tile
+    }
+
+    /// Does this map allow several mines in a single tile?
+    pub fn is_multi_bomb(&self) -> bool {
+        self.max_bombs_per_tile > 1
+    }
+
+    /// Number of tiles holding at least one mine
+    pub fn bomb_tile_count(&self) -> usize {
+        self.bomb_tile_count
+    }
+
+    /// Coordinates of the tiles holding at least one mine
+    pub fn bomb_tiles(&self) -> impl Iterator<Item = Coordinates> + '_ {
+        self.map.iter().enumerate().flat_map(|(y, line)| {
+            line.iter().enumerate()
+                .filter(|(_, tile)| tile.is_bomb())
+                .map(move |(x, _)| Coordinates { x: x as i32, y: y as i32 })
+        })
+    }
+
+    /// Tiles uncovered by revealing `start`, in order. Empty tiles open their neighbors, and
+    /// the cascade stops at the first mine. `revealable` tells covered tiles without flags
+    pub fn uncover_order(
+        &self,
+        start: impl IntoIterator<Item = Coordinates>,
+        revealable: impl Fn(Coordinates) -> bool,
+    ) -> Vec<Coordinates> {
+        let mut queue: VecDeque<_> = start.into_iter().collect();
+        let mut seen = HashSet::new();
+        let mut uncovered = Vec::new();
+        while let Some(coords) = queue.pop_front() {
+            let Some(tile) = self.get(coords) else { continue; };
+            if !revealable(coords) || !seen.insert(coords) {
+                continue;
+            }
+            uncovered.push(coords);
+            match tile {
+                Tile::Bomb(_) => break,
+                Tile::Empty => queue.extend(coords.neighbors()),
+                Tile::BombNeighbor(_) => (),
+            }
+        }
+        uncovered
+    }
+
+    /// Neighbors to reveal when chording on the number at `coords`, which needs exactly as
+    /// many flags around it as its bomb count. Question marks count as no flag
+    pub fn chord_targets(
+        &self,
+        coords: Coordinates,
+        flags: impl Fn(Coordinates) -> u8,
+        revealable: impl Fn(Coordinates) -> bool,
+    ) -> Vec<Coordinates> {
+        let Some(&Tile::BombNeighbor(count)) = self.get(coords) else { return Vec::new(); };
+        let flag_count: u16 = coords.neighbors().map(|c| u16::from(flags(c))).sum();
+        if flag_count != u16::from(count) {
+            return Vec::new();
+        }
+        coords.neighbors().filter(|&c| revealable(c)).collect()
+    }
+
+    /// Is a game won with `covered` tiles left, `is_covered` telling which, and the given flag
+    /// counts? Single mine maps are cleared once the covered tiles are exactly the mine tiles,
+    /// multi-mine ones once every mine tile carries exactly as many flags as it holds mines,
+    /// and no other tile is flagged
+    pub fn is_cleared(
+        &self,
+        covered: usize,
+        is_covered: impl Fn(Coordinates) -> bool,
+        mut flags: impl ExactSizeIterator<Item = (Coordinates, u8)>,
+    ) -> bool {
+        if !self.is_multi_bomb() {
+            // A mine uncovered in practice mode leaves a safe tile covered for the same count
+            return self.bomb_tile_count == covered && self.bomb_tiles().all(is_covered);
+        }
+        flags.len() == self.bomb_tile_count && flags.all(|(coords, n)| self.get(coords) == Some(&Tile::Bomb(n)))
+    }
 }
 
 impl Deref for TileMap {
@@ -116,8 +280,20 @@ impl Deref for TileMap {
     }
 }
 
-impl DerefMut for TileMap {
-    fn deref_mut(&mut self) -> &mut Self::Target {
-        &mut self.map
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mines_per_tile_are_bounded() {
+        let mut tile_map = TileMap::empty(3, 3);
+        tile_map.set_bombs_seeded(u16::MAX, u8::MAX, 7, None);
+        assert_eq!(tile_map.max_bombs_per_tile(), MAX_BOMBS_PER_TILE);
+        assert_eq!(tile_map.bomb_count(), 9 * MAX_BOMBS_PER_TILE as u16);
+        assert_eq!(tile_map.bomb_tile_count(), 9);
+
+        tile_map.set_bombs_at((0..9).filter(|&i| i != 4).map(|i| (Coordinates { x: i % 3, y: i / 3 }, 200)));
+        assert_eq!(tile_map.get(Coordinates { x: 1, y: 1 }), Some(&Tile::BombNeighbor(8 * MAX_BOMBS_PER_TILE)));
+        assert_eq!(tile_map.bomb_tile_count(), 8);
     }
 }
@@ -0,0 +1,20 @@
+use bevy::prelude::Resource;
+use serde::{Deserialize, Serialize};
+
+/// Touch gesture options. Must be used as a resource
+#[derive(Debug, Clone, Resource, Serialize, Deserialize)]
+pub struct TouchOptions {
+    /// Time a touch must be held in place to flag a tile, in seconds
+    pub long_press_duration: f32,
+    /// Distance in pixels a touch may travel and still count as a tap or long press
+    pub tap_slop: f32,
+}
+
+impl Default for TouchOptions {
+    fn default() -> Self {
+        Self {
+            long_press_duration: 0.5,
+            tap_slop: 12.,
+        }
+    }
+}
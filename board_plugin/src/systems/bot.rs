@@ -0,0 +1,74 @@
+use bevy::{ecs::system::SystemParam, prelude::*, log};
+
+use crate::{
+    events::{TileActionWriters, TileChordEvent, TileMarkEvent, TileTriggerEvent},
+    minefield::Move,
+    player::Player,
+    resources::{ActionInput, Board, BotOptions, ClickCounts, GameTimer, InputAction, TileCursor},
+};
+
+/// Shortest time between two bot moves, in seconds. The board needs a few frames to
+/// apply a move, and a faster bot would play again on a stale view
+const MIN_MOVE_INTERVAL: f32 = 0.05;
+
+/// Player behind the bot, rebuilt for every new board
+#[derive(Default)]
+pub struct BotState {
+    player: Option<Box<dyn Player>>,
+    board: Option<Entity>,
+    since_last_move: f32,
+}
+
+#[derive(SystemParam)]
+pub struct Bot<'w, 's> {
+    options: Res<'w, BotOptions>,
+    time: Res<'w, Time>,
+    state: Local<'s, BotState>,
+}
+
+pub fn bot_toggle_input(input: ActionInput, mut options: ResMut<BotOptions>) {
+    if input.just_pressed(InputAction::Bot) {
+        options.enabled = !options.enabled;
+        log::info!("Bot {}", if options.enabled { "started" } else { "stopped" });
+    }
+}
+
+/// Plays the board with the configured bot, sending the same events as a human player
+pub fn bot_play(
+    mut bot: Bot,
+    board: Res<Board>,
+    timer: Res<GameTimer>,
+    mut clicks: ResMut<ClickCounts>,
+    mut cursor: ResMut<TileCursor>,
+    mut actions: TileActionWriters,
+) {
+    if !bot.options.enabled || timer.finished {
+        return;
+    }
+    let delta = bot.time.delta_seconds();
+    let interval = bot.options.move_interval.max(MIN_MOVE_INTERVAL);
+    let player_kind = bot.options.player;
+    let state = &mut *bot.state;
+    if state.board != Some(board.entity) || state.player.is_none() {
+        state.board = Some(board.entity);
+        state.player = Some(player_kind.create(board.entity.to_bits()));
+        state.since_last_move = 0.;
+    }
+    state.since_last_move += delta;
+    if state.since_last_move < interval {
+        return;
+    }
+    state.since_last_move = 0.;
+
+    let Some(player) = state.player.as_mut() else { return; };
+    let Some(decision) = player.next_move(&board.view()) else { return; };
+    log::info!("Bot plays {} (risk {:.2})", decision.action, decision.risk);
+    clicks.bot += 1;
+    match decision.action {
+        Move::Reveal(coords) => actions.trigger.send(TileTriggerEvent(coords)),
+        Move::Flag(coords) => actions.mark.send(TileMarkEvent(coords)),
+        Move::Chord(coords) => actions.chord.send(TileChordEvent(coords)),
+    }
+    // Show where the bot plays
+    cursor.position = decision.action.coordinates();
+}
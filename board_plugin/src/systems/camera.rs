@@ -0,0 +1,196 @@
+use bevy::{prelude::*, ecs::system::SystemParam, window::PrimaryWindow, input::mouse::{MouseMotion, MouseScrollUnit, MouseWheel}};
+
+use crate::resources::{ActionInput, Board, EditorBoard, EndlessBoard, InputAction};
+
+const MIN_SCALE: f32 = 0.1;
+const MAX_SCALE: f32 = 20.;
+// Scale factor applied per mouse wheel line
+const ZOOM_STEP: f32 = 1.1;
+// Mouse wheel pixels counting as one line, for touchpads
+const PIXELS_PER_LINE: f32 = 50.;
+// Keyboard panning speed, in screen pixels per second
+const PAN_SPEED: f32 = 500.;
+
+/// Primary window and camera, to convert window positions to world positions
+#[derive(SystemParam)]
+pub struct WindowCamera<'w, 's> {
+    windows: Query<'w, 's, &'static Window, With<PrimaryWindow>>,
+    cameras: Query<'w, 's, (&'static Camera, &'static GlobalTransform)>,
+}
+
+impl WindowCamera<'_, '_> {
+    /// World position under a touch. Touch positions have their origin at the
+    /// top left of the window while the camera expects the bottom left
+    pub fn touch_to_world(&self, position: Vec2) -> Option<Vec2> {
+        let window = self.windows.get_single().ok()?;
+        let (camera, camera_transform) = self.cameras.get_single().ok()?;
+        let position = Vec2::new(position.x, window.height() - position.y);
+        camera.viewport_to_world_2d(camera_transform, position)
+    }
+}
+
+/// Zooms the camera with the mouse wheel, keeping the point under the cursor in place
+pub fn camera_zoom(
+    windows: Query<&Window, With<PrimaryWindow>>,
+    mut wheel_evr: EventReader<MouseWheel>,
+    mut cameras: Query<(&Camera, &GlobalTransform, &mut Transform, &mut OrthographicProjection)>,
+) {
+    let lines: f32 = wheel_evr.iter()
+        .map(|e| match e.unit {
+            MouseScrollUnit::Line => e.y,
+            MouseScrollUnit::Pixel => e.y / PIXELS_PER_LINE,
+        })
+        .sum();
+    if lines == 0. {
+        return;
+    }
+    let window = windows.single();
+    for (camera, camera_transform, mut transform, mut projection) in cameras.iter_mut() {
+        let anchor = window.cursor_position()
+            .and_then(|p| camera.viewport_to_world_2d(camera_transform, p));
+        zoom_around(&mut transform, &mut projection, anchor, ZOOM_STEP.powf(-lines));
+    }
+}
+
+/// Multiplies the camera scale by `factor`, keeping the `anchor` world point in place on screen
+pub(crate) fn zoom_around(
+    transform: &mut Transform,
+    projection: &mut OrthographicProjection,
+    anchor: Option<Vec2>,
+    factor: f32,
+) {
+    let old_scale = projection.scale;
+    let new_scale = (old_scale * factor).clamp(MIN_SCALE, MAX_SCALE);
+    if let Some(anchor) = anchor {
+        let offset = transform.translation.truncate() - anchor;
+        let translation = anchor + offset * (new_scale / old_scale);
+        transform.translation = translation.extend(transform.translation.z);
+    }
+    projection.scale = new_scale;
+}
+
+/// Pans the camera by dragging with the pan button, the middle mouse button by default.
+/// On a bounded board the cursor keys move the tile cursor, which the camera follows. Endless
+/// and editor boards have no tile cursor, so the cursor keys pan the camera there
+pub fn camera_pan(
+    input: ActionInput,
+    time: Res<Time>,
+    board: Option<Res<Board>>,
+    mut motion_evr: EventReader<MouseMotion>,
+    mut cameras: Query<(&mut Transform, &OrthographicProjection), With<Camera>>,
+) {
+    let mut delta = Vec2::ZERO;
+    if input.mouse_pressed(InputAction::PanCamera) {
+        for event in motion_evr.iter() {
+            // Window y axis points down, world y axis points up
+            delta += Vec2::new(-event.delta.x, event.delta.y);
+        }
+    } else {
+        motion_evr.clear();
+    }
+
+    if board.is_none() {
+        let directions = [
+            (InputAction::CursorLeft, Vec2::NEG_X),
+            (InputAction::CursorRight, Vec2::X),
+            (InputAction::CursorDown, Vec2::NEG_Y),
+            (InputAction::CursorUp, Vec2::Y),
+        ];
+        let direction: Vec2 = directions.iter().filter(|(a, _)| input.key_pressed(*a)).map(|(_, d)| *d).sum();
+        delta += direction.normalize_or_zero() * PAN_SPEED * time.delta_seconds();
+    }
+
+    if delta == Vec2::ZERO {
+        return;
+    }
+    for (mut transform, projection) in cameras.iter_mut() {
+        transform.translation += (delta * projection.scale).extend(0.);
+    }
+}
+
+/// Centers the camera on the board, zooming out if it is larger than the window.
+/// Runs when a board is created or when the fit camera action is triggered. Without a
+/// bounded board the camera goes back to the origin
+pub fn camera_fit_board(
+    input: ActionInput,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    board: Option<Res<Board>>,
+    endless_board: Option<Res<EndlessBoard>>,
+    editor_board: Option<Res<EditorBoard>>,
+    mut cameras: Query<(&mut Transform, &mut OrthographicProjection), With<Camera>>,
+) {
+    let created = board.as_ref().is_some_and(|b| b.is_added())
+        || endless_board.is_some_and(|b| b.is_added())
+        || editor_board.as_ref().is_some_and(|b| b.is_added());
+    if !created && !input.just_pressed(InputAction::FitCamera) {
+        return;
+    }
+    let window = windows.single();
+    let bounds = board.map(|b| b.bounds).or(editor_board.map(|b| b.bounds));
+    let (center, scale) = match bounds {
+        Some(bounds) => {
+            let window_size = Vec2::new(window.width(), window.height());
+            let fit = bounds.size / window_size;
+            (bounds.position + bounds.size / 2., fit.max_element().max(1.))
+        }
+        None => (Vec2::ZERO, 1.),
+    };
+    for (mut transform, mut projection) in cameras.iter_mut() {
+        transform.translation = center.extend(transform.translation.z);
+        projection.scale = scale;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use bevy::{input::InputPlugin, time::TimeUpdateStrategy};
+
+    use super::*;
+    use crate::{resources::{tilemap::TileMap, InputMap}, systems::testing};
+
+    fn app() -> App {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins);
+        app.add_plugin(InputPlugin);
+        app.init_resource::<InputMap>();
+        app.insert_resource(TimeUpdateStrategy::ManualInstant(Instant::now()));
+        app.add_system(camera_pan);
+        app.world.spawn((Camera::default(), OrthographicProjection::default(), Transform::default()));
+        update(&mut app);
+        app
+    }
+
+    /// Runs a frame lasting 100ms
+    fn update(app: &mut App) {
+        if let TimeUpdateStrategy::ManualInstant(instant) = app.world.resource_mut::<TimeUpdateStrategy>().as_mut() {
+            *instant += Duration::from_millis(100);
+        }
+        app.update();
+    }
+
+    fn camera_position(app: &mut App) -> Vec2 {
+        app.world.query_filtered::<&Transform, With<Camera>>().single(&app.world).translation.truncate()
+    }
+
+    #[test]
+    fn keys_pan_without_tile_cursor() {
+        let mut app = app();
+        app.world.resource_mut::<Input<KeyCode>>().press(KeyCode::Left);
+        update(&mut app);
+        update(&mut app);
+        let position = camera_position(&mut app);
+        assert!(position.x < 0. && position.y == 0., "camera at {}", position);
+    }
+
+    #[test]
+    fn keys_move_tile_cursor_on_board() {
+        let mut app = app();
+        app.insert_resource(testing::board(TileMap::empty(5, 5), 10.));
+        app.world.resource_mut::<Input<KeyCode>>().press(KeyCode::Left);
+        update(&mut app);
+        update(&mut app);
+        assert_eq!(camera_position(&mut app), Vec2::ZERO);
+    }
+}
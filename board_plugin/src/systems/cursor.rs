@@ -0,0 +1,181 @@
+use bevy::{prelude::*, log};
+
+use crate::{
+    components::Coordinates,
+    events::{TileActionWriters, TileChordEvent, TileMarkEvent, TileTriggerEvent},
+    resources::{ActionInput, Board, InputAction, TileCursor},
+};
+
+/// Marks the sprite highlighting the cursor tile
+#[derive(Debug, Copy, Clone, Component)]
+pub struct CursorHighlight;
+
+pub fn cursor_keyboard_input(
+    input: ActionInput,
+    board: Res<Board>,
+    mut cursor: ResMut<TileCursor>,
+    mut actions: TileActionWriters,
+) {
+    let pressed = |action: InputAction| input.key_just_pressed(action);
+
+    let mut direction = IVec2::ZERO;
+    if pressed(InputAction::CursorUp) {
+        direction.y += 1;
+    }
+    if pressed(InputAction::CursorDown) {
+        direction.y -= 1;
+    }
+    if pressed(InputAction::CursorLeft) {
+        direction.x -= 1;
+    }
+    if pressed(InputAction::CursorRight) {
+        direction.x += 1;
+    }
+
+    let command = CursorCommand {
+        direction,
+        jump: input.key_pressed(InputAction::CursorJump),
+        next_covered: pressed(InputAction::NextCovered),
+        reveal: pressed(InputAction::Reveal),
+        flag: pressed(InputAction::Flag),
+        chord: pressed(InputAction::Chord),
+    };
+    command.apply(&board, &mut cursor, &mut actions);
+}
+
+/// Cursor movement and tile actions requested during a frame, whatever the input device
+#[derive(Debug, Default, Copy, Clone)]
+pub(crate) struct CursorCommand {
+    pub direction: IVec2,
+    pub jump: bool,
+    pub next_covered: bool,
+    pub reveal: bool,
+    pub flag: bool,
+    pub chord: bool,
+}
+
+impl CursorCommand {
+    pub fn apply(&self, board: &Board, cursor: &mut ResMut<TileCursor>, actions: &mut TileActionWriters) {
+        let (width, height) = (board.tile_map.width() as i32, board.tile_map.height() as i32);
+        let mut position = cursor.position;
+        if self.direction != IVec2::ZERO {
+            if self.jump {
+                position = jump_to_edge(position, self.direction, (width, height));
+            } else {
+                position = Coordinates {
+                    x: (position.x + self.direction.x).clamp(0, width - 1),
+                    y: (position.y + self.direction.y).clamp(0, height - 1),
+                };
+            }
+        }
+        if self.next_covered {
+            position = next_covered(board, position).unwrap_or(position);
+        }
+        if self.direction == IVec2::ZERO && !self.next_covered && !self.reveal && !self.flag && !self.chord {
+            return;
+        }
+
+        cursor.position = position;
+        cursor.visible = true;
+        if self.reveal {
+            log::info!("Trying to uncover tile on {}", position);
+            actions.trigger.send(TileTriggerEvent(position));
+        }
+        if self.flag {
+            log::info!("Trying to mark tile on {}", position);
+            actions.mark.send(TileMarkEvent(position));
+        }
+        if self.chord {
+            log::info!("Trying to chord on {}", position);
+            actions.chord.send(TileChordEvent(position));
+        }
+    }
+}
+
+fn jump_to_edge(position: Coordinates, direction: IVec2, (width, height): (i32, i32)) -> Coordinates {
+    let pick = |d: i32, current: i32, max: i32| match d {
+        d if d < 0 => 0,
+        d if d > 0 => max - 1,
+        _ => current,
+    };
+    Coordinates {
+        x: pick(direction.x, position.x, width),
+        y: pick(direction.y, position.y, height),
+    }
+}
+
+/// Next covered, unflagged tile after `position` in reading order, wrapping around
+pub(crate) fn next_covered(board: &Board, position: Coordinates) -> Option<Coordinates> {
+    let (width, height) = (board.tile_map.width() as i32, board.tile_map.height() as i32);
+    let tile_count = width * height;
+    // Reading order goes from the top row down, and the top row has the highest y
+    let index = |c: Coordinates| (height - 1 - c.y) * width + c.x;
+    let start = index(position);
+    (1..=tile_count)
+        .map(|offset| (start + offset) % tile_count)
+        .map(|i| Coordinates { x: i % width, y: height - 1 - i / width })
+        .find(|c| board.get_covered_tile(c).is_some())
+}
+
+/// Draws the cursor highlight over the cursor tile, spawning it under the board if needed
+pub fn update_cursor_highlight(
+    mut cmd: Commands,
+    board: Res<Board>,
+    cursor: Res<TileCursor>,
+    mut highlights: Query<(&mut Transform, &mut Sprite, &mut Visibility), With<CursorHighlight>>,
+) {
+    if !board.is_changed() && !cursor.is_changed() {
+        return;
+    }
+    let translation = Vec3::new(
+        (cursor.position.x as f32 + 0.5) * board.tile_size,
+        (cursor.position.y as f32 + 0.5) * board.tile_size,
+        5.,
+    );
+    let visibility = if cursor.visible { Visibility::Inherited } else { Visibility::Hidden };
+
+    if let Ok((mut transform, mut sprite, mut visible)) = highlights.get_single_mut() {
+        transform.translation = translation;
+        sprite.custom_size = Some(Vec2::splat(board.tile_size));
+        *visible = visibility;
+        return;
+    }
+    let highlight = cmd.spawn(Name::new("Cursor"))
+        .insert(CursorHighlight)
+        .insert(SpriteBundle {
+            sprite: Sprite {
+                color: Color::rgba(1., 1., 0., 0.35),
+                custom_size: Some(Vec2::splat(board.tile_size)),
+                ..default()
+            },
+            transform: Transform::from_translation(translation),
+            visibility,
+            ..default()
+        })
+        .id();
+    cmd.entity(board.entity).add_child(highlight);
+}
+
+/// Pans the camera just enough to keep the cursor tile in view
+pub fn cursor_follow_camera(
+    board: Res<Board>,
+    cursor: Res<TileCursor>,
+    mut cameras: Query<(&Camera, &GlobalTransform, &mut Transform)>,
+) {
+    if !cursor.is_changed() || !cursor.visible {
+        return;
+    }
+    let tile_center = board.bounds.position
+        + (Vec2::new(cursor.position.x as f32, cursor.position.y as f32) + 0.5) * board.tile_size;
+    let margin = Vec2::splat(board.tile_size / 2.);
+    for (camera, camera_transform, mut transform) in cameras.iter_mut() {
+        let Some(viewport) = camera.logical_viewport_size() else { continue; };
+        let (Some(a), Some(b)) = (
+            camera.viewport_to_world_2d(camera_transform, Vec2::ZERO),
+            camera.viewport_to_world_2d(camera_transform, viewport),
+        ) else { continue; };
+        let (min, max) = (a.min(b) + margin, a.max(b) - margin);
+        let shift = tile_center.min(min) - min + tile_center.max(max) - max;
+        transform.translation += shift.extend(0.);
+    }
+}
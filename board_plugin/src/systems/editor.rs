@@ -0,0 +1,178 @@
+use bevy::{prelude::*, window::PrimaryWindow, log, math::Vec3Swizzles};
+
+use crate::{
+    bounds::Bounds2,
+    components::Coordinates,
+    puzzle::Verdict,
+    resources::{tile::Tile, ActionInput, BoardAssets, BoardEditor, BoardOptions, EditorBoard, InputAction, TileSize},
+};
+
+use super::spawn::{adaptative_tile_size, board_position, bomb_count_text_bundle};
+
+pub fn create_editor_board(
+    mut cmd: Commands,
+    editor: Option<Res<BoardEditor>>,
+    board_options: Option<Res<BoardOptions>>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+) {
+    let window = windows.single();
+    let options = board_options.map(|o| o.to_owned()).unwrap_or_default();
+    let tile_map = match editor {
+        Some(editor) => editor.layout.tile_map.clone(),
+        None => {
+            let editor = BoardEditor::load(BoardEditor::default_path(), options.map_size);
+            log::info!("Editing {}", editor.path().display());
+            let tile_map = editor.layout.tile_map.clone();
+            cmd.insert_resource(editor);
+            tile_map
+        }
+    };
+
+    let tile_size = match options.tile_size {
+        TileSize::Fixed(s) => s,
+        TileSize::Adaptive { min, max } =>
+            adaptative_tile_size(window, (min, max), (tile_map.width(), tile_map.height()))
+    };
+    let board_size = Vec2::new(tile_map.width() as f32 * tile_size, tile_map.height() as f32 * tile_size);
+    let position = board_position(&options.position, board_size);
+
+    let entity = cmd.spawn(Name::new("Editor Board"))
+        .insert(SpatialBundle {
+            transform: Transform::from_translation(position),
+            ..default()
+        })
+        .id();
+    cmd.insert_resource(EditorBoard {
+        bounds: Bounds2 { position: position.xy(), size: board_size },
+        tile_size,
+        tile_padding: options.tile_padding,
+        entity,
+    });
+}
+
+pub fn despawn_editor_board(mut cmd: Commands, board: Res<EditorBoard>) {
+    log::info!("despawning editor board");
+    cmd.entity(board.entity).despawn_recursive();
+    cmd.remove_resource::<EditorBoard>();
+}
+
+/// Draws the edited layout again after every change, or theme change: mines, live numbers,
+/// and the tiles starting revealed or flagged
+pub fn draw_editor_board(
+    mut cmd: Commands,
+    editor: Res<BoardEditor>,
+    board: Res<EditorBoard>,
+    board_assets: Res<BoardAssets>,
+) {
+    if !editor.is_changed() && !board.is_added() && !board_assets.is_changed() {
+        return;
+    }
+    let layout = &editor.layout;
+    let size = board.tile_size - board.tile_padding;
+    cmd.entity(board.entity).despawn_descendants();
+    cmd.entity(board.entity).with_children(|parent| {
+        parent.spawn(SpriteBundle {
+            sprite: Sprite {
+                color: board_assets.board_material.color,
+                custom_size: Some(board.bounds.size),
+                ..default()
+            },
+            texture: board_assets.board_material.texture.clone(),
+            transform: Transform::from_translation((board.bounds.size / 2.).extend(0.)),
+            ..default()
+        });
+        for (y, line) in layout.tile_map.iter().enumerate() {
+            for (x, tile) in line.iter().enumerate() {
+                let coords = Coordinates { x: x as i32, y: y as i32 };
+                let material = match layout.revealed.contains(&coords) {
+                    true => &board_assets.tile_material,
+                    false => &board_assets.covered_tile_material,
+                };
+                let overlay = match tile {
+                    Tile::Bomb(_) if layout.flagged.contains(&coords) => Some(&board_assets.flag_material),
+                    Tile::Bomb(_) => Some(&board_assets.bomb_material),
+                    _ => None,
+                };
+                parent.spawn(SpriteBundle {
+                    sprite: Sprite {
+                        color: material.color,
+                        custom_size: Some(Vec2::splat(size)),
+                        ..default()
+                    },
+                    texture: material.texture.clone(),
+                    transform: Transform::from_xyz(
+                        (x as f32 + 0.5) * board.tile_size,
+                        (y as f32 + 0.5) * board.tile_size,
+                        1.,
+                    ),
+                    ..default()
+                })
+                .insert(Name::new(format!("Tile ({}, {})", x, y)))
+                .with_children(|p| {
+                    if let Some(overlay) = overlay {
+                        p.spawn(SpriteBundle {
+                            sprite: Sprite {
+                                color: overlay.color,
+                                custom_size: Some(Vec2::splat(size)),
+                                ..default()
+                            },
+                            texture: overlay.texture.clone(),
+                            transform: Transform::from_xyz(0., 0., 1.),
+                            ..default()
+                        });
+                    }
+                    if let Tile::BombNeighbor(n) = tile {
+                        p.spawn(bomb_count_text_bundle(*n, &board_assets, size));
+                    }
+                });
+            }
+        }
+    });
+}
+
+/// Pointer input of the editor: the reveal button adds or removes a mine, the flag button
+/// marks a safe tile as revealed or a mine as flagged at the start
+pub fn editor_input_handling(
+    windows: Query<&Window, With<PrimaryWindow>>,
+    cameras: Query<(&Camera, &GlobalTransform)>,
+    board: Res<EditorBoard>,
+    mut editor: ResMut<BoardEditor>,
+    input: ActionInput,
+) {
+    let window = windows.single();
+    let Ok((camera, camera_transform)) = cameras.get_single() else { return; };
+
+    let mine = input.mouse_just_pressed(InputAction::Reveal);
+    let mark = input.mouse_just_pressed(InputAction::Flag);
+    if !mine && !mark {
+        return;
+    }
+    let Some(coordinates) = window.cursor_position()
+        .and_then(|p| camera.viewport_to_world_2d(camera_transform, p))
+        .and_then(|p| board.world_position(p)) else { return; };
+    if mine {
+        log::info!("Toggling mine on {}", coordinates);
+        editor.toggle_mine(coordinates);
+    } else {
+        log::info!("Toggling start mark on {}", coordinates);
+        editor.toggle_mark(coordinates);
+    }
+}
+
+/// Checks the edited puzzle with the solver, and saves it. The verdict and the outcome of
+/// the save are kept on the `BoardEditor`, for the editor panel to show
+pub fn editor_actions(mut editor: ResMut<BoardEditor>, input: ActionInput) {
+    if input.just_pressed(InputAction::CheckPuzzle) {
+        match editor.check() {
+            Ok(Verdict::NoGuess) => log::info!("Puzzle clears without guessing"),
+            Ok(Verdict::NeedsGuess(left)) => log::info!("Puzzle needs a guess, {} safe tiles left", left),
+            Err(e) => log::info!("Invalid puzzle: {}", e),
+        }
+    }
+    if input.just_pressed(InputAction::SavePuzzle) {
+        match editor.save() {
+            Ok(()) => log::info!("Saved puzzle to {}", editor.path().display()),
+            Err(e) => log::warn!("Failed to save puzzle to {}: {}", editor.path().display(), e),
+        }
+    }
+}
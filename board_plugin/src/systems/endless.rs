@@ -0,0 +1,256 @@
+use bevy::{prelude::*, window::PrimaryWindow, log};
+
+use crate::{
+    components::{Bomb, BombNeighbor, Coordinates, Themed},
+    events::{BombExplosionEvent, EndlessRunEndedEvent, TileMarkEvent, TileTriggerEvent},
+    resources::{tile::Tile, ActionInput, BoardAssets, BoardOptions, EndlessBoard, EndlessOptions, InputAction, TileSize},
+};
+
+use super::spawn::bomb_count_text_bundle;
+
+pub fn create_endless_board(
+    mut cmd: Commands,
+    endless_options: Option<Res<EndlessOptions>>,
+    board_options: Option<Res<BoardOptions>>,
+) {
+    let options = endless_options.map(|o| o.to_owned()).unwrap_or_default();
+    let board_options = board_options.map(|o| o.to_owned()).unwrap_or_default();
+    let tile_size = match board_options.tile_size {
+        TileSize::Fixed(s) => s,
+        // There is no board extent to adapt to, use the largest allowed size
+        TileSize::Adaptive { max, .. } => max,
+    };
+    log::info!("Starting endless run with seed {}", options.seed);
+
+    let entity = cmd.spawn(Name::new("Endless Board"))
+        .insert(SpatialBundle::default())
+        .id();
+    cmd.insert_resource(EndlessBoard::new(options, tile_size, board_options.tile_padding, entity));
+}
+
+pub fn despawn_endless_board(mut cmd: Commands, board: Res<EndlessBoard>) {
+    log::info!("despawning endless board, {} tiles cleared", board.cleared);
+    cmd.entity(board.entity).despawn_recursive();
+    cmd.remove_resource::<EndlessBoard>();
+}
+
+/// Spawns the chunks around the camera view and despawns the ones that left it.
+/// The player state of despawned chunks is kept in the `EndlessBoard`
+pub fn stream_chunks(
+    mut cmd: Commands,
+    mut board: ResMut<EndlessBoard>,
+    board_assets: Res<BoardAssets>,
+    cameras: Query<(&Camera, &GlobalTransform)>,
+) {
+    let Ok((camera, camera_transform)) = cameras.get_single() else { return; };
+    let Some(viewport) = camera.logical_viewport_size() else { return; };
+    let (Some(a), Some(b)) = (
+        camera.viewport_to_world_2d(camera_transform, Vec2::ZERO),
+        camera.viewport_to_world_2d(camera_transform, viewport),
+    ) else { return; };
+    let (min, max) = board.chunks_in_area(a, b);
+    let in_view = |c: &IVec2| c.x >= min.x && c.y >= min.y && c.x <= max.x && c.y <= max.y;
+
+    let out_of_view: Vec<IVec2> = board.spawned_chunks.keys().filter(|c| !in_view(c)).copied().collect();
+    for chunk in out_of_view {
+        if let Some(entity) = board.spawned_chunks.remove(&chunk) {
+            cmd.entity(entity).despawn_recursive();
+        }
+        for coords in EndlessBoard::chunk_tiles(chunk) {
+            board.covered_tiles.remove(&coords);
+        }
+    }
+
+    for y in min.y..=max.y {
+        for x in min.x..=max.x {
+            let chunk = IVec2::new(x, y);
+            if !board.spawned_chunks.contains_key(&chunk) {
+                spawn_chunk(&mut cmd, &mut board, &board_assets, chunk);
+            }
+        }
+    }
+}
+
+fn spawn_chunk(
+    cmd: &mut Commands,
+    board: &mut EndlessBoard,
+    board_assets: &BoardAssets,
+    chunk: IVec2,
+) {
+    let size = board.tile_size - board.tile_padding;
+    let tiles: Vec<(Coordinates, Tile, bool, bool)> = EndlessBoard::chunk_tiles(chunk)
+        .map(|c| (c, board.tile(c), board.is_uncovered(c), board.is_flagged(c)))
+        .collect();
+    let mut covered_tiles = Vec::new();
+
+    let chunk_entity = cmd.spawn(Name::new(format!("Chunk ({}, {})", chunk.x, chunk.y)))
+        .insert(SpatialBundle::default())
+        .with_children(|parent| {
+            for (coords, tile, uncovered, flagged) in tiles {
+                let mut cmd = parent.spawn_empty();
+                cmd
+                    .insert(SpriteBundle {
+                        sprite: Sprite {
+                            color: board_assets.tile_material.color,
+                            custom_size: Some(Vec2::splat(size)),
+                            ..default()
+                        },
+                        texture: board_assets.tile_material.texture.clone(),
+                        transform: Transform::from_translation(board.tile_position(coords).extend(1.)),
+                        ..default()
+                    })
+                    .insert(Name::new(format!("Tile ({}, {})", coords.x, coords.y)))
+                    .insert(coords)
+                    .insert(Themed::Tile);
+
+                if !uncovered {
+                    cmd.with_children(|tile_entity| {
+                        let mut cover = tile_entity.spawn(Name::new("Tile Cover"));
+                        cover.insert(SpriteBundle {
+                            sprite: Sprite {
+                                custom_size: Some(Vec2::splat(size)),
+                                color: board_assets.covered_tile_material.color,
+                                ..default()
+                            },
+                            texture: board_assets.covered_tile_material.texture.clone(),
+                            transform: Transform::from_xyz(0., 0., 2.),
+                            ..default()
+                        })
+                        .insert(Themed::CoveredTile);
+                        if flagged {
+                            cover.with_children(|parent| {
+                                parent.spawn(flag_bundle(board_assets, size));
+                            });
+                        }
+                        covered_tiles.push((coords, cover.id()));
+                    });
+                }
+
+                match tile {
+                    Tile::Bomb(n) => {
+                        cmd.insert(Bomb { count: n })
+                            .with_children(|p| {
+                                p.spawn(SpriteBundle {
+                                    sprite: Sprite {
+                                        custom_size: Some(Vec2::splat(size)),
+                                        color: board_assets.bomb_material.color,
+                                        ..default()
+                                    },
+                                    transform: Transform::from_xyz(0., 0., 1.),
+                                    texture: board_assets.bomb_material.texture.clone(),
+                                    ..default()
+                                })
+                                .insert(Themed::Bomb);
+                            });
+                    }
+                    Tile::BombNeighbor(n) => {
+                        cmd.insert(BombNeighbor { count: n })
+                            .with_children(|p| {
+                                p.spawn(bomb_count_text_bundle(n, board_assets, size));
+                            });
+                    }
+                    Tile::Empty => (),
+                }
+            }
+        })
+        .id();
+
+    cmd.entity(board.entity).add_child(chunk_entity);
+    board.covered_tiles.extend(covered_tiles);
+    board.spawned_chunks.insert(chunk, chunk_entity);
+}
+
+fn flag_bundle(board_assets: &BoardAssets, size: f32) -> (Name, SpriteBundle, Themed) {
+    (
+        Name::new("Flag"),
+        SpriteBundle {
+            texture: board_assets.flag_material.texture.clone(),
+            sprite: Sprite {
+                custom_size: Some(Vec2::splat(size)),
+                color: board_assets.flag_material.color,
+                ..default()
+            },
+            ..default()
+        },
+        Themed::Flag,
+    )
+}
+
+pub fn endless_input_handling(
+    windows: Query<&Window, With<PrimaryWindow>>,
+    cameras: Query<(&Camera, &GlobalTransform)>,
+    board: Res<EndlessBoard>,
+    input: ActionInput,
+    mut tile_trigger_ew: EventWriter<TileTriggerEvent>,
+    mut tile_mark_ew: EventWriter<TileMarkEvent>,
+) {
+    let window = windows.single();
+    let Ok((camera, camera_transform)) = cameras.get_single() else { return; };
+
+    let reveal = input.mouse_just_pressed(InputAction::Reveal);
+    let flag = input.mouse_just_pressed(InputAction::Flag);
+    if (!reveal && !flag) || !board.alive {
+        return;
+    }
+    let Some(position) = window.cursor_position()
+        .and_then(|p| camera.viewport_to_world_2d(camera_transform, p)) else { return; };
+    let coordinates = board.world_to_coordinates(position);
+    if reveal {
+        log::info!("Trying to uncover tile on {}", coordinates);
+        tile_trigger_ew.send(TileTriggerEvent(coordinates));
+    }
+    if flag {
+        log::info!("Trying to mark tile on {}", coordinates);
+        tile_mark_ew.send(TileMarkEvent(coordinates));
+    }
+}
+
+pub fn endless_uncover_tiles(
+    mut cmd: Commands,
+    mut board: ResMut<EndlessBoard>,
+    mut tile_trigger_evr: EventReader<TileTriggerEvent>,
+    mut explosion_wr: EventWriter<BombExplosionEvent>,
+    mut run_ended_wr: EventWriter<EndlessRunEndedEvent>,
+) {
+    for event in tile_trigger_evr.iter() {
+        if !board.alive {
+            break;
+        }
+        let uncovered = board.uncover(event.0);
+        for coords in uncovered.iter() {
+            if let Some(entity) = board.covered_tiles.remove(coords) {
+                cmd.entity(entity).despawn_recursive();
+            }
+        }
+        if !uncovered.is_empty() && board.tile(event.0).is_bomb() {
+            log::info!("Boom ! Run ended with {} tiles cleared", board.cleared);
+            board.alive = false;
+            explosion_wr.send(BombExplosionEvent(event.0));
+            run_ended_wr.send(EndlessRunEndedEvent(board.cleared));
+        }
+    }
+}
+
+pub fn endless_mark_tiles(
+    mut cmd: Commands,
+    mut board: ResMut<EndlessBoard>,
+    board_assets: Res<BoardAssets>,
+    mut tile_mark_er: EventReader<TileMarkEvent>,
+) {
+    for evt in tile_mark_er.iter() {
+        if !board.alive {
+            break;
+        }
+        let Some(flagged) = board.try_toggle_mark(evt.0) else { continue; };
+        // The chunk may not be spawned, its flags are then drawn once it is
+        let Some(&entity) = board.covered_tiles.get(&evt.0) else { continue; };
+        let mut cover = cmd.entity(entity);
+        if flagged {
+            cover.with_children(|parent| {
+                parent.spawn(flag_bundle(&board_assets, board.tile_size - board.tile_padding));
+            });
+        } else {
+            cover.despawn_descendants();
+        }
+    }
+}
@@ -0,0 +1,169 @@
+use bevy::{prelude::*, log};
+
+use crate::{
+    components::{Bomb, Coordinates, Themed},
+    events::{BoardCompletedEvent, BombExplosionEvent, GameEndedEvent, TileChordEvent, TileMarkEvent, TileTriggerEvent},
+    metrics::{self, GameMetrics},
+    resources::{tile::Tile, Board, BoardMetricsTask, BoardOptions, ClickCounts, GameResult, GameTimer, Stats},
+};
+
+/// Starts the timer with the first player move and counts game time until the game ends
+pub fn update_game_timer(
+    time: Res<Time>,
+    mut timer: ResMut<GameTimer>,
+    mut trigger_evr: EventReader<TileTriggerEvent>,
+    mut chord_evr: EventReader<TileChordEvent>,
+    mut mark_evr: EventReader<TileMarkEvent>,
+) {
+    let moved = trigger_evr.iter().count() + chord_evr.iter().count() + mark_evr.iter().count() > 0;
+    if timer.finished {
+        return;
+    }
+    if moved {
+        timer.started = true;
+    }
+    if timer.started {
+        timer.elapsed += time.delta_seconds();
+    }
+}
+
+/// Counts the tile actions of the game until it ends
+pub fn count_clicks(
+    timer: Res<GameTimer>,
+    mut clicks: ResMut<ClickCounts>,
+    mut trigger_evr: EventReader<TileTriggerEvent>,
+    mut chord_evr: EventReader<TileChordEvent>,
+    mut mark_evr: EventReader<TileMarkEvent>,
+) {
+    let (left, chord, right) = (trigger_evr.iter().count(), chord_evr.iter().count(), mark_evr.iter().count());
+    if timer.finished {
+        return;
+    }
+    clicks.left += left as u32;
+    clicks.chord += chord as u32;
+    clicks.right += right as u32;
+}
+
+/// Game that ended, waiting for the metrics of its board
+pub struct GameEnd {
+    /// Board entity, so that a game left before its metrics are known is dropped
+    board: Entity,
+    won: bool,
+    time: f32,
+    difficulty: String,
+    practice: bool,
+    undo_used: bool,
+    solved_bbbv: u32,
+    clicks: ClickCounts,
+}
+
+/// Ends the game when the board is completed, or when a bomb explodes outside of
+/// practice mode, where explosions can be undone. The result is sent once the metrics of
+/// the board, computed in the background, are known
+pub fn end_game(
+    (board, board_options): (Res<Board>, Option<Res<BoardOptions>>),
+    mut timer: ResMut<GameTimer>,
+    clicks: Res<ClickCounts>,
+    (mut completed_evr, mut explosion_evr): (EventReader<BoardCompletedEvent>, EventReader<BombExplosionEvent>),
+    mut metrics_task: ResMut<BoardMetricsTask>,
+    mut pending: Local<Option<GameEnd>>,
+    mut ended_ew: EventWriter<GameEndedEvent>,
+) {
+    let completed = completed_evr.iter().count() > 0;
+    let practice = board.history.undo_enabled;
+    let exploded = explosion_evr.iter().count() > 0 && !practice;
+    if !timer.finished && (completed || exploded) {
+        timer.finished = true;
+
+        // The board played, even if the options changed since it was spawned
+        let tile_map = &board.tile_map;
+        let options = BoardOptions {
+            map_size: (tile_map.width(), tile_map.height()),
+            bomb_count: tile_map.bomb_count(),
+            max_bombs_per_tile: tile_map.max_bombs_per_tile(),
+            ..board_options.map(|o| o.to_owned()).unwrap_or_default()
+        };
+        *pending = Some(GameEnd {
+            board: board.entity,
+            won: !exploded,
+            time: timer.elapsed,
+            difficulty: options.difficulty_label(),
+            practice,
+            undo_used: board.history.undo_used,
+            solved_bbbv: metrics::solved_bbbv(tile_map, |c| !board.covered_tiles.contains_key(&c)),
+            clicks: *clicks,
+        });
+    }
+    let Some(end) = pending.take().filter(|end| end.board == board.entity) else { return; };
+    let Some(board_metrics) = metrics_task.poll() else {
+        *pending = Some(end);
+        return;
+    };
+    let result = GameResult {
+        won: end.won,
+        time: end.time,
+        difficulty: end.difficulty,
+        practice: end.practice,
+        undo_used: end.undo_used,
+        bot: end.clicks.bot > 0,
+        metrics: GameMetrics::new(&board_metrics, end.solved_bbbv, end.clicks, end.time, end.won),
+    };
+    log::info!("Game {} in {:.2}s: {:?}", if end.won { "won" } else { "lost" }, result.time, result.metrics);
+    ended_ew.send(GameEndedEvent(result));
+}
+
+/// Once the game is lost, shows the mine that exploded and the flags put on tiles without
+/// as many mines. Must run after `end_game`, as the board stops updating with the game
+pub fn show_mistakes(
+    board: Res<Board>,
+    mut ended_evr: EventReader<GameEndedEvent>,
+    bombs: Query<(&Coordinates, &Children), With<Bomb>>,
+    covers: Query<&Children>,
+    mut parts: Query<&mut Themed>,
+) {
+    if !ended_evr.iter().any(|GameEndedEvent(result)| !result.won) {
+        return;
+    }
+    let mut restyle = |children: &Children, from: Themed, to: Themed| {
+        let mut iter = parts.iter_many_mut(children);
+        while let Some(mut part) = iter.fetch_next() {
+            if *part == from {
+                *part = to;
+            }
+        }
+    };
+    for (coords, children) in bombs.iter() {
+        if !board.covered_tiles.contains_key(coords) {
+            restyle(children, Themed::Bomb, Themed::ExplodedBomb);
+        }
+    }
+    for (coords, &flags) in board.marked_tiles.iter() {
+        let bomb_count = match board.tile_map.get(*coords) {
+            Some(Tile::Bomb(n)) => *n,
+            _ => 0,
+        };
+        let cover = board.covered_tiles.get(coords).and_then(|&e| covers.get(e).ok());
+        if let Some(children) = cover.filter(|_| flags > bomb_count) {
+            restyle(children, Themed::Flag, Themed::WrongFlag);
+        }
+    }
+}
+
+/// Adds finished games to the statistics and saves them, when statistics are kept
+pub fn record_game_stats(
+    mut ended_evr: EventReader<GameEndedEvent>,
+    stats: Option<ResMut<Stats>>,
+) {
+    let Some(mut stats) = stats else {
+        ended_evr.clear();
+        return;
+    };
+    for GameEndedEvent(result) in ended_evr.iter() {
+        if !stats.record(result) {
+            continue;
+        }
+        if let Err(e) = stats.save() {
+            log::warn!("Failed to save statistics to {}: {}", stats.path().display(), e);
+        }
+    }
+}
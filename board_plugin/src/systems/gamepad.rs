@@ -0,0 +1,202 @@
+use bevy::prelude::*;
+
+use crate::{
+    events::TileActionWriters,
+    resources::{ActionInput, Board, InputAction, StickOptions, TileCursor},
+};
+
+use super::cursor::CursorCommand;
+
+/// Held stick state, repeating cursor moves faster the longer the stick is held
+#[derive(Debug, Default)]
+pub struct StickRepeat {
+    direction: IVec2,
+    held: f32,
+    next_move: f32,
+}
+
+impl StickRepeat {
+    /// Returns the direction to move the cursor in this frame, if any
+    fn update(&mut self, direction: IVec2, delta: f32, options: &StickOptions) -> IVec2 {
+        if direction != self.direction {
+            *self = Self {
+                direction,
+                held: 0.,
+                next_move: options.repeat_delay,
+            };
+            return direction;
+        }
+        if direction == IVec2::ZERO {
+            return IVec2::ZERO;
+        }
+        self.held += delta;
+        if self.held < self.next_move {
+            return IVec2::ZERO;
+        }
+        // Accelerate with hold time so big boards can be crossed quickly
+        let interval = (options.repeat_interval / (1. + self.held)).max(options.min_interval);
+        self.next_move += interval;
+        direction
+    }
+}
+
+pub fn gamepad_cursor_input(
+    input: ActionInput,
+    axes: Res<Axis<GamepadAxis>>,
+    time: Res<Time>,
+    board: Res<Board>,
+    mut cursor: ResMut<TileCursor>,
+    mut stick: Local<StickRepeat>,
+    mut actions: TileActionWriters,
+) {
+    let stick_options = &input.map.stick;
+    let mut command = CursorCommand::default();
+    let mut stick_direction = IVec2::ZERO;
+
+    for gamepad in input.gamepads() {
+        let pressed = |action: InputAction| input.gamepad_just_pressed(gamepad, action);
+        if pressed(InputAction::CursorUp) {
+            command.direction.y += 1;
+        }
+        if pressed(InputAction::CursorDown) {
+            command.direction.y -= 1;
+        }
+        if pressed(InputAction::CursorLeft) {
+            command.direction.x -= 1;
+        }
+        if pressed(InputAction::CursorRight) {
+            command.direction.x += 1;
+        }
+        command.jump |= input.gamepad_pressed(gamepad, InputAction::CursorJump);
+        command.next_covered |= pressed(InputAction::NextCovered);
+        command.reveal |= pressed(InputAction::Reveal);
+        command.flag |= pressed(InputAction::Flag);
+        command.chord |= pressed(InputAction::Chord);
+
+        let axis = |t: GamepadAxisType| axes.get(GamepadAxis::new(gamepad, t)).unwrap_or(0.);
+        let quantize = |v: f32| if v.abs() < stick_options.dead_zone { 0 } else { v.signum() as i32 };
+        let direction = IVec2::new(
+            quantize(axis(GamepadAxisType::LeftStickX)),
+            quantize(axis(GamepadAxisType::LeftStickY)),
+        );
+        if direction != IVec2::ZERO {
+            stick_direction = direction;
+        }
+    }
+
+    let stick_move = stick.update(stick_direction, time.delta_seconds(), stick_options);
+    if command.direction == IVec2::ZERO {
+        command.direction = stick_move;
+    }
+    command.apply(&board, &mut cursor, &mut actions);
+}
+
+/// Rumbles every force feedback capable gamepad when a bomb explodes
+#[cfg(feature = "rumble")]
+pub fn gamepad_rumble(
+    mut explosion_evr: EventReader<crate::events::BombExplosionEvent>,
+    gilrs: Option<NonSendMut<gilrs::Gilrs>>,
+    // Effects stop when dropped, keep the last one alive until the next explosion
+    mut effect: Local<Option<gilrs::ff::Effect>>,
+) {
+    use gilrs::ff::{BaseEffect, BaseEffectType, EffectBuilder, Replay, Ticks};
+
+    if explosion_evr.iter().count() == 0 {
+        return;
+    }
+    let Some(mut gilrs) = gilrs else { return; };
+    let ids: Vec<_> = gilrs.gamepads()
+        .filter(|(_, g)| g.is_ff_supported())
+        .map(|(id, _)| id)
+        .collect();
+    if ids.is_empty() {
+        return;
+    }
+    let rumble = EffectBuilder::new()
+        .add_effect(BaseEffect {
+            kind: BaseEffectType::Strong { magnitude: 60_000 },
+            scheduling: Replay { play_for: Ticks::from_ms(400), ..default() },
+            ..default()
+        })
+        .gamepads(&ids)
+        .finish(&mut gilrs)
+        .and_then(|e| e.play().map(|_| e));
+    match rumble {
+        Ok(e) => *effect = Some(e),
+        Err(e) => bevy::log::warn!("Failed to rumble gamepads: {}", e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::input::{gamepad::*, InputPlugin};
+
+    use super::*;
+    use crate::{
+        components::Coordinates,
+        events::{TileMarkEvent, TileTriggerEvent},
+        resources::InputMap,
+        systems::testing::{self, cursor, sent},
+    };
+
+    const PAD: Gamepad = Gamepad { id: 0 };
+
+    fn app() -> App {
+        let mut app = testing::input_app(5, 5, 10.);
+        app.add_plugin(InputPlugin);
+        app.init_resource::<InputMap>();
+        app.add_system(gamepad_cursor_input);
+        let connection = GamepadConnection::Connected(GamepadInfo { name: "Test pad".to_owned() });
+        app.world.send_event(GamepadEvent::Connection(GamepadConnectionEvent::new(PAD, connection)));
+        app.update();
+        app
+    }
+
+    fn set_button(app: &mut App, button: GamepadButtonType, value: f32) {
+        app.world.send_event(GamepadEvent::Button(GamepadButtonChangedEvent::new(PAD, button, value)));
+        app.update();
+    }
+
+    /// Presses and releases a button, a frame each
+    fn tap(app: &mut App, button: GamepadButtonType) {
+        set_button(app, button, 1.);
+        set_button(app, button, 0.);
+    }
+
+    #[test]
+    fn dpad_moves_cursor_within_board() {
+        let mut app = app();
+        tap(&mut app, GamepadButtonType::DPadRight);
+        tap(&mut app, GamepadButtonType::DPadUp);
+        assert_eq!(cursor(&app), Coordinates { x: 1, y: 1 });
+        tap(&mut app, GamepadButtonType::DPadLeft);
+        tap(&mut app, GamepadButtonType::DPadLeft);
+        assert_eq!(cursor(&app), Coordinates { x: 0, y: 1 });
+    }
+
+    #[test]
+    fn held_trigger_jumps_to_edge() {
+        let mut app = app();
+        set_button(&mut app, GamepadButtonType::LeftTrigger, 1.);
+        tap(&mut app, GamepadButtonType::DPadRight);
+        assert_eq!(cursor(&app), Coordinates { x: 4, y: 0 });
+    }
+
+    #[test]
+    fn stick_moves_cursor() {
+        let mut app = app();
+        app.world.send_event(GamepadEvent::Axis(GamepadAxisChangedEvent::new(PAD, GamepadAxisType::LeftStickY, 1.)));
+        app.update();
+        assert_eq!(cursor(&app), Coordinates { x: 0, y: 1 });
+    }
+
+    #[test]
+    fn face_buttons_act_on_cursor_tile() {
+        let mut app = app();
+        tap(&mut app, GamepadButtonType::DPadRight);
+        tap(&mut app, GamepadButtonType::South);
+        assert_eq!(sent(&app, |e: &TileTriggerEvent| e.0), [Coordinates { x: 1, y: 0 }]);
+        tap(&mut app, GamepadButtonType::East);
+        assert_eq!(sent(&app, |e: &TileMarkEvent| e.0), [Coordinates { x: 1, y: 0 }]);
+    }
+}
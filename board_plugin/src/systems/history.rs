@@ -0,0 +1,68 @@
+use bevy::{prelude::*, log};
+
+use crate::{
+    events::UndoEvent,
+    resources::{ActionInput, Board, BoardAssets, BoardChange, BoardOptions, InputAction},
+};
+
+use super::spawn::{spawn_mark, tile_cover_bundle};
+
+pub fn undo_input(
+    input: ActionInput,
+    board: Res<Board>,
+    mut undo_ew: EventWriter<UndoEvent>,
+) {
+    if board.history.undo_enabled && input.just_pressed(InputAction::Undo) {
+        undo_ew.send(UndoEvent);
+    }
+}
+
+/// Reverts the last move, covering uncovered tiles again and restoring marks
+pub fn undo_moves(
+    mut cmd: Commands,
+    mut board: ResMut<Board>,
+    board_assets: Res<BoardAssets>,
+    board_options: Option<Res<BoardOptions>>,
+    mut undo_evr: EventReader<UndoEvent>,
+    children: Query<&Children>,
+) {
+    // Covers and marks are drawn inside the tile padding, as when the board was spawned
+    let size = board.tile_size - board_options.map_or(0., |o| o.tile_padding);
+    for _ in undo_evr.iter() {
+        let Some(changes) = board.history.pop_move() else {
+            log::info!("Nothing to undo");
+            continue;
+        };
+        log::info!("Undoing move of {} changes", changes.len());
+        let multi_bomb = board.tile_map.is_multi_bomb();
+
+        for change in changes.into_iter().rev() {
+            match change {
+                BoardChange::Uncovered { coords, mark } => {
+                    let Some(&tile) = board.tiles.get(&coords) else {
+                        log::error!("No tile at {}", coords);
+                        continue;
+                    };
+                    let mut cover = cmd.spawn(Name::new("Tile Cover"));
+                    cover.insert(tile_cover_bundle(&board_assets, size));
+                    cover.with_children(|parent| {
+                        spawn_mark(parent, mark, multi_bomb, &board_assets, size);
+                    });
+                    let cover = cover.id();
+                    cmd.entity(tile).add_child(cover);
+                    board.recover_tile(coords, cover, mark);
+                }
+                BoardChange::Marked { coords, previous } => {
+                    let Some(&cover) = board.covered_tiles.get(&coords) else { continue; };
+                    for &child in children.get(cover).into_iter().flatten() {
+                        cmd.entity(child).despawn_recursive();
+                    }
+                    cmd.entity(cover).with_children(|parent| {
+                        spawn_mark(parent, previous, multi_bomb, &board_assets, size);
+                    });
+                    board.set_mark(coords, previous);
+                }
+            }
+        }
+    }
+}
@@ -1,37 +1,41 @@
-use bevy::{prelude::{Query, With, Res, EventReader, MouseButton, EventWriter}, window::{Window, PrimaryWindow}, input::{mouse::MouseButtonInput, ButtonState}, log};
+use bevy::{prelude::{Query, With, Res, ResMut, Camera, GlobalTransform}, window::{Window, PrimaryWindow}, log};
 
-use crate::{resources::Board, events::{TileTriggerEvent, TileMarkEvent}};
+use crate::{resources::{ActionInput, Board, BoardOptions, ChordMode, InputAction, TileCursor}, events::{TileTriggerEvent, TileMarkEvent, TileChordEvent, TileActionWriters}};
 
 
 pub fn input_handling(
     windows: Query<&Window, With<PrimaryWindow>>,
+    cameras: Query<(&Camera, &GlobalTransform)>,
     board: Res<Board>,
-    mut button_evr: EventReader<MouseButtonInput>,
-    mut tile_trigger_ew: EventWriter<TileTriggerEvent>,
-    mut tile_mark_ew: EventWriter<TileMarkEvent>,
+    board_options: Res<BoardOptions>,
+    mut cursor: ResMut<TileCursor>,
+    input: ActionInput,
+    mut actions: TileActionWriters,
 ) {
     let window = windows.single();
+    let Ok((camera, camera_transform)) = cameras.get_single() else { return; };
 
-    for event in button_evr.iter() {
-        if let ButtonState::Pressed = event.state {
-            let position = window.cursor_position();
-            if let Some(pos) = position {
-                log::trace!("Mouse button pressed: {:?} at {}", event.button, pos);
-                let tile_coordinates = board.mouse_position(window, pos);
-                if let Some(coordinates) = tile_coordinates {
-                    match event.button {
-                        MouseButton::Left => {
-                            log::info!("Trying to uncover tile on {}", coordinates);
-                            tile_trigger_ew.send(TileTriggerEvent(coordinates));
-                        }
-                        MouseButton::Right => {
-                            log::info!("Trying to mark tile on {}", coordinates);
-                            tile_mark_ew.send(TileMarkEvent(coordinates));
-                        }
-                        _ => (),
-                    }
-                }
-            }
-        }
+    let reveal = input.mouse_just_pressed(InputAction::Reveal);
+    let flag = input.mouse_just_pressed(InputAction::Flag);
+    let chord = input.mouse_just_pressed(InputAction::Chord);
+    if !reveal && !flag && !chord {
+        return;
+    }
+    let Some(pos) = window.cursor_position() else { return; };
+    log::trace!("Mouse action at {}", pos);
+    let Some(coordinates) = board.mouse_position(camera, camera_transform, pos) else { return; };
+
+    // Keep the keyboard cursor where the player last clicked
+    cursor.position = coordinates;
+    if reveal && board.covered_tiles.contains_key(&coordinates) {
+        log::info!("Trying to uncover tile on {}", coordinates);
+        actions.trigger.send(TileTriggerEvent(coordinates));
+    } else if chord || (reveal && board_options.chord == ChordMode::Reveal) {
+        log::info!("Trying to chord on {}", coordinates);
+        actions.chord.send(TileChordEvent(coordinates));
+    }
+    if flag {
+        log::info!("Trying to mark tile on {}", coordinates);
+        actions.mark.send(TileMarkEvent(coordinates));
     }
 }
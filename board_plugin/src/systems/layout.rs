@@ -0,0 +1,86 @@
+use bevy::{prelude::*, window::{PrimaryWindow, WindowResized}, math::Vec3Swizzles, log};
+
+use crate::{
+    bounds::Bounds2,
+    components::{BoardBackground, Coordinates},
+    resources::{Board, BoardOptions, TileSize},
+};
+
+use super::spawn::{adaptative_tile_size, board_position};
+
+/// Recomputes the adaptive tile size when the window is resized, and lays the
+/// existing board out again without regenerating it
+pub fn relayout_board(
+    mut resize_evr: EventReader<WindowResized>,
+    windows: Query<(Entity, &Window), With<PrimaryWindow>>,
+    board_options: Option<Res<BoardOptions>>,
+    mut board: ResMut<Board>,
+    children: Query<&Children>,
+    board_parts: Query<(Option<&Coordinates>, Option<&BoardBackground>)>,
+    mut visuals: Query<(&mut Transform, Option<&mut Sprite>, Option<&mut Text>)>,
+) {
+    let (window_entity, window) = windows.single();
+    if !resize_evr.iter().any(|e| e.window == window_entity) {
+        return;
+    }
+    let options = board_options.map(|o| o.to_owned()).unwrap_or_default();
+    let TileSize::Adaptive { min, max } = options.tile_size else { return; };
+    let (width, height) = (board.tile_map.width(), board.tile_map.height());
+    let old_size = board.tile_size;
+    let new_size = adaptative_tile_size(window, (min, max), (width, height));
+    if new_size == old_size {
+        return;
+    }
+    // Tile contents are all drawn relative to the tile size inside the padding, so scaling them
+    // by its ratio gives the sizes a new board would be spawned with
+    let padding = options.tile_padding;
+    let scale = (new_size - padding) / (old_size - padding);
+    if !scale.is_finite() || scale <= 0. {
+        return;
+    }
+    log::info!("Window resized, tile size {} -> {}", old_size, new_size);
+
+    let board_size = Vec2::new(width as f32 * new_size, height as f32 * new_size);
+    let position = board_position(&options.position, board_size);
+    if let Ok((mut transform, _, _)) = visuals.get_mut(board.entity) {
+        transform.translation = position;
+    }
+
+    for &child in children.get(board.entity).into_iter().flatten() {
+        match board_parts.get(child) {
+            Ok((_, Some(_background))) => {
+                if let Ok((mut transform, Some(mut sprite), _)) = visuals.get_mut(child) {
+                    sprite.custom_size = Some(board_size);
+                    transform.translation = (board_size / 2.).extend(transform.translation.z);
+                }
+            }
+            Ok((Some(coords), _)) => {
+                if let Ok((mut transform, _, _)) = visuals.get_mut(child) {
+                    transform.translation = Vec3::new(
+                        (coords.x as f32 * new_size) + (new_size / 2.),
+                        (coords.y as f32 * new_size) + (new_size / 2.),
+                        transform.translation.z,
+                    );
+                }
+                for entity in std::iter::once(child).chain(children.iter_descendants(child)) {
+                    let Ok((_, sprite, text)) = visuals.get_mut(entity) else { continue; };
+                    if let Some(mut sprite) = sprite {
+                        sprite.custom_size = sprite.custom_size.map(|s| s * scale);
+                    }
+                    if let Some(mut text) = text {
+                        for section in text.sections.iter_mut() {
+                            section.style.font_size *= scale;
+                        }
+                    }
+                }
+            }
+            _ => (),
+        }
+    }
+
+    board.tile_size = new_size;
+    board.bounds = Bounds2 {
+        position: position.xy(),
+        size: board_size,
+    };
+}
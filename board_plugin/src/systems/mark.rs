@@ -1,43 +1,37 @@
 use bevy::{prelude::*, log};
 
-use crate::{events::TileMarkEvent, resources::{Board, BoardAssets}};
+use crate::{events::{TileMarkEvent, BoardCompletedEvent}, resources::{Board, BoardAssets, BoardOptions}};
+
+use super::spawn::spawn_mark;
 
 
 pub fn mark_tiles(
     mut cmd: Commands,
     mut board: ResMut<Board>,
     board_assets: Res<BoardAssets>,
+    board_options: Res<BoardOptions>,
     mut tile_mark_er: EventReader<TileMarkEvent>,
+    mut board_completed_wr: EventWriter<BoardCompletedEvent>,
     query: Query<&Children>,
 ) {
     for evt in tile_mark_er.iter() {
-        if let Some((entity, mark)) = board.try_toggle_mark(&evt.0) {
-            if mark {
-                cmd.entity(entity).with_children(|parent| {
-                    parent
-                        .spawn(Name::new("Flag"))
-                        .insert(SpriteBundle {
-                            texture: board_assets.flag_material.texture.clone(),
-                            sprite: Sprite {
-                                custom_size: Some(Vec2::splat(board.tile_size)),
-                                color: board_assets.flag_material.color,
-                                ..default()
-                            },
-                            ..default()
-                        });
-                });
-            } else {
-                let children = match query.get(entity) {
-                    Ok(c) => c,
-                    Err(e) => {
-                        log::error!("Failed to retrieve flag entity components: {}", e);
-                        continue;
-                    }
-                };
+        board.history.begin_move();
+        if let Some((entity, mark)) = board.try_cycle_mark(&evt.0, board_options.question_marks) {
+            // Clear the previous mark before drawing the new one
+            if let Ok(children) = query.get(entity) {
                 for child in children.iter() {
                     cmd.entity(*child).despawn_recursive();
                 }
             }
+            let multi_bomb = board.tile_map.is_multi_bomb();
+            let size = board.tile_size - board_options.tile_padding;
+            cmd.entity(entity).with_children(|parent| {
+                spawn_mark(parent, mark, multi_bomb, &board_assets, size);
+            });
+            if board.tile_map.is_multi_bomb() && board.is_complete() {
+                log::info!("Board completed");
+                board_completed_wr.send(BoardCompletedEvent { undo_used: board.history.undo_used });
+            }
         }
     }
 
@@ -2,3 +2,74 @@ pub mod input;
 pub mod uncover;
 pub mod spawn;
 pub mod mark;
+pub mod endless;
+pub mod camera;
+pub mod layout;
+pub mod cursor;
+pub mod gamepad;
+pub mod touch;
+pub mod history;
+pub mod game;
+pub mod bot;
+pub mod editor;
+pub mod theme;
+
+/// Helpers for the tests running board systems in a headless `App`
+#[cfg(test)]
+pub(crate) mod testing {
+    use bevy::{ecs::event::Event, prelude::*, utils::HashMap};
+
+    use crate::{
+        bounds::Bounds2,
+        components::Coordinates,
+        events::{TileChordEvent, TileMarkEvent, TileTriggerEvent},
+        resources::{tilemap::TileMap, Board, MoveHistory, TileCursor},
+    };
+
+    /// Board resource over `tile_map` with its bottom left corner at the world origin, every
+    /// tile covered. Tiles and covers are the same stand-in entities
+    pub fn board(tile_map: TileMap, tile_size: f32) -> Board {
+        let (width, height) = (tile_map.width(), tile_map.height());
+        let tiles: HashMap<_, _> = (0..height as i32)
+            .flat_map(|y| (0..width as i32).map(move |x| Coordinates { x, y }))
+            .enumerate()
+            .map(|(i, coords)| (coords, Entity::from_raw(i as u32)))
+            .collect();
+        Board {
+            tile_map,
+            bounds: Bounds2 {
+                position: Vec2::ZERO,
+                size: Vec2::new(width as f32, height as f32) * tile_size,
+            },
+            tile_size,
+            entity: Entity::from_raw(u32::MAX),
+            covered_tiles: tiles.clone(),
+            tiles,
+            marked_tiles: Default::default(),
+            questioned_tiles: Default::default(),
+            history: MoveHistory::new(false),
+        }
+    }
+
+    /// App with the resources and events of the tile input systems, over an empty board
+    pub fn input_app(width: u16, height: u16, tile_size: f32) -> App {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins);
+        app.init_resource::<TileCursor>();
+        app.add_event::<TileTriggerEvent>();
+        app.add_event::<TileMarkEvent>();
+        app.add_event::<TileChordEvent>();
+        app.insert_resource(board(TileMap::empty(width, height), tile_size));
+        app
+    }
+
+    /// Tiles of the events sent during the last two frames
+    pub fn sent<E: Event>(app: &App, coordinates: impl Fn(&E) -> Coordinates) -> Vec<Coordinates> {
+        let events = app.world.resource::<Events<E>>();
+        events.get_reader().iter(events).map(coordinates).collect()
+    }
+
+    pub fn cursor(app: &App) -> Coordinates {
+        app.world.resource::<TileCursor>().position
+    }
+}
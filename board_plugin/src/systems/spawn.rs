@@ -1,25 +1,36 @@
 use bevy::{prelude::*, window::PrimaryWindow, log, utils::HashMap, math::Vec3Swizzles};
 
-use crate::{resources::{BoardOptions, tilemap::TileMap, TileSize, BoardPosition, tile::Tile, Board, BoardAssets}, bounds::Bounds2, components::{Coordinates, BombNeighbor, Bomb, Uncover}};
+use crate::{resources::{BoardOptions, tilemap::TileMap, TileSize, BoardPosition, tile::Tile, Board, BoardAssets, BoardStart, TileMark, TileCursor, MoveHistory, GameTimer, ClickCounts, BoardMetricsTask, FirstMoveTask}, bounds::Bounds2, components::{Coordinates, BombNeighbor, Bomb, Uncover, BoardBackground, Themed}, minefield::{Cell, Generation, Minefield}};
 
 pub fn create_board(
     mut cmd: Commands,
     board_options: Option<Res<BoardOptions>>,
+    board_start: Option<Res<BoardStart>>,
     board_assets: Res<BoardAssets>,
     windows: Query<&Window, With<PrimaryWindow>>,
 ) {
     let window = windows.single();
     let options = board_options.map(|o| o.to_owned()).unwrap_or_default();
 
-    let mut tile_map = TileMap::empty(options.map_size.0, options.map_size.1);
-    tile_map.set_bombs(options.bomb_count);
+    let start = board_start.as_deref().map(|BoardStart(field)| field);
+    let tile_map = match start {
+        Some(field) => field.tile_map().clone(),
+        None => {
+            let mut tile_map = TileMap::empty(options.map_size.0, options.map_size.1);
+            match options.seed {
+                Some(seed) => tile_map.set_bombs_seeded(options.bomb_count, options.max_bombs_per_tile, seed, None),
+                None => tile_map.set_bombs_stacked(options.bomb_count, options.max_bombs_per_tile),
+            }
+            tile_map
+        }
+    };
     #[cfg(feature = "debug")]
     log::info!("{}", tile_map.console_output());
 
     let tile_size = match options.tile_size {
         TileSize::Fixed(s) => s,
         TileSize::Adaptive { min, max } =>
-            adaptative_tile_size(window, (min, max), (options.map_size.0, options.map_size.1))
+            adaptative_tile_size(window, (min, max), (tile_map.width(), tile_map.height()))
     };
 
     let board_size = Vec2::new(
@@ -27,14 +38,12 @@ pub fn create_board(
         tile_map.height() as f32 * tile_size,
     );
     log::info!("board size: {}", board_size);
-    let board_position = match options.position {
-        BoardPosition::Centered { offset } => {
-            Vec3::new(-(board_size.x / 2.), -(board_size.y / 2.), 0.) + offset
-        }
-        BoardPosition::Custom(p) => p,
-    };
+    let board_position = board_position(&options.position, board_size);
 
-    let mut covered_tiles = HashMap::with_capacity((tile_map.width() * tile_map.height()).into());
+    let tile_count = tile_map.width() as usize * tile_map.height() as usize;
+    let mut tiles = HashMap::with_capacity(tile_count);
+    let mut covered_tiles = HashMap::with_capacity(tile_count);
+    let marked_tiles = start.map(|field| field.flags().collect()).unwrap_or_default();
     let mut safe_start = None;
 
     let board_entity = cmd.spawn(Name::new("Board"))
@@ -46,6 +55,8 @@ pub fn create_board(
         .with_children(|board| {
             board
                 .spawn(Name::new("Background"))
+                .insert(BoardBackground)
+                .insert(Themed::Board)
                 .insert(SpriteBundle {
                     sprite: Sprite {
                         color: board_assets.board_material.color,
@@ -57,24 +68,31 @@ pub fn create_board(
                     ..default()
                 });
 
-            spawn_tiles(
+            safe_start = spawn_tiles(
                 board,
                 &tile_map,
                 tile_size,
                 options.tile_padding,
                 &board_assets,
-                &mut covered_tiles,
-                &mut safe_start,
+                start,
+                (&mut tiles, &mut covered_tiles),
             );
         })
         .id();
     
-    if options.safe_start {
-        if let Some(e) = safe_start {
-            cmd.entity(e).insert(Uncover);
+    // A board start already has its tiles uncovered
+    if options.generation == Generation::SafeStart && start.is_none() {
+        if let Some(coords) = safe_start {
+            for coords in tile_map.uncover_order([coords], |c| covered_tiles.contains_key(&c)) {
+                cmd.entity(covered_tiles[&coords]).insert(Uncover);
+            }
         }
     }
     
+    cmd.insert_resource(TileCursor::default());
+    cmd.insert_resource(GameTimer::default());
+    cmd.insert_resource(ClickCounts::default());
+    cmd.insert_resource(BoardMetricsTask::spawn(&tile_map));
     cmd.insert_resource(Board {
         tile_map,
         bounds: Bounds2 {
@@ -83,8 +101,11 @@ pub fn create_board(
         },
         tile_size,
         entity: board_entity,
+        tiles,
         covered_tiles,
-        marked_tiles: Vec::new(),
+        marked_tiles,
+        questioned_tiles: Default::default(),
+        history: MoveHistory::new(options.practice_mode),
     });
 }
 
@@ -94,13 +115,17 @@ fn spawn_tiles(
     size: f32,
     padding: f32,
     board_assets: &BoardAssets,
-    covered_tiles: &mut HashMap<Coordinates, Entity>,
-    safe_start_entity: &mut Option<Entity>,
-) {
+    start: Option<&Minefield>,
+    // Tile and cover entities of the board, filled as they are spawned
+    (tiles, covered_tiles): (&mut HashMap<Coordinates, Entity>, &mut HashMap<Coordinates, Entity>),
+) -> Option<Coordinates> {
+    let mut safe_start = None;
     for (y, line) in tile_map.iter().enumerate() {
         for (x, tile) in line.iter().enumerate() {
-            let coords = Coordinates { x: x as u16, y: y as u16 };
+            let coords = Coordinates { x: x as i32, y: y as i32 };
+            let cell = start.and_then(|field| field.cell(coords)).unwrap_or(Cell::Covered);
             let mut cmd = parent.spawn_empty();
+            tiles.insert(coords, cmd.id());
             cmd
                 .insert(SpriteBundle {
                     sprite: Sprite {
@@ -118,28 +143,28 @@ fn spawn_tiles(
                 })
                 .insert(Name::new(format!("Tile ({}, {})", x, y)))
                 .insert(coords)
+                .insert(Themed::Tile)
                 .with_children(|tile_entity| {
+                    if matches!(cell, Cell::Uncovered(_)) {
+                        return;
+                    }
                     let entity = tile_entity.spawn(Name::new("Tile Cover"))
-                        .insert(SpriteBundle {
-                            sprite: Sprite {
-                                custom_size: Some(Vec2::splat(size - padding)),
-                                color: board_assets.covered_tile_material.color,
-                                ..default()
-                            },
-                            texture: board_assets.covered_tile_material.texture.clone(),
-                            transform: Transform::from_xyz(0., 0., 2.),
-                            ..default()
+                        .insert(tile_cover_bundle(board_assets, size - padding))
+                        .with_children(|cover| {
+                            if let Cell::Flagged(flags) = cell {
+                                spawn_mark(cover, TileMark::Flags(flags), tile_map.is_multi_bomb(), board_assets, size - padding);
+                            }
                         })
                         .id();
                     covered_tiles.insert(coords, entity);
-                    if safe_start_entity.is_none() && *tile == Tile::Empty {
-                        *safe_start_entity = Some(entity);
+                    if safe_start.is_none() && *tile == Tile::Empty {
+                        safe_start = Some(coords);
                     }
                 });
 
             match tile {
-                Tile::Bomb => {
-                    cmd.insert(Bomb)
+                Tile::Bomb(n) => {
+                    cmd.insert(Bomb { count: *n })
                         .with_children(|p| {
                             p.spawn(SpriteBundle {
                                 sprite: Sprite {
@@ -150,7 +175,12 @@ fn spawn_tiles(
                                 transform: Transform::from_xyz(0., 0., 1.),
                                 texture: board_assets.bomb_material.texture.clone(),
                                 ..default()
-                            });
+                            })
+                            .insert(Themed::Bomb);
+                            if *n > 1 {
+                                p.spawn(bomb_count_text_bundle(*n, board_assets, size - padding))
+                                    .insert(Transform::from_xyz(0., 0., 2.));
+                            }
                         });
                 },
                 Tile::BombNeighbor(n) => {
@@ -163,15 +193,28 @@ fn spawn_tiles(
             }
         }
     }
+    safe_start
 }
 
 pub fn despawn_board(mut cmd: Commands, board: Res<Board>) {
     log::info!("despawning board");
     cmd.entity(board.entity).despawn_recursive();
     cmd.remove_resource::<Board>();
+    cmd.remove_resource::<BoardMetricsTask>();
+    cmd.remove_resource::<FirstMoveTask>();
+}
+
+/// World position of the board for the given board size
+pub(crate) fn board_position(position: &BoardPosition, board_size: Vec2) -> Vec3 {
+    match position {
+        BoardPosition::Centered { offset } => {
+            Vec3::new(-(board_size.x / 2.), -(board_size.y / 2.), 0.) + *offset
+        }
+        BoardPosition::Custom(p) => *p,
+    }
 }
 
-fn adaptative_tile_size(
+pub(crate) fn adaptative_tile_size(
     window: &Window,
     (min, max): (f32, f32), // Tile size constraints
     (width, height): (u16, u16), // Tile map dimensions
@@ -181,15 +224,77 @@ fn adaptative_tile_size(
     max_width.min(max_heigth).clamp(min, max)
 }
 
-fn bomb_count_text_bundle(count: u8, board_assets: &BoardAssets, size: f32) -> Text2dBundle {
+pub(crate) fn tile_cover_bundle(board_assets: &BoardAssets, size: f32) -> (SpriteBundle, Themed) {
+    let sprite = SpriteBundle {
+        sprite: Sprite {
+            custom_size: Some(Vec2::splat(size)),
+            color: board_assets.covered_tile_material.color,
+            ..default()
+        },
+        texture: board_assets.covered_tile_material.texture.clone(),
+        transform: Transform::from_xyz(0., 0., 2.),
+        ..default()
+    };
+    (sprite, Themed::CoveredTile)
+}
+
+/// Draws the mark of a tile cover: flags, with their count on multi-mine boards, or a question mark
+pub(crate) fn spawn_mark(
+    cover: &mut ChildBuilder,
+    mark: TileMark,
+    multi_bomb: bool,
+    board_assets: &BoardAssets,
+    tile_size: f32,
+) {
+    match mark {
+        TileMark::None => (),
+        TileMark::Flags(flags) => {
+            cover
+                .spawn(Name::new("Flag"))
+                .insert(SpriteBundle {
+                    texture: board_assets.flag_material.texture.clone(),
+                    sprite: Sprite {
+                        custom_size: Some(Vec2::splat(tile_size)),
+                        color: board_assets.flag_material.color,
+                        ..default()
+                    },
+                    ..default()
+                })
+                .insert(Themed::Flag);
+            if multi_bomb {
+                cover
+                    .spawn(Name::new("Flag Count"))
+                    .insert(bomb_count_text_bundle(flags, board_assets, tile_size / 2.));
+            }
+        }
+        TileMark::Question => {
+            let style = TextStyle {
+                font: board_assets.bomb_counter_font.clone(),
+                font_size: tile_size / 2.,
+                color: board_assets.flag_material.color,
+            };
+            cover
+                .spawn(Name::new("Question Mark"))
+                .insert(Text2dBundle {
+                    text: Text::from_section("?", style).with_alignment(TextAlignment::Center),
+                    transform: Transform::from_xyz(0., 0., 1.),
+                    ..default()
+                })
+                .insert(Themed::QuestionMark);
+        }
+    }
+}
+
+pub(crate) fn bomb_count_text_bundle(count: u8, board_assets: &BoardAssets, size: f32) -> (Text2dBundle, Themed) {
     let text = count.to_string();
     let color = board_assets.bomb_counter_color(count);
     let style = TextStyle { font: board_assets.bomb_counter_font.clone(), font_size: size, color };
     let alignment = TextAlignment::Center;
 
-    Text2dBundle {
+    let text = Text2dBundle {
         text: Text::from_section(text, style).with_alignment(alignment),
         transform: Transform::from_xyz(0., 0., 1.),
         ..default()
-    }
+    };
+    (text, Themed::Count(count))
 }
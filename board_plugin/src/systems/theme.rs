@@ -0,0 +1,32 @@
+use bevy::prelude::*;
+
+use crate::{components::Themed, resources::BoardAssets};
+
+/// Draws the themed sprites and texts again with the current board assets: all of them when
+/// the theme changes, otherwise the ones that changed part
+pub fn restyle_board(
+    board_assets: Option<Res<BoardAssets>>,
+    mut sprites: Query<(Ref<Themed>, &mut Sprite, &mut Handle<Image>)>,
+    mut texts: Query<(Ref<Themed>, &mut Text)>,
+) {
+    let Some(board_assets) = board_assets else { return; };
+    let all = board_assets.is_changed();
+    for (part, mut sprite, mut texture) in sprites.iter_mut() {
+        if !all && !part.is_changed() {
+            continue;
+        }
+        let Some(material) = board_assets.material(*part) else { continue; };
+        sprite.color = material.color;
+        *texture = material.texture.clone();
+    }
+    for (part, mut text) in texts.iter_mut() {
+        if !all && !part.is_changed() {
+            continue;
+        }
+        let Some(color) = board_assets.text_color(*part) else { continue; };
+        for section in text.sections.iter_mut() {
+            section.style.font = board_assets.bomb_counter_font.clone();
+            section.style.color = color;
+        }
+    }
+}
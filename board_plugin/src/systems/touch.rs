@@ -0,0 +1,266 @@
+use bevy::{prelude::*, ecs::system::SystemParam, input::touch::{TouchInput, TouchPhase}, utils::HashMap, log};
+
+use crate::{
+    events::{TileActionWriters, TileChordEvent, TileMarkEvent, TileTriggerEvent},
+    resources::{Board, BoardOptions, ChordMode, TileCursor, TouchOptions},
+};
+
+use super::camera::{zoom_around, WindowCamera};
+
+/// A finger on the screen, tracked from the moment it touched down
+#[derive(Debug, Copy, Clone)]
+struct TrackedTouch {
+    start: Vec2,
+    position: Vec2,
+    started_at: f64,
+    /// Moved beyond the tap slop or joined by another finger, so it is a camera gesture
+    gesture: bool,
+    /// Already flagged its tile by being held
+    long_pressed: bool,
+}
+
+/// Touches currently on the screen, by touch id
+#[derive(Debug, Default)]
+pub struct TouchTracker {
+    touches: HashMap<u64, TrackedTouch>,
+}
+
+impl TouchTracker {
+    fn update(&mut self, event: &TouchInput, now: f64, options: &TouchOptions) -> Option<TrackedTouch> {
+        match event.phase {
+            TouchPhase::Started => {
+                let multi_touch = !self.touches.is_empty();
+                for touch in self.touches.values_mut() {
+                    touch.gesture = true;
+                }
+                self.touches.insert(event.id, TrackedTouch {
+                    start: event.position,
+                    position: event.position,
+                    started_at: now,
+                    gesture: multi_touch,
+                    long_pressed: false,
+                });
+                None
+            }
+            TouchPhase::Moved => {
+                if let Some(touch) = self.touches.get_mut(&event.id) {
+                    touch.position = event.position;
+                    touch.gesture |= touch.position.distance(touch.start) > options.tap_slop;
+                }
+                None
+            }
+            TouchPhase::Ended => self.touches.remove(&event.id),
+            TouchPhase::Cancelled => {
+                self.touches.remove(&event.id);
+                None
+            }
+        }
+    }
+}
+
+/// Touch events of the frame, applied to the touches tracked by the system
+#[derive(SystemParam)]
+pub struct TouchGestures<'w, 's> {
+    touch_evr: EventReader<'w, 's, TouchInput>,
+    time: Res<'w, Time>,
+    options: Res<'w, TouchOptions>,
+    tracker: Local<'s, TouchTracker>,
+}
+
+impl TouchGestures<'_, '_> {
+    /// Reads the touch events of the frame, returning the touches that were lifted
+    fn update(&mut self) -> Vec<TrackedTouch> {
+        let now = self.time.elapsed_seconds_f64();
+        let Self { touch_evr, options, tracker, .. } = self;
+        touch_evr.iter()
+            .filter_map(|event| tracker.update(event, now, options))
+            .collect()
+    }
+
+    /// Touches held in place long enough to count as a long press, reported once each
+    fn long_presses(&mut self) -> Vec<TrackedTouch> {
+        let now = self.time.elapsed_seconds_f64();
+        let long_press = self.options.long_press_duration as f64;
+        self.tracker.touches.values_mut()
+            .filter(|touch| !touch.gesture && !touch.long_pressed && now - touch.started_at >= long_press)
+            .map(|touch| {
+                touch.long_pressed = true;
+                *touch
+            })
+            .collect()
+    }
+}
+
+/// Tap to uncover a tile or chord a revealed number, unless chording is left to the chord
+/// action, hold to flag a tile
+pub fn touch_tile_input(
+    mut gestures: TouchGestures,
+    view: WindowCamera,
+    board: Res<Board>,
+    board_options: Res<BoardOptions>,
+    mut cursor: ResMut<TileCursor>,
+    mut actions: TileActionWriters,
+) {
+    let tile_at = |position: Vec2| view.touch_to_world(position).and_then(|p| board.world_position(p));
+
+    for touch in gestures.update() {
+        if touch.gesture || touch.long_pressed {
+            continue;
+        }
+        let Some(coordinates) = tile_at(touch.position) else { continue; };
+        cursor.position = coordinates;
+        if board.covered_tiles.contains_key(&coordinates) {
+            log::info!("Trying to uncover tile on {}", coordinates);
+            actions.trigger.send(TileTriggerEvent(coordinates));
+        } else if board_options.chord == ChordMode::Reveal {
+            log::info!("Trying to chord on {}", coordinates);
+            actions.chord.send(TileChordEvent(coordinates));
+        }
+    }
+
+    // Long presses flag while the finger is still down, so the player feels it happen
+    for touch in gestures.long_presses() {
+        let Some(coordinates) = tile_at(touch.position) else { continue; };
+        cursor.position = coordinates;
+        log::info!("Trying to mark tile on {}", coordinates);
+        actions.mark.send(TileMarkEvent(coordinates));
+    }
+}
+
+/// Drag one finger to pan the camera, pinch two fingers to zoom
+pub fn touch_camera_input(
+    mut gestures: TouchGestures,
+    view: WindowCamera,
+    mut cameras: Query<(&mut Transform, &mut OrthographicProjection), With<Camera>>,
+) {
+    let previous = gestures.tracker.touches.clone();
+    gestures.update();
+    let tracker = &gestures.tracker;
+
+    // Only fingers present in both frames contribute to the gesture
+    let mut moved: Vec<_> = tracker.touches.iter()
+        .filter(|(_, touch)| touch.gesture)
+        .filter_map(|(id, touch)| previous.get(id).map(|old| (*id, old.position, touch.position)))
+        .collect();
+    moved.sort_by_key(|(id, _, _)| *id);
+
+    match moved.as_slice() {
+        [(_, old, new)] if tracker.touches.len() == 1 => {
+            // Window y axis points down, world y axis points up
+            let delta = Vec2::new(old.x - new.x, new.y - old.y);
+            for (mut transform, projection) in cameras.iter_mut() {
+                transform.translation += (delta * projection.scale).extend(0.);
+            }
+        }
+        [(_, old_a, new_a), (_, old_b, new_b), ..] => {
+            let (old_distance, new_distance) = (old_a.distance(*old_b), new_a.distance(*new_b));
+            if old_distance <= 0. || new_distance <= 0. {
+                return;
+            }
+            let anchor = view.touch_to_world((*new_a + *new_b) / 2.);
+            for (mut transform, mut projection) in cameras.iter_mut() {
+                zoom_around(&mut transform, &mut projection, anchor, old_distance / new_distance);
+            }
+        }
+        _ => (),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use bevy::{
+        asset::AssetPlugin,
+        input::touch::TouchInput,
+        render::camera::CameraProjectionPlugin,
+        time::TimeUpdateStrategy,
+        window::WindowPlugin,
+    };
+
+    use super::*;
+    use crate::{
+        components::Coordinates,
+        systems::testing::{self, sent},
+    };
+
+    /// Window position of the center of a tile: the camera is centered on the board origin
+    /// of a default 1280x720 window, with 10 pixel tiles
+    fn tile_center(x: f32, y: f32) -> Vec2 {
+        Vec2::new(640. + (x + 0.5) * 10., 360. - (y + 0.5) * 10.)
+    }
+
+    /// Headless app with a primary window and a camera, see `update` for time
+    fn app() -> App {
+        let mut app = testing::input_app(5, 5, 10.);
+        app.add_plugin(WindowPlugin::default());
+        app.add_plugin(TransformPlugin);
+        app.add_plugin(AssetPlugin::default());
+        app.add_asset::<Image>();
+        app.add_plugin(CameraProjectionPlugin::<OrthographicProjection>::default());
+        app.add_event::<TouchInput>();
+        app.init_resource::<BoardOptions>();
+        app.init_resource::<TouchOptions>();
+        app.insert_resource(TimeUpdateStrategy::ManualInstant(Instant::now()));
+        app.add_systems((touch_tile_input, touch_camera_input));
+        app.world.spawn(Camera2dBundle::default());
+        update(&mut app);
+        app
+    }
+
+    /// Runs a frame lasting 100ms
+    fn update(app: &mut App) {
+        if let TimeUpdateStrategy::ManualInstant(instant) = app.world.resource_mut::<TimeUpdateStrategy>().as_mut() {
+            *instant += Duration::from_millis(100);
+        }
+        app.update();
+    }
+
+    fn touch(app: &mut App, id: u64, phase: TouchPhase, position: Vec2) {
+        app.world.send_event(TouchInput { phase, position, force: None, id });
+    }
+
+    #[test]
+    fn tap_uncovers_tile() {
+        let mut app = app();
+        touch(&mut app, 0, TouchPhase::Started, tile_center(2., 1.));
+        update(&mut app);
+        touch(&mut app, 0, TouchPhase::Ended, tile_center(2., 1.));
+        update(&mut app);
+        assert_eq!(sent(&app, |e: &TileTriggerEvent| e.0), [Coordinates { x: 2, y: 1 }]);
+        assert!(sent(&app, |e: &TileMarkEvent| e.0).is_empty());
+    }
+
+    #[test]
+    fn long_press_flags_tile() {
+        let mut app = app();
+        touch(&mut app, 0, TouchPhase::Started, tile_center(3., 3.));
+        let mut frames = 0;
+        while sent(&app, |e: &TileMarkEvent| e.0).is_empty() {
+            assert!(frames < 10, "no long press after {} frames", frames);
+            update(&mut app);
+            frames += 1;
+        }
+        // Flagged once held for the long press duration, 500ms
+        assert!(frames >= 5, "long press after {} frames", frames);
+        assert_eq!(sent(&app, |e: &TileMarkEvent| e.0), [Coordinates { x: 3, y: 3 }]);
+        touch(&mut app, 0, TouchPhase::Ended, tile_center(3., 3.));
+        update(&mut app);
+        assert!(sent(&app, |e: &TileTriggerEvent| e.0).is_empty());
+    }
+
+    #[test]
+    fn pinch_zooms_camera() {
+        let mut app = app();
+        touch(&mut app, 0, TouchPhase::Started, Vec2::new(600., 360.));
+        touch(&mut app, 1, TouchPhase::Started, Vec2::new(680., 360.));
+        update(&mut app);
+        touch(&mut app, 0, TouchPhase::Moved, Vec2::new(560., 360.));
+        touch(&mut app, 1, TouchPhase::Moved, Vec2::new(720., 360.));
+        update(&mut app);
+        let mut projections = app.world.query::<&OrthographicProjection>();
+        let scale = projections.single(&app.world).scale;
+        assert!((scale - 0.5).abs() < 1e-4, "scale {}", scale);
+        assert!(sent(&app, |e: &TileTriggerEvent| e.0).is_empty());
+    }
+}
@@ -1,15 +1,90 @@
 use bevy::{prelude::*, log};
 
-use crate::{events::{TileTriggerEvent, BoardCompletedEvent, BombExplosionEvent}, resources::Board, components::{Uncover, BombNeighbor, Coordinates, Bomb}};
+use crate::{
+    BoardState,
+    events::{TileTriggerEvent, TileChordEvent, BoardCompletedEvent, BombExplosionEvent},
+    resources::{Board, BoardOptions, BoardStart, FirstMoveTask},
+    components::{Uncover, Coordinates, Bomb},
+    minefield::{Generation, MinefieldOptions},
+};
 
 pub fn trigger_event_handler(
     mut cmd: Commands,
-    board: Res<Board>,
+    mut board: ResMut<Board>,
+    board_options: Res<BoardOptions>,
+    board_start: Option<Res<BoardStart>>,
+    first_move_task: Option<Res<FirstMoveTask>>,
     mut tile_trigger_evr: EventReader<TileTriggerEvent>,
 ) {
+    // Moves wait for the layout of the first one
+    if first_move_task.is_some() {
+        tile_trigger_evr.clear();
+        return;
+    }
+    let first_move = board_start.is_none()
+        && board.covered_tiles.len() == board.tile_map.width() as usize * board.tile_map.height() as usize
+        && matches!(board_options.generation, Generation::SafeFirstMove | Generation::NoGuess);
     for event in tile_trigger_evr.iter() {
-        if let Some(tile_cover_entity) = board.get_covered_tile(&event.0) {
-            cmd.entity(*tile_cover_entity).insert(Uncover);
+        if first_move && board.get_covered_tile(&event.0).is_some() {
+            log::info!("Placing mines away from the first move on {}", event.0);
+            cmd.insert_resource(FirstMoveTask::spawn(first_move_options(&board, &board_options), event.0));
+            return;
+        }
+        let entities = board.uncover_targets([event.0]);
+        if !entities.is_empty() {
+            board.history.begin_move();
+        }
+        for entity in entities {
+            cmd.entity(entity).insert(Uncover);
+        }
+    }
+}
+
+/// Options of a new layout like the board's, to be generated keeping the first move and
+/// its neighbors clear
+fn first_move_options(board: &Board, options: &BoardOptions) -> MinefieldOptions {
+    let tile_map = &board.tile_map;
+    MinefieldOptions {
+        map_size: (tile_map.width(), tile_map.height()),
+        bomb_count: tile_map.bomb_count(),
+        max_bombs_per_tile: tile_map.max_bombs_per_tile(),
+        seed: options.seed.unwrap_or_else(rand::random),
+        generation: options.generation,
+    }
+}
+
+/// Spawns the board again from the game of the first move once its layout is generated.
+/// Flags placed so far are kept
+pub fn start_first_move(
+    mut cmd: Commands,
+    board: Res<Board>,
+    first_move_task: Option<ResMut<FirstMoveTask>>,
+    mut next_board_state: ResMut<NextState<BoardState>>,
+) {
+    let Some(mut task) = first_move_task else { return; };
+    let Some(mut field) = task.poll() else { return; };
+    for (&flagged, &flags) in board.marked_tiles.iter() {
+        for _ in 0..flags {
+            field.cycle_flag(flagged);
+        }
+    }
+    cmd.remove_resource::<FirstMoveTask>();
+    cmd.insert_resource(BoardStart(field));
+    next_board_state.set(BoardState::Active);
+}
+
+pub fn chord_event_handler(
+    mut cmd: Commands,
+    mut board: ResMut<Board>,
+    mut tile_chord_evr: EventReader<TileChordEvent>,
+) {
+    for event in tile_chord_evr.iter() {
+        let entities = board.chord_tiles(&event.0);
+        if !entities.is_empty() {
+            board.history.begin_move();
+        }
+        for entity in entities {
+            cmd.entity(entity).insert(Uncover);
         }
     }
 }
@@ -18,14 +93,14 @@ pub fn uncover_tiles(
     mut cmd: Commands,
     mut board: ResMut<Board>,
     children: Query<(Entity, &Parent), With<Uncover>>,
-    parents: Query<(&Coordinates, Option<&Bomb>, Option<&BombNeighbor>)>,
+    parents: Query<(&Coordinates, Option<&Bomb>)>,
     mut board_completed_wr: EventWriter<BoardCompletedEvent>,
     mut explosion_wr: EventWriter<BombExplosionEvent>,
 ) {
     for (entity, parent) in children.iter() {
         cmd.entity(entity).despawn_recursive();
 
-        let (coords, bomb, bomb_counter) = match parents.get(parent.get()) {
+        let (coords, bomb) = match parents.get(parent.get()) {
             Ok(v) => v,
             Err(e) => {
                 log::error!("{}", e);
@@ -36,18 +111,50 @@ pub fn uncover_tiles(
             None => log::debug!("Tried to uncover an already uncovered tile"),
             Some(e) => log::debug!("Uncovered tile {} (entity: {:?})", coords, e),
         }
-        if board.is_complete() {
+        // Uncovering a bomb leaves as many covered tiles as safe ones, it is not a win
+        if bomb.is_none() && board.is_complete() {
             log::info!("Board completed");
-            board_completed_wr.send(BoardCompletedEvent);
+            board_completed_wr.send(BoardCompletedEvent { undo_used: board.history.undo_used });
         }
         if bomb.is_some() {
             log::info!("Boom !");
             explosion_wr.send(BombExplosionEvent(*coords));
         }
-        else if bomb_counter.is_none() {
-            for &entity in board.adjacent_covered_tiles(coords) {
-                cmd.entity(entity).insert(Uncover);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{resources::tilemap::TileMap, systems::testing};
+
+    #[test]
+    fn first_move_layout_is_generated_in_the_background() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins);
+        app.add_state::<BoardState>();
+        app.add_event::<TileTriggerEvent>();
+        app.insert_resource(BoardOptions { generation: Generation::NoGuess, seed: Some(0), ..default() });
+        let mut tile_map = TileMap::empty(9, 9);
+        tile_map.set_bombs(10);
+        app.insert_resource(testing::board(tile_map, 10.));
+        app.add_systems((trigger_event_handler, start_first_move).chain());
+
+        let start = Coordinates { x: 4, y: 4 };
+        app.world.send_event(TileTriggerEvent(start));
+        app.update();
+        // Later moves wait for the layout of the first one
+        app.world.send_event(TileTriggerEvent(Coordinates { x: 0, y: 0 }));
+        for _ in 0..500 {
+            if app.world.contains_resource::<BoardStart>() {
+                break;
             }
+            std::thread::sleep(std::time::Duration::from_millis(10));
+            app.update();
         }
+        assert!(!app.world.contains_resource::<FirstMoveTask>());
+        let BoardStart(field) = app.world.get_resource::<BoardStart>().expect("no layout after 5s");
+        assert!(field.is_uncovered(start));
+        assert_eq!(field.tile_map().bomb_count(), 10);
     }
 }
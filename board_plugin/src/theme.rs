@@ -0,0 +1,153 @@
+//! Board themes, loaded from `.theme.ron` asset files:
+//!
+//! ```ron
+//! (
+//!     name: "Default",
+//!     font: "fonts/pixeled.ttf",
+//!     board: (color: "ffffff"),
+//!     covered: (color: "808080"),
+//!     flag: (texture: Some("sprites/flag.png")),
+//!     number_colors: ["ffffff", "00ff00"],
+//! )
+//! ```
+//!
+//! Colors are hex strings and paths are relative to the assets folder. A missing material is
+//! plain white, missing number colors are the default palette.
+use std::fmt;
+
+use bevy::{
+    asset::{AssetLoader, AssetPath, LoadContext, LoadedAsset},
+    prelude::*,
+    reflect::TypeUuid,
+    render::color::HexColorError,
+    utils::BoxedFuture,
+};
+use serde::Deserialize;
+
+use crate::resources::{BoardAssets, SpriteMaterial};
+
+/// Board assets of a theme file. Must be used as an asset, see `ThemeLoader`
+#[derive(Debug, Clone, TypeUuid)]
+#[uuid = "a4d1c8e2-6f3b-4e57-8c29-1b7e0d9f5a36"]
+pub struct Theme(pub BoardAssets);
+
+#[derive(Debug, Deserialize)]
+struct ThemeFile {
+    name: String,
+    font: String,
+    #[serde(default)]
+    board: ThemeMaterial,
+    #[serde(default)]
+    revealed: ThemeMaterial,
+    #[serde(default)]
+    covered: ThemeMaterial,
+    #[serde(default)]
+    flag: ThemeMaterial,
+    #[serde(default)]
+    bomb: ThemeMaterial,
+    #[serde(default)]
+    wrong_flag: ThemeMaterial,
+    #[serde(default)]
+    exploded: ThemeMaterial,
+    /// Colors of the mine counts, from 1
+    #[serde(default)]
+    number_colors: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+struct ThemeMaterial {
+    color: String,
+    texture: Option<String>,
+}
+
+impl Default for ThemeMaterial {
+    fn default() -> Self {
+        Self { color: "ffffff".to_owned(), texture: None }
+    }
+}
+
+#[derive(Debug)]
+pub enum ThemeError {
+    Parse(ron::error::SpannedError),
+    Color(String, HexColorError),
+}
+
+impl fmt::Display for ThemeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Parse(e) => write!(f, "invalid theme: {}", e),
+            Self::Color(color, e) => write!(f, "invalid color {:?}: {}", color, e),
+        }
+    }
+}
+
+impl std::error::Error for ThemeError {}
+
+fn color(hex: &str) -> Result<Color, ThemeError> {
+    Color::hex(hex).map_err(|e| ThemeError::Color(hex.to_owned(), e))
+}
+
+/// Loads `Theme` assets from `.theme.ron` files, along with the textures and font they use
+#[derive(Debug, Default)]
+pub struct ThemeLoader;
+
+impl AssetLoader for ThemeLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), bevy::asset::Error>> {
+        Box::pin(async move {
+            let file: ThemeFile = ron::de::from_bytes(bytes).map_err(ThemeError::Parse)?;
+            let mut dependencies = Vec::new();
+            let mut dependency = |path: &str| {
+                let path = AssetPath::new(path.into(), None);
+                dependencies.push(path.clone());
+                path
+            };
+
+            let mut material = |material: &ThemeMaterial| -> Result<SpriteMaterial, ThemeError> {
+                Ok(SpriteMaterial {
+                    color: color(&material.color)?,
+                    texture: match &material.texture {
+                        Some(path) => load_context.get_handle(dependency(path)),
+                        None => SpriteMaterial::default().texture,
+                    },
+                })
+            };
+            let board_material = material(&file.board)?;
+            let tile_material = material(&file.revealed)?;
+            let covered_tile_material = material(&file.covered)?;
+            let flag_material = material(&file.flag)?;
+            let bomb_material = material(&file.bomb)?;
+            let wrong_flag_material = material(&file.wrong_flag)?;
+            let exploded_material = material(&file.exploded)?;
+            let bomb_counter_font = load_context.get_handle(dependency(&file.font));
+
+            let bomb_counter_colors = match file.number_colors.is_empty() {
+                true => BoardAssets::default_colors(),
+                false => file.number_colors.iter().map(|c| color(c)).collect::<Result<_, _>>()?,
+            };
+
+            let theme = Theme(BoardAssets {
+                label: file.name,
+                board_material,
+                tile_material,
+                covered_tile_material,
+                bomb_counter_font,
+                bomb_counter_colors,
+                flag_material,
+                bomb_material,
+                wrong_flag_material,
+                exploded_material,
+            });
+            load_context.set_default_asset(LoadedAsset::new(theme).with_dependencies(dependencies));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["theme.ron"]
+    }
+}
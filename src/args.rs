@@ -0,0 +1,45 @@
+use board_plugin::{
+    minefield::{Generation, MinefieldOptions},
+    resources::{tilemap::MAX_BOMBS_PER_TILE, Difficulty},
+};
+use clap::Args;
+
+/// Board generation options common to the command line tools
+#[derive(Debug, Clone, Args)]
+pub struct BoardArgs {
+    /// Difficulty preset, overriding the size and bomb count
+    #[arg(long)]
+    pub difficulty: Option<Difficulty>,
+    #[arg(long, default_value_t = 20)]
+    pub width: u16,
+    #[arg(long, default_value_t = 20)]
+    pub height: u16,
+    #[arg(long, default_value_t = 40)]
+    pub bombs: u16,
+    /// Maximum number of mines a single tile can hold
+    #[arg(long, default_value_t = 1, value_parser = clap::value_parser!(u8).range(1..=MAX_BOMBS_PER_TILE as i64))]
+    pub max_per_tile: u8,
+    /// Board seed, the same seed and options always give the same board
+    #[arg(long)]
+    pub seed: Option<u64>,
+    /// Mine placement: random, safe-start, safe-first-move or no-guess
+    #[arg(long, default_value = "random")]
+    pub generation: Generation,
+}
+
+impl BoardArgs {
+    /// Options of the board generated from `seed`, overriding the `--seed` argument
+    pub fn options(&self, seed: u64) -> MinefieldOptions {
+        let (map_size, bomb_count) = match self.difficulty {
+            Some(difficulty) => difficulty.parameters(),
+            None => ((self.width, self.height), self.bombs),
+        };
+        MinefieldOptions {
+            map_size,
+            bomb_count,
+            max_bombs_per_tile: self.max_per_tile,
+            seed,
+            generation: self.generation,
+        }
+    }
+}
@@ -0,0 +1,221 @@
+//! Plays many games with a bot on every difficulty, in parallel, and reports how it did.
+//!
+//! Game `i` of a run is played on the board generated from seed `seed_start + i`, so two
+//! runs with the same options play the same boards and can be compared.
+
+use std::{
+    fs::File,
+    io::{self, Write},
+    path::PathBuf,
+    process::ExitCode,
+    time::Instant,
+};
+
+use board_plugin::{
+    minefield::{GameStatus, Generation, Minefield, MinefieldOptions, Move},
+    player::PlayerKind,
+    resources::Difficulty,
+};
+use clap::{Parser, ValueEnum};
+use rayon::prelude::*;
+use serde::Serialize;
+
+/// Two-sided 95% normal quantile, for the win rate confidence intervals
+const Z_95: f64 = 1.96;
+/// Moves after which a game is given up, so a bot stuck in a loop can't hang the run
+const MAX_MOVES: u32 = 100_000;
+
+/// Runs bot games on seeded boards and reports win rates
+#[derive(Debug, Parser)]
+struct Args {
+    /// Bot to play with: random, single-cell or solver
+    #[arg(long, default_value = "solver")]
+    player: PlayerKind,
+    /// Difficulties to play, every preset when missing
+    #[arg(long)]
+    difficulty: Vec<Difficulty>,
+    /// Games per difficulty
+    #[arg(long, default_value_t = 1000)]
+    games: u64,
+    /// Seed of the first game
+    #[arg(long, default_value_t = 0)]
+    seed_start: u64,
+    /// Mine placement: random, safe-start, safe-first-move or no-guess
+    #[arg(long, default_value = "safe-first-move")]
+    generation: Generation,
+    /// Worker threads, one per core when missing
+    #[arg(long)]
+    threads: Option<usize>,
+    #[arg(long, value_enum, default_value_t = Format::Text)]
+    format: Format,
+    /// File to write the report to, stdout when missing
+    #[arg(long)]
+    output: Option<PathBuf>,
+}
+
+#[derive(Debug, Copy, Clone, ValueEnum)]
+enum Format {
+    Text,
+    Csv,
+    Json,
+}
+
+/// Outcome of a single game
+struct GameRecord {
+    won: bool,
+    moves: u32,
+    guesses: u32,
+    seconds: f64,
+}
+
+/// Results of the games played on one difficulty
+#[derive(Debug, Serialize)]
+struct Summary {
+    difficulty: String,
+    player: String,
+    generation: String,
+    seed_start: u64,
+    games: u64,
+    wins: u64,
+    win_rate: f64,
+    /// Wilson score interval of the win rate, at 95% confidence
+    win_rate_low: f64,
+    win_rate_high: f64,
+    average_seconds: f64,
+    average_moves: f64,
+    average_guesses: f64,
+    /// Share of the games won without a single guess
+    no_guess_rate: f64,
+}
+
+fn play(player: PlayerKind, options: &MinefieldOptions) -> GameRecord {
+    let start = Instant::now();
+    let mut field = Minefield::generate(options);
+    let mut bot = player.create(options.seed);
+    // The first reveal can't explode on these boards, whatever the bot thinks of it
+    let mut safe_first_move = matches!(options.generation, Generation::SafeFirstMove | Generation::NoGuess);
+    let (mut moves, mut guesses) = (0, 0);
+    while field.status() == GameStatus::Playing && moves < MAX_MOVES {
+        let Some(decision) = bot.next_move(&field.view()) else { break; };
+        let first_reveal = matches!(decision.action, Move::Reveal(_)) && std::mem::take(&mut safe_first_move);
+        if decision.is_guess() && !first_reveal {
+            guesses += 1;
+        }
+        field.play(decision.action);
+        moves += 1;
+    }
+    GameRecord {
+        won: field.status() == GameStatus::Won,
+        moves,
+        guesses,
+        seconds: start.elapsed().as_secs_f64(),
+    }
+}
+
+fn wilson_interval(wins: u64, games: u64) -> (f64, f64) {
+    if games == 0 {
+        return (0., 0.);
+    }
+    let (n, p) = (games as f64, wins as f64 / games as f64);
+    let z2 = Z_95 * Z_95;
+    let center = (p + z2 / (2. * n)) / (1. + z2 / n);
+    let margin = Z_95 / (1. + z2 / n) * (p * (1. - p) / n + z2 / (4. * n * n)).sqrt();
+    ((center - margin).max(0.), (center + margin).min(1.))
+}
+
+fn run_difficulty(args: &Args, difficulty: Difficulty) -> Summary {
+    let (map_size, bomb_count) = difficulty.parameters();
+    let records: Vec<GameRecord> = (0..args.games)
+        .into_par_iter()
+        .map(|i| {
+            let options = MinefieldOptions {
+                map_size,
+                bomb_count,
+                max_bombs_per_tile: 1,
+                seed: args.seed_start.wrapping_add(i),
+                generation: args.generation,
+            };
+            play(args.player, &options)
+        })
+        .collect();
+
+    let games = records.len() as u64;
+    let wins = records.iter().filter(|r| r.won).count() as u64;
+    let average = |value: fn(&GameRecord) -> f64| {
+        if games == 0 { 0. } else { records.iter().map(value).sum::<f64>() / games as f64 }
+    };
+    let (win_rate_low, win_rate_high) = wilson_interval(wins, games);
+    Summary {
+        difficulty: difficulty.label().to_owned(),
+        player: args.player.to_string(),
+        generation: format!("{:?}", args.generation),
+        seed_start: args.seed_start,
+        games,
+        wins,
+        win_rate: if games == 0 { 0. } else { wins as f64 / games as f64 },
+        win_rate_low,
+        win_rate_high,
+        average_seconds: average(|r| r.seconds),
+        average_moves: average(|r| r.moves as f64),
+        average_guesses: average(|r| r.guesses as f64),
+        no_guess_rate: average(|r| (r.won && r.guesses == 0) as u8 as f64),
+    }
+}
+
+fn write_report(out: &mut impl Write, format: Format, summaries: &[Summary]) -> io::Result<()> {
+    match format {
+        Format::Text => {
+            for s in summaries {
+                writeln!(
+                    out,
+                    "{:<12} {} on {} boards: won {}/{} ({:.1}%, 95% CI {:.1}-{:.1}%), {:.2} ms/game, {:.1} moves, {:.2} guesses, {:.1}% won without guessing",
+                    s.difficulty, s.player, s.generation, s.wins, s.games,
+                    s.win_rate * 100., s.win_rate_low * 100., s.win_rate_high * 100.,
+                    s.average_seconds * 1000., s.average_moves, s.average_guesses, s.no_guess_rate * 100.,
+                )?;
+            }
+        }
+        Format::Csv => {
+            writeln!(out, "difficulty,player,generation,seed_start,games,wins,win_rate,win_rate_low,win_rate_high,average_seconds,average_moves,average_guesses,no_guess_rate")?;
+            for s in summaries {
+                writeln!(
+                    out,
+                    "{},{},{},{},{},{},{:.6},{:.6},{:.6},{:.6},{:.3},{:.3},{:.6}",
+                    s.difficulty, s.player, s.generation, s.seed_start, s.games, s.wins,
+                    s.win_rate, s.win_rate_low, s.win_rate_high,
+                    s.average_seconds, s.average_moves, s.average_guesses, s.no_guess_rate,
+                )?;
+            }
+        }
+        Format::Json => {
+            serde_json::to_writer_pretty(&mut *out, summaries)?;
+            writeln!(out)?;
+        }
+    }
+    out.flush()
+}
+
+fn main() -> ExitCode {
+    let args = Args::parse();
+    if let Some(threads) = args.threads {
+        if let Err(e) = rayon::ThreadPoolBuilder::new().num_threads(threads).build_global() {
+            eprintln!("Failed to start {} worker threads: {}", threads, e);
+            return ExitCode::FAILURE;
+        }
+    }
+    let difficulties = match args.difficulty.is_empty() {
+        true => Difficulty::ALL.to_vec(),
+        false => args.difficulty.clone(),
+    };
+    let summaries: Vec<Summary> = difficulties.into_iter().map(|d| run_difficulty(&args, d)).collect();
+
+    let result = match &args.output {
+        Some(path) => File::create(path).and_then(|mut file| write_report(&mut file, args.format, &summaries)),
+        None => write_report(&mut io::stdout().lock(), args.format, &summaries),
+    };
+    if let Err(e) = result {
+        eprintln!("Failed to write the report: {}", e);
+        return ExitCode::FAILURE;
+    }
+    ExitCode::SUCCESS
+}
@@ -0,0 +1,196 @@
+//! Plays a list of moves on a seeded board and prints the result.
+//!
+//! Moves are read one per line from a file or stdin: `r 3 4` reveals, `f 5 6` cycles flags
+//! and `c 2 2` chords, with `x` from the left and `y` from the bottom. Blank lines and lines
+//! starting with `#` are ignored.
+//!
+//! With `--step`, the board is printed once before the first move and again after every
+//! move, so a bot can play through a pipe: each state is the board followed by a status
+//! line (`playing`, `won` or `lost`). A move that is unreadable, outside of the board, changes
+//! nothing or comes once the game is over is answered with `error <reason>` instead.
+//!
+//! `--daily <date>` plays the daily challenge of that day instead, and `--check-golden`
+//! checks that seeded boards still come out exactly as they used to. `--puzzle <file>`
+//! plays a puzzle file from its start state, and `--check-puzzles` checks that every
+//! bundled puzzle can be cleared without guessing.
+
+use std::{
+    fs::File,
+    io::{self, BufRead, BufReader, Write},
+    path::PathBuf,
+    process::ExitCode,
+};
+
+use board_plugin::{
+    daily::{self, Date},
+    minefield::{GameStatus, Minefield, Move},
+    puzzle::{self, Puzzle},
+    resources::Difficulty,
+};
+use clap::Parser;
+use minesweeper::args::BoardArgs;
+
+/// Replays minesweeper moves on a seeded board
+#[derive(Debug, Parser)]
+struct Args {
+    #[command(flatten)]
+    board: BoardArgs,
+    /// File to read moves from, stdin when missing
+    #[arg(long)]
+    moves: Option<PathBuf>,
+    /// Print the board after every move, for bots playing through a pipe
+    #[arg(long)]
+    step: bool,
+    /// Play the daily board of this day (YYYY-MM-DD), on the `--difficulty` preset
+    #[arg(long, conflicts_with = "seed")]
+    daily: Option<Date>,
+    /// Play this puzzle file instead of a generated board
+    #[arg(long, conflicts_with_all = ["seed", "daily"])]
+    puzzle: Option<PathBuf>,
+    /// Check that seeded boards still match their golden layouts, then exit
+    #[arg(long, exclusive = true)]
+    check_golden: bool,
+    /// Check that every bundled puzzle can be cleared without guessing, then exit
+    #[arg(long, exclusive = true)]
+    check_puzzles: bool,
+}
+
+fn status_line(field: &Minefield) -> &'static str {
+    match field.status() {
+        GameStatus::Playing => "playing",
+        GameStatus::Won => "won",
+        GameStatus::Lost => "lost",
+    }
+}
+
+fn print_state(out: &mut impl Write, field: &Minefield) -> io::Result<()> {
+    write!(out, "{}", field)?;
+    writeln!(out, "{}", status_line(field))?;
+    out.flush()
+}
+
+fn run(args: &Args, input: impl BufRead, out: &mut impl Write) -> io::Result<Result<(), String>> {
+    let (mut field, source) = match (&args.puzzle, args.daily) {
+        (Some(path), _) => {
+            let puzzle = match Puzzle::load(path) {
+                Ok(puzzle) => puzzle,
+                Err(e) => return Ok(Err(format!("Failed to load {}: {}", path.display(), e))),
+            };
+            match puzzle.minefield() {
+                Ok(field) => (field, format!("puzzle {}", puzzle.name)),
+                Err(e) => return Ok(Err(e)),
+            }
+        }
+        (None, daily) => {
+            let options = match daily {
+                Some(date) => daily::daily_options(date, args.board.difficulty.unwrap_or(Difficulty::Intermediate)),
+                None => args.board.options(args.board.seed.unwrap_or(0)),
+            };
+            (Minefield::generate(&options), format!("seed {}", options.seed))
+        }
+    };
+    writeln!(
+        out,
+        "board {}x{} bombs {} {}",
+        field.width(), field.height(), field.tile_map().bomb_count(), source,
+    )?;
+    if args.step {
+        print_state(out, &field)?;
+    }
+
+    let mut ignored = 0;
+    for (number, line) in input.lines().enumerate() {
+        let line = line?;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if field.status() != GameStatus::Playing {
+            if args.step {
+                writeln!(out, "error game over")?;
+                out.flush()?;
+            }
+            ignored += 1;
+            continue;
+        }
+        let action = match line.parse::<Move>() {
+            Ok(action) => action,
+            Err(e) if args.step => {
+                writeln!(out, "error {}", e)?;
+                out.flush()?;
+                continue;
+            }
+            Err(e) => return Ok(Err(format!("line {}: {}", number + 1, e))),
+        };
+        let changed = field.play(action);
+        if !args.step {
+            continue;
+        }
+        if !field.in_bounds(action.coordinates()) {
+            writeln!(out, "error {} is outside of the board", action.coordinates())?;
+            out.flush()?;
+        } else if !changed {
+            writeln!(out, "error {} changes nothing", action)?;
+            out.flush()?;
+        } else {
+            print_state(out, &field)?;
+        }
+    }
+
+    if !args.step {
+        print_state(out, &field)?;
+    }
+    if ignored > 0 && !args.step {
+        eprintln!("Ignored {} moves after the end of the game", ignored);
+    }
+    Ok(Ok(()))
+}
+
+fn main() -> ExitCode {
+    let args = Args::parse();
+    if args.check_golden {
+        return match daily::check_golden_layouts() {
+            Ok(count) => {
+                println!("{} golden layouts match", count);
+                ExitCode::SUCCESS
+            }
+            Err(e) => {
+                eprintln!("Golden layout mismatch: {}", e);
+                ExitCode::FAILURE
+            }
+        };
+    }
+    if args.check_puzzles {
+        return match puzzle::check_puzzle_pack() {
+            Ok(count) => {
+                println!("{} puzzles clear without guessing", count);
+                ExitCode::SUCCESS
+            }
+            Err(e) => {
+                eprintln!("Invalid puzzle: {}", e);
+                ExitCode::FAILURE
+            }
+        };
+    }
+    let input: Box<dyn BufRead> = match &args.moves {
+        Some(path) => match File::open(path) {
+            Ok(file) => Box::new(BufReader::new(file)),
+            Err(e) => {
+                eprintln!("Failed to open {}: {}", path.display(), e);
+                return ExitCode::from(2);
+            }
+        },
+        None => Box::new(io::stdin().lock()),
+    };
+    match run(&args, input, &mut io::stdout().lock()) {
+        Ok(Ok(())) => ExitCode::SUCCESS,
+        Ok(Err(e)) => {
+            eprintln!("{}", e);
+            ExitCode::from(2)
+        }
+        Err(e) => {
+            eprintln!("{}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,48 @@
+//! Headless server hosting game rooms for the terminal frontend and any other client.
+//!
+//! Players create a room with `tui --join <address> --create-room coop` (or `versus`),
+//! then share its code so others join with `tui --join <address> --room <code>`. A player
+//! whose connection drops keeps their seat for `--grace` seconds and takes it back when
+//! their client reconnects.
+
+use std::{net::TcpListener, process::ExitCode, time::Duration};
+
+use clap::Parser;
+use minesweeper::net::server::{self, ServerOptions};
+
+/// Hosts minesweeper rooms over TCP
+#[derive(Debug, Parser)]
+struct Args {
+    /// Address to listen on
+    #[arg(long, default_value = "0.0.0.0:7878")]
+    listen: String,
+    /// Most rooms open at once
+    #[arg(long, default_value_t = 100)]
+    max_rooms: usize,
+    /// Seconds the seat of a player whose connection dropped is kept
+    #[arg(long, default_value_t = 30)]
+    grace: u64,
+    /// Don't print rooms opening and closing
+    #[arg(long)]
+    quiet: bool,
+}
+
+fn main() -> ExitCode {
+    let args = Args::parse();
+    let listener = match TcpListener::bind(&args.listen) {
+        Ok(listener) => listener,
+        Err(e) => {
+            eprintln!("Failed to listen on {}: {}", args.listen, e);
+            return ExitCode::FAILURE;
+        }
+    };
+    if !args.quiet {
+        eprintln!("Listening on {}", listener.local_addr().map_or(args.listen.clone(), |a| a.to_string()));
+    }
+    server::serve(listener, ServerOptions {
+        max_rooms: args.max_rooms,
+        reconnect_grace: Duration::from_secs(args.grace),
+        log: !args.quiet,
+    });
+    ExitCode::SUCCESS
+}
@@ -0,0 +1,114 @@
+//! Daily challenge, sharing its official attempts and streaks with the game.
+
+use std::time::Duration;
+
+use board_plugin::{
+    components::Coordinates,
+    daily::{daily_options, Date},
+    minefield::{Cell, GameStatus, Minefield, Move},
+    resources::{tile::Tile, DailyChallenge, DailyOutcome, DailyResults, Difficulty},
+};
+
+use crate::{Clock, Game};
+
+/// The board of the day, the first game on it being the official attempt
+pub struct DailyGame {
+    challenge: DailyChallenge,
+    field: Minefield,
+    clock: Clock,
+    results: DailyResults,
+    /// Why the results couldn't be saved
+    error: Option<String>,
+}
+
+impl DailyGame {
+    /// Daily challenge of today, its official attempt recorded in `results`
+    pub fn new(difficulty: Difficulty, results: DailyResults) -> Self {
+        let date = Date::today();
+        let mut game = Self {
+            challenge: DailyChallenge { date, difficulty, official: false },
+            field: Minefield::generate(&daily_options(date, difficulty)),
+            clock: Clock::default(),
+            results,
+            error: None,
+        };
+        game.challenge.official = game.results.start(difficulty, date);
+        if game.challenge.official {
+            game.save();
+        }
+        game
+    }
+
+    fn save(&mut self) {
+        self.error = self.results.save().err().map(|e| format!("Failed to save {}: {}", self.results.path().display(), e));
+    }
+}
+
+impl Game for DailyGame {
+    fn width(&self) -> u16 {
+        self.field.width()
+    }
+
+    fn height(&self) -> u16 {
+        self.field.height()
+    }
+
+    fn cell(&self, coords: Coordinates) -> Option<Cell> {
+        self.field.cell(coords)
+    }
+
+    fn mine(&self, coords: Coordinates) -> Option<Tile> {
+        self.field.tile_map().get(coords).copied().filter(Tile::is_bomb)
+    }
+
+    fn status(&self) -> GameStatus {
+        self.field.status()
+    }
+
+    fn bombs_left(&self) -> i64 {
+        self.field.tile_map().bomb_count() as i64 - self.field.flag_count() as i64
+    }
+
+    fn is_multi_bomb(&self) -> bool {
+        false
+    }
+
+    fn elapsed(&self) -> Duration {
+        self.clock.elapsed()
+    }
+
+    fn title(&self) -> String {
+        let DailyChallenge { date, difficulty, official } = self.challenge;
+        let title = format!("Daily {} {}", difficulty.label(), date);
+        match (&self.error, official) {
+            (Some(error), _) => format!("{}   {}", title, error),
+            (None, true) => format!("{}   Streak {}", title, self.results.streak(difficulty, date)),
+            (None, false) => format!("{}   Replay, only the first attempt counts", title),
+        }
+    }
+
+    fn play(&mut self, action: Move) {
+        let changed = self.field.play(action);
+        self.clock.update(changed, self.field.status());
+        let won = match self.field.status() {
+            GameStatus::Playing => return,
+            status => status == GameStatus::Won,
+        };
+        let outcome = DailyOutcome { won, time: self.clock.elapsed().as_secs_f32() };
+        if self.challenge.official && self.results.finish(self.challenge.difficulty, self.challenge.date, outcome) {
+            self.save();
+        }
+    }
+
+    /// Plays the same board again, unofficially
+    fn new_game(&mut self) {
+        let DailyChallenge { date, difficulty, .. } = self.challenge;
+        self.field = Minefield::generate(&daily_options(date, difficulty));
+        self.clock = Clock::default();
+        self.challenge.official = false;
+    }
+
+    fn start(&self) -> Option<Coordinates> {
+        self.field.safe_start()
+    }
+}
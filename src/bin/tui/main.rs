@@ -0,0 +1,636 @@
+//! Terminal frontend, playing the board rules without any window or GPU.
+//!
+//! Mouse: left click reveals (or chords on a number), right click flags, middle click chords.
+//! Keyboard: arrows/WASD/HJKL move the cursor (with Shift to jump 5 tiles), Space/Enter
+//! reveals, F/M flags, X chords, G starts a new game and Q/Escape quits.
+//!
+//! With `--host <address>` the board is shared with the players joining it with
+//! `--join <address>`, each of them seeing the others' cursors and flags in their colour.
+//! Adding `--versus` makes them race on copies of the same board instead, and `--versus`
+//! alone splits the terminal between two players racing side by side.
+//!
+//! `--publish <address>` plays alone while others watch with `--spectate <address>`, which
+//! also works on any hosted game. `--delay` holds the stream back a few seconds.
+//!
+//! `--daily` plays the daily challenge of the `--difficulty` preset, intermediate by default,
+//! sharing its official attempts and streaks with the game.
+//!
+//! On a room server (the `server` binary), `--join <address> --create-room coop` (or
+//! `versus`) opens a room with the board options and shows its code, which the others pass
+//! with `--room <code>` to `--join` or `--spectate`. A dropped connection is retried until
+//! the host gives the seat up.
+
+use std::{
+    io::{self, Stdout, Write},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use board_plugin::{
+    components::Coordinates,
+    minefield::{Cell, GameStatus, Generation, Minefield, Move},
+    resources::{tile::{count_char, Tile}, DailyResults, Difficulty},
+};
+use clap::Parser;
+use minesweeper::{
+    args::BoardArgs,
+    net::{
+        client::Greeting,
+        protocol::{Mode, PlayerId, RoomRequest},
+        session::Session,
+    },
+};
+use crossterm::{
+    cursor::{Hide, MoveTo, Show},
+    event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers, MouseButton, MouseEvent, MouseEventKind},
+    queue,
+    style::{Attribute, Color, Print, ResetColor, SetAttribute, SetBackgroundColor, SetForegroundColor},
+    terminal::{self, Clear, ClearType, EnterAlternateScreen, LeaveAlternateScreen},
+};
+
+use online::OnlineGame;
+use daily::DailyGame;
+use versus::RaceSide;
+
+mod daily;
+mod online;
+mod versus;
+
+/// Tiles moved by a cursor jump
+const JUMP: i32 = 5;
+/// Terminal columns per tile, so the board looks roughly square
+const TILE_WIDTH: u16 = 2;
+/// Board position in a screen, below the status line
+const BOARD_ORIGIN: (u16, u16) = (1, 2);
+/// Columns of the side panel right of each board
+const PANEL_WIDTH: u16 = 32;
+/// Time between two redraws without input, keeping the timer and other players current
+const FRAME: Duration = Duration::from_millis(50);
+/// Colour of each player on a shared board, the first one also used for local flags
+const PLAYER_COLORS: [Color; 6] = [Color::Magenta, Color::Blue, Color::Green, Color::Yellow, Color::Cyan, Color::Red];
+
+/// Plays minesweeper in the terminal
+#[derive(Debug, Parser)]
+struct Args {
+    #[command(flatten)]
+    board: BoardArgs,
+    /// Shares the board with other players, listening on this address (like 0.0.0.0:7878)
+    #[arg(long, conflicts_with_all = ["join", "spectate", "publish"])]
+    host: Option<String>,
+    /// Plays on the board of a host, at this address
+    #[arg(long, conflicts_with_all = ["spectate", "publish"])]
+    join: Option<String>,
+    /// Plays alone while others can watch, listening on this address
+    #[arg(long, conflicts_with_all = ["spectate", "versus"])]
+    publish: Option<String>,
+    /// Watches the game of a host, at this address
+    #[arg(long)]
+    spectate: Option<String>,
+    /// Seconds a spectator stays behind the game
+    #[arg(long, default_value_t = 0., requires = "spectate")]
+    delay: f32,
+    /// Room to join or watch, on a server hosting rooms
+    #[arg(long, conflicts_with_all = ["host", "publish", "create_room"])]
+    room: Option<String>,
+    /// Opens a room in coop or versus mode on the server given to `--join`
+    #[arg(long, requires = "join")]
+    create_room: Option<Mode>,
+    /// Name shown to the other players
+    #[arg(long)]
+    name: Option<String>,
+    /// Races on copies of the same board, over the network with `--host` or split-screen
+    #[arg(long, conflicts_with_all = ["join", "spectate"])]
+    versus: bool,
+    /// Plays the daily challenge, the same board for everyone today
+    #[arg(long, conflicts_with_all = ["host", "join", "publish", "spectate", "versus"])]
+    daily: bool,
+}
+
+fn player_color(player: PlayerId) -> Color {
+    PLAYER_COLORS[(player as usize).saturating_sub(1) % PLAYER_COLORS.len()]
+}
+
+/// A board the terminal can show and play, on its own or shared with other players
+trait Game {
+    fn width(&self) -> u16;
+    fn height(&self) -> u16;
+    /// Tile as seen by the player, `None` outside of the map
+    fn cell(&self, coords: Coordinates) -> Option<Cell>;
+    /// Mine on a tile, only asked once the game is over
+    fn mine(&self, coords: Coordinates) -> Option<Tile>;
+    fn status(&self) -> GameStatus;
+    /// Bombs minus flags
+    fn bombs_left(&self) -> i64;
+    fn is_multi_bomb(&self) -> bool;
+    fn elapsed(&self) -> Duration;
+    /// End of the status line, after the counter and timer
+    fn title(&self) -> String;
+    fn play(&mut self, action: Move);
+    fn new_game(&mut self);
+
+    /// Tile to put the cursor on at the start
+    fn start(&self) -> Option<Coordinates> {
+        None
+    }
+
+    fn cursor_moved(&mut self, _coords: Coordinates) {}
+
+    /// Catches up with anything that happened outside of the terminal input
+    fn update(&mut self) {}
+
+    fn flag_color(&self, _coords: Coordinates) -> Color {
+        PLAYER_COLORS[0]
+    }
+
+    /// Colour of another player's cursor on a tile
+    fn other_cursor(&self, _coords: Coordinates) -> Option<Color> {
+        None
+    }
+
+    /// Lines shown right of the board, with their colour
+    fn panel(&self) -> Vec<(String, Color)> {
+        Vec::new()
+    }
+}
+
+/// Game time, from the first change to the end of the game
+#[derive(Debug, Default)]
+struct Clock {
+    started: Option<Instant>,
+    finished: Option<Duration>,
+}
+
+impl Clock {
+    /// Clock starting at a given time, like the end of a race countdown
+    fn starting_at(start: Instant) -> Self {
+        Self { started: Some(start), finished: None }
+    }
+
+    fn elapsed(&self) -> Duration {
+        match (self.finished, self.started) {
+            (Some(time), _) => time,
+            (None, Some(start)) => Instant::now().saturating_duration_since(start),
+            (None, None) => Duration::ZERO,
+        }
+    }
+
+    /// Starts on the first change and stops when the game ends
+    fn update(&mut self, changed: bool, status: GameStatus) {
+        if changed && self.started.is_none() {
+            self.started = Some(Instant::now());
+        }
+        if status != GameStatus::Playing && self.finished.is_none() {
+            self.finished = Some(self.elapsed());
+        }
+    }
+}
+
+/// A game played alone on a seeded board
+struct LocalGame {
+    board: BoardArgs,
+    field: Minefield,
+    seed: u64,
+    clock: Clock,
+}
+
+impl LocalGame {
+    fn new(board: BoardArgs, seed: u64) -> Self {
+        let field = Minefield::generate(&board.options(seed));
+        Self { board, field, seed, clock: Clock::default() }
+    }
+}
+
+impl Game for LocalGame {
+    fn width(&self) -> u16 {
+        self.field.width()
+    }
+
+    fn height(&self) -> u16 {
+        self.field.height()
+    }
+
+    fn cell(&self, coords: Coordinates) -> Option<Cell> {
+        self.field.cell(coords)
+    }
+
+    fn mine(&self, coords: Coordinates) -> Option<Tile> {
+        self.field.tile_map().get(coords).copied().filter(Tile::is_bomb)
+    }
+
+    fn status(&self) -> GameStatus {
+        self.field.status()
+    }
+
+    fn bombs_left(&self) -> i64 {
+        self.field.tile_map().bomb_count() as i64 - self.field.flag_count() as i64
+    }
+
+    fn is_multi_bomb(&self) -> bool {
+        self.field.tile_map().is_multi_bomb()
+    }
+
+    fn elapsed(&self) -> Duration {
+        self.clock.elapsed()
+    }
+
+    fn title(&self) -> String {
+        format!("Seed {}", self.seed)
+    }
+
+    fn play(&mut self, action: Move) {
+        let changed = self.field.play(action);
+        self.clock.update(changed, self.field.status());
+    }
+
+    fn new_game(&mut self) {
+        *self = Self::new(self.board.clone(), self.seed.wrapping_add(1));
+    }
+
+    fn start(&self) -> Option<Coordinates> {
+        self.field.safe_start().filter(|_| self.board.generation == Generation::SafeStart)
+    }
+}
+
+/// Keys driving a screen
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Controls {
+    /// Every key, for a single board
+    Solo,
+    /// WASD moves, E reveals and R flags: the left board of a split screen
+    Left,
+    /// Arrows or IJKL move, Enter or U reveals and O flags: the right board of a split screen
+    Right,
+}
+
+/// What a key does on a screen
+enum Command {
+    Move(i32, i32),
+    Reveal,
+    Flag,
+    Chord,
+}
+
+impl Controls {
+    fn command(&self, key: KeyEvent) -> Option<Command> {
+        let step = if key.modifiers.contains(KeyModifiers::SHIFT) { JUMP } else { 1 };
+        let letter = match key.code {
+            KeyCode::Char(c) => Some(c.to_ascii_lowercase()),
+            _ => None,
+        };
+        let command = match (self, key.code, letter) {
+            (Self::Solo | Self::Right, KeyCode::Up, _) => Command::Move(0, step),
+            (Self::Solo | Self::Right, KeyCode::Down, _) => Command::Move(0, -step),
+            (Self::Solo | Self::Right, KeyCode::Left, _) => Command::Move(-step, 0),
+            (Self::Solo | Self::Right, KeyCode::Right, _) => Command::Move(step, 0),
+            (Self::Solo | Self::Right, KeyCode::Enter, _) => Command::Reveal,
+            (Self::Solo, _, Some(c)) => match c {
+                'w' | 'k' => Command::Move(0, step),
+                's' | 'j' => Command::Move(0, -step),
+                'a' | 'h' => Command::Move(-step, 0),
+                'd' | 'l' => Command::Move(step, 0),
+                ' ' => Command::Reveal,
+                'f' | 'm' => Command::Flag,
+                'x' => Command::Chord,
+                _ => return None,
+            },
+            (Self::Left, _, Some(c)) => match c {
+                'w' => Command::Move(0, step),
+                's' => Command::Move(0, -step),
+                'a' => Command::Move(-step, 0),
+                'd' => Command::Move(step, 0),
+                'e' => Command::Reveal,
+                'r' => Command::Flag,
+                _ => return None,
+            },
+            (Self::Right, _, Some(c)) => match c {
+                'i' => Command::Move(0, step),
+                'k' => Command::Move(0, -step),
+                'j' => Command::Move(-step, 0),
+                'l' => Command::Move(step, 0),
+                'u' => Command::Reveal,
+                'o' => Command::Flag,
+                _ => return None,
+            },
+            _ => return None,
+        };
+        Some(command)
+    }
+}
+
+/// A game, its cursor and where it is drawn
+struct Screen {
+    game: Box<dyn Game>,
+    cursor: Coordinates,
+    controls: Controls,
+    /// First terminal column of the screen
+    left: u16,
+}
+
+impl Screen {
+    fn new(game: Box<dyn Game>, controls: Controls, left: u16) -> Self {
+        let cursor = game.start()
+            .unwrap_or(Coordinates { x: game.width() as i32 / 2, y: game.height() as i32 / 2 });
+        Self { game, cursor, controls, left }
+    }
+
+    /// Columns taken by the board and its side panel
+    fn width(&self) -> u16 {
+        BOARD_ORIGIN.0 + self.game.width() * TILE_WIDTH + 2 + PANEL_WIDTH
+    }
+
+    fn board_origin(&self) -> (u16, u16) {
+        (self.left + BOARD_ORIGIN.0, BOARD_ORIGIN.1)
+    }
+
+    fn move_cursor(&mut self, dx: i32, dy: i32) {
+        let (width, height) = (self.game.width() as i32, self.game.height() as i32);
+        self.set_cursor(Coordinates {
+            x: (self.cursor.x + dx).clamp(0, width - 1),
+            y: (self.cursor.y + dy).clamp(0, height - 1),
+        });
+    }
+
+    fn set_cursor(&mut self, coords: Coordinates) {
+        if coords != self.cursor {
+            self.cursor = coords;
+            self.game.cursor_moved(coords);
+        }
+    }
+
+    fn run(&mut self, command: Command) {
+        let cursor = self.cursor;
+        match command {
+            Command::Move(dx, dy) => self.move_cursor(dx, dy),
+            Command::Reveal => self.reveal(cursor),
+            Command::Flag => self.game.play(Move::Flag(cursor)),
+            Command::Chord => self.game.play(Move::Chord(cursor)),
+        }
+    }
+
+    /// Reveals a covered tile, or chords on an uncovered one
+    fn reveal(&mut self, coords: Coordinates) {
+        match self.game.cell(coords) {
+            Some(Cell::Uncovered(_)) => self.game.play(Move::Chord(coords)),
+            _ => self.game.play(Move::Reveal(coords)),
+        }
+    }
+
+    fn new_game(&mut self) {
+        self.game.new_game();
+        let cursor = self.game.start().unwrap_or(self.cursor);
+        self.set_cursor(cursor);
+    }
+
+    /// Board coordinates under a terminal position
+    fn screen_to_tile(&self, column: u16, row: u16) -> Option<Coordinates> {
+        let origin = self.board_origin();
+        let x = column.checked_sub(origin.0)? / TILE_WIDTH;
+        let row = row.checked_sub(origin.1)?;
+        let height = self.game.height();
+        if x >= self.game.width() || row >= height {
+            return None;
+        }
+        // The first row of the map is at the bottom, as in the window
+        Some(Coordinates { x: x as i32, y: (height - 1 - row) as i32 })
+    }
+
+    /// Draws the status line, board and side panel. A screen sharing the terminal keeps
+    /// to its own columns
+    fn draw(&self, out: &mut Stdout, shared: bool) -> io::Result<()> {
+        let status = match self.game.status() {
+            GameStatus::Playing => "",
+            GameStatus::Won => "  Board cleared!",
+            GameStatus::Lost => "  Boom!",
+        };
+        let status_line = format!(
+            "Bombs {:>3}   Time {:>5.1}s   {}{}",
+            self.game.bombs_left(), self.game.elapsed().as_secs_f32(), self.game.title(), status,
+        );
+        queue!(out, MoveTo(self.left, 0))?;
+        if shared {
+            queue!(out, Print(fit(&status_line, self.width())))?;
+        } else {
+            queue!(out, Print(status_line), Clear(ClearType::UntilNewLine))?;
+        }
+
+        let origin = self.board_origin();
+        let height = self.game.height() as i32;
+        for y in (0..height).rev() {
+            queue!(out, MoveTo(origin.0, origin.1 + (height - 1 - y) as u16))?;
+            for x in 0..self.game.width() as i32 {
+                let coords = Coordinates { x, y };
+                let (symbol, color) = self.tile_symbol(coords);
+                let background = match coords == self.cursor {
+                    true => Some(Color::DarkGrey),
+                    false => self.game.other_cursor(coords),
+                };
+                if let Some(background) = background {
+                    queue!(out, SetBackgroundColor(background))?;
+                }
+                queue!(out, SetForegroundColor(color), Print(format!("{:>width$}", symbol, width = TILE_WIDTH as usize)))?;
+                queue!(out, SetAttribute(Attribute::Reset), ResetColor)?;
+            }
+        }
+
+        // Blank the whole panel height so shorter updates leave nothing behind
+        let panel_left = origin.0 + self.game.width() * TILE_WIDTH + 2;
+        let lines = self.game.panel();
+        for row in 0..lines.len().max(height as usize) {
+            let (text, color) = lines.get(row).map_or(("", Color::Reset), |(text, color)| (text.as_str(), *color));
+            queue!(out, MoveTo(panel_left, origin.1 + row as u16), SetForegroundColor(color), Print(fit(text, PANEL_WIDTH)), ResetColor)?;
+        }
+        Ok(())
+    }
+
+    /// Character and colour of a tile, matching `Tile::console_output`
+    fn tile_symbol(&self, coords: Coordinates) -> (char, Color) {
+        let over = self.game.status() != GameStatus::Playing;
+        match self.game.cell(coords) {
+            Some(Cell::Uncovered(tile)) => tile_char(tile),
+            // Show the remaining mines once the game is over
+            Some(Cell::Covered) if over => match self.game.mine(coords) {
+                Some(tile) => tile_char(tile),
+                None => ('·', Color::Grey),
+            },
+            Some(Cell::Flagged(1)) if !self.game.is_multi_bomb() => ('F', self.game.flag_color(coords)),
+            Some(Cell::Flagged(n)) => (count_char(n), self.game.flag_color(coords)),
+            _ => ('·', Color::Grey),
+        }
+    }
+}
+
+/// Pads or cuts a text to exactly `width` characters
+fn fit(text: &str, width: u16) -> String {
+    let width = width as usize;
+    format!("{:<width$.width$}", text, width = width)
+}
+
+fn tile_char(tile: Tile) -> (char, Color) {
+    match tile {
+        Tile::Bomb(1) => ('*', Color::Red),
+        Tile::Bomb(n) => (count_char(n), Color::Red),
+        Tile::BombNeighbor(1) => ('1', Color::DarkCyan),
+        Tile::BombNeighbor(2) => ('2', Color::DarkGreen),
+        Tile::BombNeighbor(3) => ('3', Color::DarkYellow),
+        Tile::BombNeighbor(n) => (count_char(n), Color::DarkRed),
+        Tile::Empty => (' ', Color::Reset),
+    }
+}
+
+/// Restores the terminal when the game exits, even on a panic
+struct TerminalGuard;
+
+impl TerminalGuard {
+    fn enter(out: &mut Stdout) -> io::Result<Self> {
+        terminal::enable_raw_mode()?;
+        crossterm::execute!(out, EnterAlternateScreen, EnableMouseCapture, Hide)?;
+        Ok(Self)
+    }
+}
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        let _ = crossterm::execute!(io::stdout(), Show, DisableMouseCapture, LeaveAlternateScreen);
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+/// What to do after an input
+enum Flow {
+    Continue,
+    NewGame,
+    Quit,
+}
+
+fn handle_key(screens: &mut [Screen], key: KeyEvent) -> Flow {
+    if key.kind == KeyEventKind::Release {
+        return Flow::Continue;
+    }
+    match key.code {
+        KeyCode::Char('q' | 'Q') | KeyCode::Esc => return Flow::Quit,
+        KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => return Flow::Quit,
+        KeyCode::Char('g' | 'G') => return Flow::NewGame,
+        _ => (),
+    }
+    for screen in screens {
+        if let Some(command) = screen.controls.command(key) {
+            screen.run(command);
+        }
+    }
+    Flow::Continue
+}
+
+fn handle_mouse(screens: &mut [Screen], mouse: MouseEvent) {
+    let MouseEventKind::Down(button) = mouse.kind else { return; };
+    for screen in screens {
+        let Some(coords) = screen.screen_to_tile(mouse.column, mouse.row) else { continue; };
+        // Keep the keyboard cursor where the player last clicked
+        screen.set_cursor(coords);
+        match button {
+            MouseButton::Left => screen.reveal(coords),
+            MouseButton::Right => screen.game.play(Move::Flag(coords)),
+            MouseButton::Middle => screen.game.play(Move::Chord(coords)),
+        }
+    }
+}
+
+fn help(screens: &[Screen]) -> &'static str {
+    match screens {
+        [_] => "Arrows/WASD/HJKL move, Space reveal, F flag, X chord, G new game, Q quit",
+        _ => "Left: WASD move, E reveal, R flag   Right: arrows/IJKL move, Enter/U reveal, O flag   G rematch, Q quit",
+    }
+}
+
+fn draw(screens: &[Screen], out: &mut Stdout) -> io::Result<()> {
+    let shared = screens.len() > 1;
+    for screen in screens {
+        screen.draw(out, shared)?;
+    }
+    let bottom = screens.iter().map(|s| s.game.height()).max().unwrap_or(0);
+    queue!(out, MoveTo(0, BOARD_ORIGIN.1 + bottom + 1), Print(help(screens)), Clear(ClearType::UntilNewLine))?;
+    out.flush()
+}
+
+fn time_seed() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_nanos() as u64)
+}
+
+fn run(screens: &mut [Screen], out: &mut Stdout) -> io::Result<()> {
+    loop {
+        for screen in screens.iter_mut() {
+            screen.game.update();
+        }
+        draw(screens, out)?;
+        if !event::poll(FRAME)? {
+            continue;
+        }
+        let flow = match event::read()? {
+            Event::Key(key) => handle_key(screens, key),
+            Event::Mouse(mouse) => {
+                handle_mouse(screens, mouse);
+                Flow::Continue
+            }
+            Event::Resize(..) => {
+                queue!(out, Clear(ClearType::All))?;
+                Flow::Continue
+            }
+            _ => Flow::Continue,
+        };
+        match flow {
+            Flow::Continue => (),
+            Flow::NewGame => {
+                for screen in screens.iter_mut() {
+                    screen.new_game();
+                }
+                queue!(out, Clear(ClearType::All))?;
+            }
+            Flow::Quit => return Ok(()),
+        }
+    }
+}
+
+fn main() -> io::Result<()> {
+    let args = Args::parse();
+    let name = args.name.clone()
+        .or_else(|| std::env::var("USER").ok())
+        .unwrap_or_else(|| "player".to_owned());
+    let seed = args.board.seed.unwrap_or_else(time_seed);
+    let mode = if args.versus { Mode::Versus } else { Mode::Coop };
+    // Connect before taking over the terminal, so connection errors stay readable
+    let options = args.board.options(seed);
+    let games: Vec<Box<dyn Game>> = if let Some(address) = &args.host {
+        vec![Box::new(OnlineGame::host(address, Session::new(mode, options), &name)?)]
+    } else if let Some(address) = &args.publish {
+        vec![Box::new(OnlineGame::host(address, Session::new(Mode::Coop, options).limit_players(1), &name)?)]
+    } else if let Some(address) = &args.join {
+        let room = match (args.create_room, &args.room) {
+            (Some(mode), _) => Some(RoomRequest::Create { mode, options }),
+            (None, Some(room)) => Some(RoomRequest::Join { room: room.clone() }),
+            (None, None) => None,
+        };
+        vec![Box::new(OnlineGame::join(address, Greeting { name, spectator: false, room })?)]
+    } else if let Some(address) = &args.spectate {
+        let room = args.room.clone().map(|room| RoomRequest::Join { room });
+        vec![Box::new(OnlineGame::spectate(address, room, Duration::from_secs_f32(args.delay.max(0.)))?)]
+    } else if args.daily {
+        let path = std::env::var_os("MINESWEEPER_DAILY").map_or_else(DailyResults::default_path, Into::into);
+        let difficulty = args.board.difficulty.unwrap_or(Difficulty::Intermediate);
+        vec![Box::new(DailyGame::new(difficulty, DailyResults::load(path)))]
+    } else if args.versus {
+        RaceSide::split_screen(options).into_iter().map(|side| Box::new(side) as Box<dyn Game>).collect()
+    } else {
+        vec![Box::new(LocalGame::new(args.board.clone(), seed))]
+    };
+    let mut screens: Vec<Screen> = Vec::new();
+    let layouts = match games.len() {
+        1 => [Controls::Solo].as_slice(),
+        _ => [Controls::Left, Controls::Right].as_slice(),
+    };
+    for (game, &controls) in games.into_iter().zip(layouts) {
+        let left = screens.last().map_or(0, |s: &Screen| s.left + s.width() + 2);
+        screens.push(Screen::new(game, controls, left));
+    }
+
+    let mut out = io::stdout();
+    let _guard = TerminalGuard::enter(&mut out)?;
+    run(&mut screens, &mut out)
+}
@@ -0,0 +1,250 @@
+//! Board shared with other players through a host.
+
+use std::{
+    io,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, TcpListener},
+    thread,
+    time::{Duration, Instant},
+};
+
+use board_plugin::{
+    components::Coordinates,
+    minefield::{Cell, GameStatus, Move},
+    resources::tile::Tile,
+};
+use crossterm::style::Color;
+use minesweeper::net::{
+    client::{Client, Greeting},
+    host,
+    protocol::{ClientMessage, Mode, PlayerInfo, RoomRequest, ServerMessage},
+    server::MAX_BOARD_SIDE,
+    session::Session,
+};
+
+use crate::{player_color, versus::race_panel, Clock, Game, PLAYER_COLORS};
+
+/// Time between two attempts to reconnect after the connection dropped
+const RECONNECT_INTERVAL: Duration = Duration::from_secs(2);
+
+/// How this terminal takes part in an online game
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Role {
+    Host,
+    Player,
+    Spectator,
+}
+
+/// A game played or watched on the board of a host, possibly hosted by this process
+pub struct OnlineGame {
+    client: Client,
+    /// Where the host is, or listens when hosting
+    address: String,
+    role: Role,
+    clock: Clock,
+    /// Last thing the host refused, until the next move
+    notice: Option<String>,
+    /// Last move played, shown to spectators
+    last_move: Option<String>,
+    /// Last attempt to reconnect, and why it failed
+    reconnecting: Option<(Instant, String)>,
+}
+
+impl OnlineGame {
+    /// Hosts `session` on `address` in the background and joins it
+    pub fn host(address: &str, session: Session, name: &str) -> io::Result<Self> {
+        // Snapshots of bigger boards may not fit in a message
+        let (width, height) = session.options().map_size;
+        if width > MAX_BOARD_SIDE || height > MAX_BOARD_SIDE {
+            let message = format!("hosted boards are at most {} tiles wide and high", MAX_BOARD_SIDE);
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, message));
+        }
+        let listener = TcpListener::bind(address)?;
+        let local = listener.local_addr()?;
+        thread::spawn(move || host::serve(listener, session));
+        // A host listening on every interface is reached on loopback
+        let ip = match local.ip() {
+            IpAddr::V4(ip) if ip.is_unspecified() => IpAddr::V4(Ipv4Addr::LOCALHOST),
+            IpAddr::V6(ip) if ip.is_unspecified() => IpAddr::V6(Ipv6Addr::LOCALHOST),
+            ip => ip,
+        };
+        let greeting = Greeting { name: name.to_owned(), ..Greeting::default() };
+        let mut game = Self::join(&SocketAddr::new(ip, local.port()).to_string(), greeting)?;
+        game.address = local.to_string();
+        game.role = Role::Host;
+        Ok(game)
+    }
+
+    pub fn join(address: &str, greeting: Greeting) -> io::Result<Self> {
+        let client = Client::open(address, greeting)
+            .map_err(|e| io::Error::new(e.kind(), format!("Failed to join {}: {}", address, e)))?;
+        Ok(Self::new(client, address, Role::Player))
+    }
+
+    /// Watches the game of a host, or of one of its rooms, `delay` behind
+    pub fn spectate(address: &str, room: Option<RoomRequest>, delay: Duration) -> io::Result<Self> {
+        let mut client = Client::open(address, Greeting { spectator: true, room, ..Greeting::default() })
+            .map_err(|e| io::Error::new(e.kind(), format!("Failed to watch {}: {}", address, e)))?;
+        client.set_delay(delay);
+        Ok(Self::new(client, address, Role::Spectator))
+    }
+
+    fn new(client: Client, address: &str, role: Role) -> Self {
+        Self {
+            client,
+            address: address.to_owned(),
+            role,
+            clock: Clock::default(),
+            notice: None,
+            last_move: None,
+            reconnecting: None,
+        }
+    }
+
+    /// Tries to get back in the game every few seconds after the connection dropped
+    fn reconnect(&mut self) {
+        if self.role == Role::Host || self.reconnecting.as_ref().is_some_and(|(at, _)| at.elapsed() < RECONNECT_INTERVAL) {
+            return;
+        }
+        self.reconnecting = match self.client.reconnect() {
+            Ok(()) => None,
+            Err(e) => Some((Instant::now(), e.to_string())),
+        };
+    }
+
+    fn send(&mut self, message: ClientMessage) {
+        if self.role == Role::Spectator {
+            return;
+        }
+        self.notice = None;
+        // A lost connection is shown in the status line
+        let _ = self.client.send(&message);
+    }
+}
+
+impl Game for OnlineGame {
+    fn width(&self) -> u16 {
+        self.client.board().width()
+    }
+
+    fn height(&self) -> u16 {
+        self.client.board().height()
+    }
+
+    fn cell(&self, coords: Coordinates) -> Option<Cell> {
+        self.client.board().cell(coords)
+    }
+
+    fn mine(&self, coords: Coordinates) -> Option<Tile> {
+        self.client.board().mine(coords)
+    }
+
+    fn status(&self) -> GameStatus {
+        self.client.board().status()
+    }
+
+    fn bombs_left(&self) -> i64 {
+        let board = self.client.board();
+        board.bomb_count() as i64 - board.flag_count() as i64
+    }
+
+    fn is_multi_bomb(&self) -> bool {
+        self.client.board().max_bombs_per_tile() > 1
+    }
+
+    fn elapsed(&self) -> Duration {
+        self.clock.elapsed()
+    }
+
+    fn title(&self) -> String {
+        let mut place = match self.role {
+            Role::Host => format!("Hosting on {}", self.address),
+            Role::Player => format!("Joined {}", self.address),
+            Role::Spectator => format!("Watching {}", self.address),
+        };
+        if let Some(room) = self.client.room() {
+            place = format!("{} room {}", place, room);
+        }
+        let race = match (self.client.mode(), self.client.race_start()) {
+            (Mode::Coop, _) => None,
+            (Mode::Versus, None) => Some("G starts the race".to_owned()),
+            (Mode::Versus, Some(start)) => start.checked_duration_since(Instant::now())
+                .map(|left| format!("Starts in {}", left.as_secs() + 1)),
+        };
+        let notice = match self.client.is_connected() {
+            true => self.notice.clone().or(race).or_else(|| self.last_move.clone()),
+            false => Some(match (&self.role, &self.reconnecting) {
+                (Role::Host, _) => "Disconnected".to_owned(),
+                (_, None) => "Disconnected, reconnecting".to_owned(),
+                (_, Some((_, error))) => format!("Disconnected, reconnecting ({})", error),
+            }),
+        };
+        match notice {
+            Some(notice) => format!("{}   {}", place, notice),
+            None => place,
+        }
+    }
+
+    fn play(&mut self, action: Move) {
+        self.send(ClientMessage::Play { action });
+    }
+
+    fn new_game(&mut self) {
+        self.send(ClientMessage::NewGame);
+    }
+
+    fn cursor_moved(&mut self, coords: Coordinates) {
+        if self.role != Role::Spectator {
+            let _ = self.client.send(&ClientMessage::Cursor { coords });
+        }
+    }
+
+    fn update(&mut self) {
+        if !self.client.is_connected() {
+            self.reconnect();
+        }
+        for message in self.client.poll() {
+            match message {
+                ServerMessage::Update { player, action, tiles, status, .. } => {
+                    self.clock.update(!tiles.is_empty(), status);
+                    if self.role == Role::Spectator {
+                        let name = self.client.players().find(|p| p.id == player).map_or("", |p| p.name.as_str());
+                        self.last_move = Some(format!("{} played {}", name, action));
+                    }
+                }
+                ServerMessage::NewBoard { .. } => self.clock = Clock::default(),
+                ServerMessage::Countdown { .. } => {
+                    self.clock = self.client.race_start().map_or_else(Clock::default, Clock::starting_at);
+                }
+                ServerMessage::Invalid { reason } => self.notice = Some(reason),
+                _ => (),
+            }
+        }
+    }
+
+    fn flag_color(&self, coords: Coordinates) -> Color {
+        self.client.board().flag_owner(coords).map_or(PLAYER_COLORS[0], player_color)
+    }
+
+    fn other_cursor(&self, coords: Coordinates) -> Option<Color> {
+        self.client.players()
+            .find(|p| p.id != self.client.player() && p.cursor == Some(coords))
+            .map(|p| player_color(p.id))
+    }
+
+    fn panel(&self) -> Vec<(String, Color)> {
+        let name = |info: &PlayerInfo| match (info.id == self.client.player(), info.away) {
+            (true, _) => format!("{} (you)", info.name),
+            (false, true) => format!("{} (away)", info.name),
+            (false, false) => info.name.clone(),
+        };
+        match self.client.mode() {
+            Mode::Coop => self.client.players()
+                .map(|p| (format!("■ {}", name(p)), player_color(p.id)))
+                .collect(),
+            Mode::Versus => race_panel(
+                self.client.players().map(|p| (p.id, name(p), self.client.progress(p.id))),
+                self.client.results(),
+            ),
+        }
+    }
+}
@@ -0,0 +1,152 @@
+//! Races between two players sharing the terminal, and the race side panel.
+
+use std::{
+    cell::{Ref, RefCell},
+    rc::Rc,
+    time::{Duration, Instant},
+};
+
+use board_plugin::{
+    components::Coordinates,
+    minefield::{Cell, GameStatus, Minefield, MinefieldOptions, Move},
+    resources::tile::Tile,
+};
+use crossterm::style::Color;
+use minesweeper::{
+    net::protocol::PlayerId,
+    race::{Progress, Race, RaceResult},
+};
+
+use crate::{player_color, Game};
+
+/// Players of a split-screen race
+const RACERS: [(PlayerId, &str); 2] = [(1, "Player 1"), (2, "Player 2")];
+
+/// One player's side of a race played on this terminal
+pub struct RaceSide {
+    race: Rc<RefCell<Race>>,
+    player: PlayerId,
+}
+
+impl RaceSide {
+    /// Both sides of a new race, on the board of `options`
+    pub fn split_screen(options: MinefieldOptions) -> [Self; 2] {
+        let race = Rc::new(RefCell::new(new_race(options)));
+        RACERS.map(|(player, _)| Self { race: race.clone(), player })
+    }
+
+    fn field(&self) -> Ref<'_, Minefield> {
+        Ref::map(self.race.borrow(), |race| race.field(self.player).expect("split-screen racers never leave"))
+    }
+}
+
+fn new_race(options: MinefieldOptions) -> Race {
+    Race::new(options, RACERS.map(|(id, name)| (id, name.to_owned())), Instant::now())
+}
+
+impl Game for RaceSide {
+    fn width(&self) -> u16 {
+        self.field().width()
+    }
+
+    fn height(&self) -> u16 {
+        self.field().height()
+    }
+
+    fn cell(&self, coords: Coordinates) -> Option<Cell> {
+        self.field().cell(coords)
+    }
+
+    fn mine(&self, coords: Coordinates) -> Option<Tile> {
+        self.field().tile_map().get(coords).copied().filter(Tile::is_bomb)
+    }
+
+    fn status(&self) -> GameStatus {
+        self.field().status()
+    }
+
+    fn bombs_left(&self) -> i64 {
+        let field = self.field();
+        field.tile_map().bomb_count() as i64 - field.flag_count() as i64
+    }
+
+    fn is_multi_bomb(&self) -> bool {
+        self.field().tile_map().is_multi_bomb()
+    }
+
+    fn elapsed(&self) -> Duration {
+        self.race.borrow().elapsed(self.player, Instant::now())
+    }
+
+    fn title(&self) -> String {
+        let race = self.race.borrow();
+        let name = RACERS.iter().find(|(id, _)| *id == self.player).map_or("", |(_, name)| *name);
+        match race.countdown(Instant::now()) {
+            Some(left) => format!("{}   Starts in {}", name, left.as_secs() + 1),
+            None => format!("{}   Seed {}", name, race.options().seed),
+        }
+    }
+
+    fn play(&mut self, action: Move) {
+        // Moves before the start or after the end are simply ignored
+        let _ = self.race.borrow_mut().play(self.player, action, Instant::now());
+    }
+
+    /// Starts a rematch on the next board once both players finished
+    fn new_game(&mut self) {
+        let mut race = self.race.borrow_mut();
+        if race.is_over() {
+            let options = race.options();
+            *race = new_race(MinefieldOptions { seed: options.seed.wrapping_add(1), ..*options });
+        }
+    }
+
+    fn flag_color(&self, _coords: Coordinates) -> Color {
+        player_color(self.player)
+    }
+
+    fn panel(&self) -> Vec<(String, Color)> {
+        let race = self.race.borrow();
+        let racers = RACERS.map(|(id, name)| (id, name.to_owned(), race.progress(id)));
+        let results = match race.is_over() {
+            true => race.results(Instant::now()),
+            false => Vec::new(),
+        };
+        race_panel(racers, &results)
+    }
+}
+
+/// Side panel of a race: how far everyone got, then the standings once it is over
+pub fn race_panel(racers: impl IntoIterator<Item = (PlayerId, String, Option<Progress>)>, results: &[RaceResult]) -> Vec<(String, Color)> {
+    let mut lines: Vec<_> = racers.into_iter()
+        .map(|(player, name, progress)| {
+            let progress = progress.map_or("  0%".to_owned(), |p| {
+                let state = match p.status {
+                    GameStatus::Playing => "",
+                    GameStatus::Won => " cleared",
+                    GameStatus::Lost => " dead",
+                };
+                format!("{:>3.0}%{}", p.cleared * 100., state)
+            });
+            (format!("■ {:<14} {}", name, progress), player_color(player))
+        })
+        .collect();
+    if results.is_empty() {
+        return lines;
+    }
+    lines.push((String::new(), Color::Reset));
+    lines.push(("Results".to_owned(), Color::White));
+    for (rank, result) in results.iter().enumerate() {
+        let outcome = match result.status {
+            GameStatus::Won => format!("{:.2}s", result.seconds),
+            _ => "dead".to_owned(),
+        };
+        let color = player_color(result.player);
+        lines.push((format!("{}. {:<14} {}", rank + 1, result.name, outcome), color));
+        lines.push((
+            format!("   3BV {}/{}   3BV/s {:.2}", result.solved_bbbv, result.bbbv, result.bbbv_per_second),
+            color,
+        ));
+    }
+    lines
+}
@@ -0,0 +1,213 @@
+use bevy::{prelude::*, log};
+use board_plugin::{
+    resources::{BoardAssets, InputAction, InputBinding, InputMap, Modifier, PhysicalInput},
+};
+
+use crate::{AppState, ResumeState};
+
+/// Rebinding screen, listing every action with its bindings
+pub struct ControlsPlugin;
+
+impl Plugin for ControlsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Rebinding>();
+        app.add_system(spawn_controls.in_schedule(OnEnter(AppState::Controls)));
+        app.add_system(despawn_controls.in_schedule(OnExit(AppState::Controls)));
+        app.add_systems(
+            (capture_binding, controls_buttons, update_binding_texts)
+                .chain()
+                .in_set(OnUpdate(AppState::Controls)),
+        );
+    }
+}
+
+/// Action waiting for its new binding
+#[derive(Debug, Default, Resource)]
+struct Rebinding(Option<InputAction>);
+
+#[derive(Component)]
+struct ControlsRoot;
+
+#[derive(Component)]
+enum ControlsButton {
+    Rebind(InputAction),
+    Reset,
+}
+
+#[derive(Component)]
+struct BindingsText(InputAction);
+
+const BUTTON_COLOR: Color = Color::rgb(0.25, 0.25, 0.25);
+
+fn spawn_controls(mut cmd: Commands, assets: Res<BoardAssets>) {
+    let text_style = |size: f32| TextStyle {
+        font: assets.bomb_counter_font.clone(),
+        font_size: size,
+        color: Color::WHITE,
+    };
+    let button_style = Style {
+        padding: UiRect::all(Val::Px(4.)),
+        margin: UiRect::all(Val::Px(2.)),
+        ..default()
+    };
+
+    cmd.spawn(NodeBundle {
+        style: Style {
+            size: Size::new(Val::Percent(100.), Val::Percent(100.)),
+            flex_direction: FlexDirection::Column,
+            align_items: AlignItems::Center,
+            justify_content: JustifyContent::Center,
+            ..default()
+        },
+        background_color: Color::rgb(0.1, 0.1, 0.1).into(),
+        ..default()
+    })
+    .insert(Name::new("Controls"))
+    .insert(ControlsRoot)
+    .with_children(|parent| {
+        parent.spawn(TextBundle::from_section("Controls", text_style(24.)));
+        for action in InputAction::ALL {
+            parent.spawn(NodeBundle {
+                style: Style {
+                    flex_direction: FlexDirection::Row,
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                ..default()
+            })
+            .with_children(|row| {
+                row.spawn(TextBundle::from_section(action.label(), text_style(10.)).with_style(Style {
+                    size: Size::width(Val::Px(200.)),
+                    ..default()
+                }));
+                row.spawn(ButtonBundle {
+                    style: button_style.clone(),
+                    background_color: BUTTON_COLOR.into(),
+                    ..default()
+                })
+                .insert(ControlsButton::Rebind(action))
+                .with_children(|button| {
+                    button.spawn(TextBundle::from_section("", text_style(10.)))
+                        .insert(BindingsText(action));
+                });
+            });
+        }
+        parent.spawn(ButtonBundle {
+            style: button_style.clone(),
+            background_color: BUTTON_COLOR.into(),
+            ..default()
+        })
+        .insert(ControlsButton::Reset)
+        .with_children(|button| {
+            button.spawn(TextBundle::from_section("Reset to defaults", text_style(10.)));
+        });
+        parent.spawn(TextBundle::from_section(
+            "Click an action then press a key, mouse or gamepad button. Escape to go back",
+            text_style(8.),
+        ));
+    });
+}
+
+fn despawn_controls(
+    mut cmd: Commands,
+    roots: Query<Entity, With<ControlsRoot>>,
+    mut rebinding: ResMut<Rebinding>,
+) {
+    for root in roots.iter() {
+        cmd.entity(root).despawn_recursive();
+    }
+    rebinding.0 = None;
+}
+
+fn controls_buttons(
+    buttons: Query<(&Interaction, &ControlsButton), Changed<Interaction>>,
+    mut rebinding: ResMut<Rebinding>,
+    mut map: ResMut<InputMap>,
+) {
+    for (interaction, button) in buttons.iter() {
+        if *interaction != Interaction::Clicked {
+            continue;
+        }
+        match button {
+            ControlsButton::Rebind(action) => rebinding.0 = Some(*action),
+            ControlsButton::Reset => {
+                *map = InputMap::default();
+                save(&map);
+            }
+        }
+    }
+}
+
+/// Waits for the input bound to the selected action. Modifier keys count as
+/// modifiers of the next input, or as an input on their own when released alone
+fn capture_binding(
+    keys: Res<Input<KeyCode>>,
+    mouse: Res<Input<MouseButton>>,
+    gamepad_buttons: Res<Input<GamepadButton>>,
+    resume: Res<ResumeState>,
+    mut next_app_state: ResMut<NextState<AppState>>,
+    mut rebinding: ResMut<Rebinding>,
+    mut map: ResMut<InputMap>,
+) {
+    let Some(action) = rebinding.0 else {
+        if keys.just_pressed(KeyCode::Escape) {
+            next_app_state.set(resume.0);
+        }
+        return;
+    };
+    if keys.just_pressed(KeyCode::Escape) {
+        rebinding.0 = None;
+        return;
+    }
+
+    let is_modifier = |key: &KeyCode| Modifier::ALL.iter().any(|m| m.keys().contains(key));
+    let held: Vec<Modifier> = Modifier::ALL.into_iter()
+        .filter(|m| keys.any_pressed(m.keys()))
+        .collect();
+    let with_modifiers = |input: PhysicalInput| InputBinding { input, modifiers: held.clone() };
+
+    let binding = keys.get_just_pressed()
+        .find(|key| !is_modifier(key))
+        .map(|&key| with_modifiers(PhysicalInput::Key(key)))
+        .or_else(|| mouse.get_just_pressed().next().map(|&b| with_modifiers(PhysicalInput::Mouse(b))))
+        .or_else(|| gamepad_buttons.get_just_pressed()
+            .next()
+            .map(|b| PhysicalInput::Gamepad(b.button_type).into()))
+        .or_else(|| keys.get_just_released()
+            .find(|key| is_modifier(key))
+            .map(|&key| PhysicalInput::Key(key).into()));
+    let Some(binding) = binding else { return; };
+
+    log::info!("Binding {} to {:?}", binding, action);
+    map.rebind(action, binding);
+    save(&map);
+    rebinding.0 = None;
+}
+
+fn update_binding_texts(
+    map: Res<InputMap>,
+    rebinding: Res<Rebinding>,
+    mut texts: Query<(&mut Text, &BindingsText)>,
+    spawned: Query<(), Added<BindingsText>>,
+) {
+    if !map.is_changed() && !rebinding.is_changed() && spawned.is_empty() {
+        return;
+    }
+    for (mut text, BindingsText(action)) in texts.iter_mut() {
+        text.sections[0].value = if rebinding.0 == Some(*action) {
+            "Press an input...".to_owned()
+        } else {
+            let bindings: Vec<String> = map.bindings(*action).iter().map(|b| b.to_string()).collect();
+            match bindings.is_empty() {
+                true => "Unbound".to_owned(),
+                false => bindings.join(", "),
+            }
+        };
+    }
+}
+
+fn save(map: &InputMap) {
+    if let Err(e) = map.save() {
+        log::warn!("Failed to save input bindings to {}: {}", InputMap::path().display(), e);
+    }
+}
@@ -0,0 +1,82 @@
+use bevy::{log, prelude::*};
+use board_plugin::{
+    BoardState,
+    daily::{daily_seed, Date},
+    minefield::Generation,
+    events::GameEndedEvent,
+    resources::{ActionInput, BoardOptions, DailyChallenge, DailyOutcome, DailyResults, Difficulty, InputAction},
+};
+
+use crate::{puzzles::leave_puzzle, AppState};
+
+/// Daily challenge: the same board for everyone on a given day, with one official attempt
+pub struct DailyPlugin;
+
+impl Plugin for DailyPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(start_daily);
+        app.add_system(record_daily);
+    }
+}
+
+/// Daily challenge of the current game, if it is played on a daily board
+pub fn current_challenge(challenge: Option<&DailyChallenge>, options: &BoardOptions) -> Option<DailyChallenge> {
+    challenge.copied().filter(|c| options.seed == Some(daily_seed(c.date, c.difficulty)))
+}
+
+fn start_daily(
+    mut cmd: Commands,
+    input: ActionInput,
+    app_state: Res<State<AppState>>,
+    mut next_app_state: ResMut<NextState<AppState>>,
+    mut board_options: ResMut<BoardOptions>,
+    mut daily: ResMut<DailyResults>,
+    mut next_board_state: ResMut<NextState<BoardState>>,
+) {
+    let playing = matches!(app_state.0, AppState::InGame | AppState::MainMenu | AppState::GameOver);
+    if !playing || !input.just_pressed(InputAction::Daily) {
+        return;
+    }
+    let date = Date::today();
+    let difficulty = board_options.difficulty().unwrap_or(Difficulty::Intermediate);
+    let official = daily.start(difficulty, date);
+    if official {
+        if let Err(e) = daily.save() {
+            log::warn!("Failed to save daily results to {}: {}", daily.path().display(), e);
+        }
+        log::info!("Starting the {} daily challenge of {}", difficulty.label(), date);
+    } else {
+        log::info!("Replaying the {} daily challenge of {}, only the first attempt counts", difficulty.label(), date);
+    }
+
+    let (map_size, bomb_count) = difficulty.parameters();
+    board_options.map_size = map_size;
+    board_options.bomb_count = bomb_count;
+    board_options.max_bombs_per_tile = 1;
+    board_options.generation = Generation::SafeStart;
+    board_options.practice_mode = false;
+    board_options.seed = Some(daily_seed(date, difficulty));
+    cmd.insert_resource(DailyChallenge { date, difficulty, official });
+    leave_puzzle(&mut cmd);
+    next_app_state.set(AppState::InGame);
+    next_board_state.set(BoardState::Active);
+}
+
+/// Records the end of the official daily attempt and saves it
+pub fn record_daily(
+    mut ended_evr: EventReader<GameEndedEvent>,
+    challenge: Option<Res<DailyChallenge>>,
+    board_options: Res<BoardOptions>,
+    mut daily: ResMut<DailyResults>,
+) {
+    let Some(GameEndedEvent(result)) = ended_evr.iter().last() else { return; };
+    let Some(challenge) = current_challenge(challenge.as_deref(), &board_options).filter(|c| c.official) else { return; };
+    // A bot clearing the board wins nothing
+    let outcome = DailyOutcome { won: result.won && !result.bot, time: result.time };
+    if !daily.finish(challenge.difficulty, challenge.date, outcome) {
+        return;
+    }
+    if let Err(e) = daily.save() {
+        log::warn!("Failed to save daily results to {}: {}", daily.path().display(), e);
+    }
+}
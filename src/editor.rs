@@ -0,0 +1,142 @@
+use bevy::{prelude::*, log};
+use board_plugin::{
+    BoardState,
+    puzzle::Verdict,
+    resources::{ActionInput, BoardAssets, BoardEditor, BoardOptions, BoardStart, InputAction, InputMap, PuzzleChallenge},
+};
+
+/// Panel of the puzzle editor, describing the edited puzzle and the editor controls.
+/// A new game from the editor is a test game of the edited puzzle
+pub struct EditorPlugin;
+
+impl Plugin for EditorPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(spawn_editor_panel.in_schedule(OnEnter(BoardState::Editor)));
+        app.add_system(despawn_editor_panel.in_schedule(OnExit(BoardState::Editor)));
+        app.add_systems((update_editor_panel, test_puzzle).in_set(OnUpdate(BoardState::Editor)));
+    }
+}
+
+#[derive(Component)]
+struct EditorPanel;
+
+#[derive(Component)]
+struct EditorText;
+
+fn spawn_editor_panel(mut cmd: Commands, assets: Res<BoardAssets>) {
+    let text_style = TextStyle {
+        font: assets.bomb_counter_font.clone(),
+        font_size: 8.,
+        color: Color::WHITE,
+    };
+
+    cmd.spawn(NodeBundle {
+        style: Style {
+            position_type: PositionType::Absolute,
+            position: UiRect { left: Val::Px(10.), top: Val::Px(10.), ..default() },
+            padding: UiRect::all(Val::Px(8.)),
+            flex_direction: FlexDirection::Column,
+            ..default()
+        },
+        background_color: Color::rgba(0., 0., 0., 0.75).into(),
+        ..default()
+    })
+    .insert(Name::new("Editor Panel"))
+    .insert(EditorPanel)
+    .with_children(|parent| {
+        parent.spawn(TextBundle::from_section("Puzzle editor", TextStyle { font_size: 16., ..text_style.clone() }));
+        parent.spawn(TextBundle::from_section("", text_style)).insert(EditorText);
+    });
+}
+
+fn despawn_editor_panel(mut cmd: Commands, panels: Query<Entity, With<EditorPanel>>) {
+    for panel in panels.iter() {
+        cmd.entity(panel).despawn_recursive();
+    }
+}
+
+fn update_editor_panel(
+    editor: Option<Res<BoardEditor>>,
+    map: Res<InputMap>,
+    mut texts: Query<&mut Text, With<EditorText>>,
+    spawned: Query<(), Added<EditorText>>,
+) {
+    let Some(editor) = editor else { return; };
+    if !editor.is_changed() && !map.is_changed() && spawned.is_empty() {
+        return;
+    }
+    for mut text in texts.iter_mut() {
+        let style = text.sections[0].style.clone();
+        text.sections = describe(&editor, &map, style);
+    }
+}
+
+/// Sections of the panel text, the solver verdict and the last message standing out in
+/// their own color
+fn describe(editor: &BoardEditor, map: &InputMap, style: TextStyle) -> Vec<TextSection> {
+    let layout = &editor.layout;
+    let (verdict, color) = match &editor.verdict {
+        None => (format!("Not checked, {} to check", binding(map, InputAction::CheckPuzzle)), Color::GRAY),
+        Some(Ok(Verdict::NoGuess)) => ("Clears without guessing".to_owned(), Color::GREEN),
+        Some(Ok(Verdict::NeedsGuess(left))) => (format!("Needs a guess, {} safe tiles left", left), Color::ORANGE),
+        Some(Err(e)) => (format!("Invalid: {}", e), Color::RED),
+    };
+    let summary = format!(
+        "{}\n{}x{}, {} mines, {} revealed, {} flagged\n",
+        editor.name,
+        layout.tile_map.width(),
+        layout.tile_map.height(),
+        layout.tile_map.bomb_count(),
+        layout.revealed.len(),
+        layout.flagged.len(),
+    );
+    let message = editor.message.as_ref().map_or_else(String::new, |m| format!("{}\n", m));
+    let controls = format!(
+        "\n{}: add or remove a mine\n{}: reveal a tile or flag a mine at the start\n{}: save   {}: test   {}: leave",
+        binding(map, InputAction::Reveal),
+        binding(map, InputAction::Flag),
+        binding(map, InputAction::SavePuzzle),
+        binding(map, InputAction::Restart),
+        binding(map, InputAction::Editor),
+    );
+    vec![
+        TextSection::new(summary, style.clone()),
+        TextSection::new(verdict + "\n", TextStyle { color, ..style.clone() }),
+        TextSection::new(message, TextStyle { color: Color::YELLOW, ..style.clone() }),
+        TextSection::new(controls, style),
+    ]
+}
+
+/// First binding of an action, as shown to the player
+fn binding(map: &InputMap, action: InputAction) -> String {
+    map.bindings(action).first().map_or_else(|| "Unbound".to_owned(), ToString::to_string)
+}
+
+/// Starts a test game on the edited puzzle, which the editor opens again afterwards
+fn test_puzzle(
+    mut cmd: Commands,
+    input: ActionInput,
+    mut editor: ResMut<BoardEditor>,
+    mut board_options: ResMut<BoardOptions>,
+    mut next_board_state: ResMut<NextState<BoardState>>,
+) {
+    if !input.just_pressed(InputAction::Restart) {
+        return;
+    }
+    let field = match editor.layout.minefield() {
+        Ok(field) => field,
+        Err(e) => {
+            editor.message = Some(format!("Can't test the puzzle: {}", e));
+            return;
+        }
+    };
+    log::info!("Testing the edited puzzle");
+    board_options.map_size = (field.width(), field.height());
+    board_options.bomb_count = field.tile_map().bomb_count();
+    board_options.max_bombs_per_tile = field.tile_map().max_bombs_per_tile();
+    board_options.practice_mode = false;
+    board_options.seed = None;
+    cmd.insert_resource(BoardStart(field));
+    cmd.remove_resource::<PuzzleChallenge>();
+    next_board_state.set(BoardState::Active);
+}
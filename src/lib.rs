@@ -0,0 +1,5 @@
+//! Shared pieces of the command line tools in `src/bin`
+
+pub mod args;
+pub mod net;
+pub mod race;
@@ -2,13 +2,66 @@ use bevy::{prelude::*, log};
 #[cfg(feature = "debug")]
 use bevy_inspector_egui::quick::{StateInspectorPlugin,WorldInspectorPlugin};
 use board_plugin::{BoardPlugin, BoardState};
-use board_plugin::resources::{BoardOptions, BoardAssets, SpriteMaterial};
+use board_plugin::resources::{ActionInput, BoardAssets, BoardOptions, DailyResults, EndlessOptions, InputAction, InputMap, PuzzleResults, Settings, Stats};
+
+mod controls;
+mod daily;
+mod editor;
+mod menu;
+mod puzzles;
+mod results;
+mod settings;
+#[cfg(feature = "audio")]
+mod sound;
+mod statistics;
+mod themes;
 
 #[derive(Default, Debug, PartialEq, Eq, Hash, Copy, Clone, States, Reflect)]
 pub enum AppState {
+    /// Waiting for the board themes, see `themes::ThemesPlugin`
     #[default]
+    Loading,
+    MainMenu,
     InGame,
+    /// Pause overlay, hiding the board
     Paused,
+    /// Win or loss screen over the finished board
+    GameOver,
+    /// Rebinding screen
+    Controls,
+    Statistics,
+    /// Puzzle pack
+    Puzzles,
+    Settings,
+}
+
+/// App state the side screens, such as statistics, go back to. Must be used as a resource
+#[derive(Debug, Default, Resource)]
+pub struct ResumeState(pub AppState);
+
+/// Follows the game, so side screens go back where they were opened from. A game in
+/// progress is resumed paused
+fn track_resume_state(
+    app_state: Res<State<AppState>>,
+    board_state: Res<State<BoardState>>,
+    mut resume: ResMut<ResumeState>,
+) {
+    if !app_state.is_changed() && !board_state.is_changed() {
+        return;
+    }
+    resume.0 = match (app_state.0, board_state.0) {
+        (AppState::InGame, BoardState::Active | BoardState::Endless) => AppState::Paused,
+        (state @ (AppState::MainMenu | AppState::InGame | AppState::Paused | AppState::GameOver | AppState::Settings), _) => state,
+        _ => return,
+    };
+}
+
+/// Prepares a new game on a generated board, leaving any daily challenge or puzzle.
+/// The caller then enters `BoardState::Active`
+pub fn start_game(cmd: &mut Commands, board_options: &mut BoardOptions, practice: bool) {
+    board_options.practice_mode = practice;
+    board_options.seed = None;
+    puzzles::leave_puzzle(cmd);
 }
 
 fn main() {
@@ -17,15 +70,38 @@ fn main() {
     app
         .add_plugins(DefaultPlugins)
         .add_state::<AppState>()
+        .insert_resource(InputMap::load())
+        .insert_resource(load_settings())
+        .insert_resource(load_stats())
+        .insert_resource(load_daily_results())
+        .insert_resource(load_puzzle_results())
         .add_plugin(BoardPlugin)
+        .add_plugin(controls::ControlsPlugin)
+        .add_plugin(statistics::StatisticsPlugin)
+        .add_plugin(results::ResultsPlugin)
+        .add_plugin(daily::DailyPlugin)
+        .add_plugin(puzzles::PuzzlesPlugin)
+        .add_plugin(editor::EditorPlugin)
+        .add_plugin(menu::MenuPlugin)
+        .add_plugin(settings::SettingsPlugin)
+        .add_plugin(themes::ThemesPlugin)
+        .init_resource::<ResumeState>()
         .add_system(state_handler)
+        .add_system(track_resume_state)
         .add_startup_system(board_setup)
         .add_startup_system(camera_setup)
         .configure_set(OnUpdate(BoardState::Active)
             .run_if(in_state(AppState::InGame)))
+        .configure_set(OnUpdate(BoardState::Endless)
+            .run_if(in_state(AppState::InGame)))
+        .configure_set(OnUpdate(BoardState::Editor)
+            .run_if(in_state(AppState::InGame)))
         .register_type::<AppState>()
     ;
 
+    #[cfg(feature = "audio")]
+    app.add_plugin(sound::SoundPlugin);
+
     #[cfg(feature = "debug")]
     {
         app.add_plugin(WorldInspectorPlugin::new());
@@ -37,69 +113,88 @@ fn main() {
 
 }
 
-fn board_setup(
-    mut cmd: Commands,
-    asset_server: Res<AssetServer>,
-) {
-    cmd.insert_resource(BoardOptions {
-        map_size: (20, 20),
-        bomb_count: 40,
-        safe_start: true,
-        tile_padding: 2.,
-        ..default()
-    });
-    cmd.insert_resource(BoardAssets {
-        label: "Default".to_owned(),
-        board_material: SpriteMaterial {
-            color: Color::WHITE,
-            ..Default::default()
-        },
-        tile_material: SpriteMaterial {
-            color: Color::DARK_GRAY,
-            ..Default::default()
-        },
-        covered_tile_material: SpriteMaterial {
-            color: Color::GRAY,
-            ..Default::default()
-        },
-        bomb_counter_font: asset_server.load("fonts/pixeled.ttf"),
-        bomb_counter_colors: BoardAssets::default_colors(),
-        flag_material: SpriteMaterial {
-            texture: asset_server.load("sprites/flag.png"),
-            color: Color::WHITE,
-        },
-        bomb_material: SpriteMaterial {
-            texture: asset_server.load("sprites/bomb.png"),
-            color: Color::WHITE,
-        },
-    });
+/// Statistics are saved in the platform data directory, unless `MINESWEEPER_STATS` gives another path
+fn load_stats() -> Stats {
+    let path = std::env::var_os("MINESWEEPER_STATS")
+        .map(Into::into)
+        .unwrap_or_else(Stats::default_path);
+    Stats::load(path)
+}
+
+/// Daily results are saved next to the statistics, unless `MINESWEEPER_DAILY` gives another path
+fn load_daily_results() -> DailyResults {
+    let path = std::env::var_os("MINESWEEPER_DAILY")
+        .map(Into::into)
+        .unwrap_or_else(DailyResults::default_path);
+    DailyResults::load(path)
+}
+
+/// Puzzle results are saved next to the statistics, unless `MINESWEEPER_PUZZLES` gives another path
+fn load_puzzle_results() -> PuzzleResults {
+    let path = std::env::var_os("MINESWEEPER_PUZZLES")
+        .map(Into::into)
+        .unwrap_or_else(PuzzleResults::default_path);
+    PuzzleResults::load(path)
+}
+
+/// Settings are saved in the platform config directory, unless `MINESWEEPER_SETTINGS` gives another path
+fn load_settings() -> Settings {
+    let path = std::env::var_os("MINESWEEPER_SETTINGS")
+        .map(Into::into)
+        .unwrap_or_else(Settings::default_path);
+    Settings::load(path)
+}
+
+fn board_setup(mut cmd: Commands, settings: Res<Settings>) {
+    let mut board_options = BoardOptions::default();
+    settings.apply(&mut board_options);
+    cmd.insert_resource(board_options);
+    // Replaced by the theme of the settings once loaded
+    cmd.insert_resource(BoardAssets::default());
 }
 
 fn state_handler(
+    mut cmd: Commands,
     app_state: Res<State<AppState>>,
     mut next_app_state: ResMut<NextState<AppState>>,
     board_state: Res<State<BoardState>>,
     mut next_board_state: ResMut<NextState<BoardState>>,
-    keys: Res<Input<KeyCode>>,
+    mut board_options: ResMut<BoardOptions>,
+    input: ActionInput,
 ) {
-
-    enum StateControlKey { C, G, Esc }
-    use StateControlKey::*;
     use AppState::*;
     use BoardState::*;
 
-    let key_c = keys.just_pressed(KeyCode::C).then_some(C);
-    let key_g = keys.just_pressed(KeyCode::G).then_some(G);
-    let key_esc = keys.just_pressed(KeyCode::Escape).then_some(Esc);
-
-    let Some(key) = key_c.or(key_g).or(key_esc) else { return; };
+    let actions = [
+        InputAction::Stop,
+        InputAction::Endless,
+        InputAction::Restart,
+        InputAction::Practice,
+        InputAction::Pause,
+        InputAction::Controls,
+        InputAction::Statistics,
+        InputAction::Puzzles,
+        InputAction::Editor,
+    ];
+    let Some(action) = actions.into_iter().find(|&a| input.just_pressed(a)) else { return; };
 
-    let (message, app, board) = match (app_state.0, board_state.0, key) {
-        (InGame, Inactive, G) => (Some("Starting game"), None, Some(Active)),
-        (InGame, Active, G) => (Some("Restarting game"), None, Some(Active)),
-        (InGame, Active, Esc) => (Some("Pausing game"), Some(Paused), None),
-        (Paused, Active, Esc) => (Some("Unpausing game"), Some(InGame), None),
-        (InGame, Active, C) => (Some("Stopping game"), None, Some(Inactive)),
+    let (message, app, board) = match (app_state.0, board_state.0, action) {
+        // A new game from the editor tests the edited puzzle, see `editor::test_puzzle`
+        (InGame, Editor, InputAction::Restart) => (None, None, None),
+        (InGame | MainMenu | GameOver, Active, InputAction::Restart) => (Some("Restarting game"), Some(InGame), Some(Active)),
+        (InGame | MainMenu | GameOver, _, InputAction::Restart) => (Some("Starting game"), Some(InGame), Some(Active)),
+        (InGame | MainMenu | GameOver, _, InputAction::Practice) => (Some("Starting practice game"), Some(InGame), Some(Active)),
+        (InGame | MainMenu | GameOver, Endless, InputAction::Endless) => (Some("Restarting endless run"), Some(InGame), Some(Endless)),
+        (InGame | MainMenu | GameOver, Inactive | Active, InputAction::Endless) => (Some("Starting endless run"), Some(InGame), Some(Endless)),
+        (InGame, Active | Endless, InputAction::Pause) => (Some("Pausing game"), Some(Paused), None),
+        (Paused, Active | Endless, InputAction::Pause) => (Some("Unpausing game"), Some(InGame), None),
+        (MainMenu, Active | Endless, InputAction::Pause) => (Some("Continuing game"), Some(InGame), None),
+        (InGame | Paused | GameOver, Active | Endless, InputAction::Stop) => (Some("Stopping game"), Some(MainMenu), Some(Inactive)),
+        (InGame | Paused | MainMenu | GameOver, _, InputAction::Controls) => (Some("Opening controls"), Some(Controls), None),
+        (InGame | Paused | MainMenu | GameOver, _, InputAction::Statistics) => (Some("Opening statistics"), Some(Statistics), None),
+        (InGame | Paused | MainMenu | GameOver, _, InputAction::Puzzles) => (Some("Opening puzzles"), Some(Puzzles), None),
+        (InGame | MainMenu | GameOver, Inactive | Active | Endless, InputAction::Editor) => (Some("Opening puzzle editor"), Some(InGame), Some(Editor)),
+        (InGame, Editor, InputAction::Editor) => (Some("Leaving puzzle editor"), Some(MainMenu), Some(Inactive)),
         _ => (None, None, None),
     };
 
@@ -110,6 +205,12 @@ fn state_handler(
         next_app_state.set(app);
     }
     if let Some(board) = board {
+        match board {
+            Active => start_game(&mut cmd, &mut board_options, action == InputAction::Practice),
+            // A new run gets a new seed
+            Endless => cmd.remove_resource::<EndlessOptions>(),
+            _ => (),
+        }
         next_board_state.set(board);
     }
 }
@@ -0,0 +1,186 @@
+use bevy::{prelude::*, app::AppExit, log};
+use board_plugin::{
+    BoardState,
+    resources::{Board, BoardAssets, BoardOptions, Difficulty, EndlessBoard, GameTimer},
+};
+
+use crate::{start_game, AppState};
+
+pub const BUTTON_COLOR: Color = Color::rgb(0.25, 0.25, 0.25);
+const BACKGROUND_COLOR: Color = Color::rgb(0.1, 0.1, 0.1);
+
+/// Main menu, shown at startup, and the pause overlay hiding the board
+pub struct MenuPlugin;
+
+impl Plugin for MenuPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(spawn_main_menu.in_schedule(OnEnter(AppState::MainMenu)));
+        app.add_system(despawn_menu.in_schedule(OnExit(AppState::MainMenu)));
+        app.add_systems((spawn_pause_menu, hide_board).in_schedule(OnEnter(AppState::Paused)));
+        app.add_systems((despawn_menu, show_board).in_schedule(OnExit(AppState::Paused)));
+        app.add_system(menu_buttons);
+    }
+}
+
+#[derive(Component)]
+struct MenuRoot;
+
+#[derive(Component, Copy, Clone)]
+enum MenuButton {
+    Continue,
+    NewGame,
+    Preset(Difficulty),
+    Puzzles,
+    Statistics,
+    Settings,
+    Quit,
+    Resume,
+    MainMenu,
+}
+
+/// Spawns a labelled button with the given action component
+pub fn spawn_button(parent: &mut ChildBuilder, assets: &BoardAssets, label: &str, action: impl Component) {
+    parent.spawn(ButtonBundle {
+        style: Style {
+            size: Size::width(Val::Px(280.)),
+            justify_content: JustifyContent::Center,
+            padding: UiRect::all(Val::Px(6.)),
+            margin: UiRect::all(Val::Px(3.)),
+            ..default()
+        },
+        background_color: BUTTON_COLOR.into(),
+        ..default()
+    })
+    .insert(action)
+    .with_children(|button| {
+        button.spawn(TextBundle::from_section(label, text_style(assets, 10.)));
+    });
+}
+
+pub fn text_style(assets: &BoardAssets, size: f32) -> TextStyle {
+    TextStyle {
+        font: assets.bomb_counter_font.clone(),
+        font_size: size,
+        color: Color::WHITE,
+    }
+}
+
+fn menu_root(cmd: &mut Commands, name: &str) -> Entity {
+    cmd.spawn(NodeBundle {
+        style: Style {
+            size: Size::new(Val::Percent(100.), Val::Percent(100.)),
+            flex_direction: FlexDirection::Column,
+            align_items: AlignItems::Center,
+            justify_content: JustifyContent::Center,
+            ..default()
+        },
+        background_color: BACKGROUND_COLOR.into(),
+        ..default()
+    })
+    .insert(Name::new(name.to_owned()))
+    .insert(MenuRoot)
+    .id()
+}
+
+fn spawn_main_menu(
+    mut cmd: Commands,
+    assets: Res<BoardAssets>,
+    board_state: Res<State<BoardState>>,
+    timer: Option<Res<GameTimer>>,
+    endless: Option<Res<EndlessBoard>>,
+) {
+    // A finished game can only be started again
+    let can_continue = match board_state.0 {
+        BoardState::Active => timer.is_some_and(|t| !t.finished),
+        BoardState::Endless => endless.is_some_and(|b| b.alive),
+        _ => false,
+    };
+    let root = menu_root(&mut cmd, "Main Menu");
+    cmd.entity(root).with_children(|parent| {
+        parent.spawn(TextBundle::from_section("Minesweeper\n", text_style(&assets, 32.)));
+        if can_continue {
+            spawn_button(parent, &assets, "Continue", MenuButton::Continue);
+        }
+        spawn_button(parent, &assets, "New game", MenuButton::NewGame);
+        for difficulty in Difficulty::ALL {
+            let ((width, height), bombs) = difficulty.parameters();
+            let label = format!("{}  {}x{}, {} mines", difficulty.label(), width, height, bombs);
+            spawn_button(parent, &assets, &label, MenuButton::Preset(difficulty));
+        }
+        spawn_button(parent, &assets, "Puzzles", MenuButton::Puzzles);
+        spawn_button(parent, &assets, "Statistics", MenuButton::Statistics);
+        spawn_button(parent, &assets, "Settings", MenuButton::Settings);
+        spawn_button(parent, &assets, "Quit", MenuButton::Quit);
+    });
+}
+
+fn spawn_pause_menu(mut cmd: Commands, assets: Res<BoardAssets>) {
+    let root = menu_root(&mut cmd, "Pause Menu");
+    cmd.entity(root).with_children(|parent| {
+        parent.spawn(TextBundle::from_section("Paused\n", text_style(&assets, 24.)));
+        spawn_button(parent, &assets, "Resume", MenuButton::Resume);
+        spawn_button(parent, &assets, "Settings", MenuButton::Settings);
+        spawn_button(parent, &assets, "Main menu", MenuButton::MainMenu);
+        spawn_button(parent, &assets, "Quit", MenuButton::Quit);
+    });
+}
+
+fn despawn_menu(mut cmd: Commands, roots: Query<Entity, With<MenuRoot>>) {
+    for root in roots.iter() {
+        cmd.entity(root).despawn_recursive();
+    }
+}
+
+/// Hides the board while paused, so pausing gives no time to think
+fn hide_board(board: Option<Res<Board>>, endless: Option<Res<EndlessBoard>>, visibilities: Query<&mut Visibility>) {
+    set_board_visibility(board, endless, visibilities, Visibility::Hidden);
+}
+
+fn show_board(board: Option<Res<Board>>, endless: Option<Res<EndlessBoard>>, visibilities: Query<&mut Visibility>) {
+    set_board_visibility(board, endless, visibilities, Visibility::Visible);
+}
+
+fn set_board_visibility(
+    board: Option<Res<Board>>,
+    endless: Option<Res<EndlessBoard>>,
+    mut visibilities: Query<&mut Visibility>,
+    visibility: Visibility,
+) {
+    let entities = board.map(|b| b.entity).into_iter().chain(endless.map(|b| b.entity));
+    for entity in entities {
+        if let Ok(mut v) = visibilities.get_mut(entity) {
+            *v = visibility;
+        }
+    }
+}
+
+fn menu_buttons(
+    mut cmd: Commands,
+    buttons: Query<(&Interaction, &MenuButton), Changed<Interaction>>,
+    mut board_options: ResMut<BoardOptions>,
+    mut next_app_state: ResMut<NextState<AppState>>,
+    mut next_board_state: ResMut<NextState<BoardState>>,
+    mut exit: EventWriter<AppExit>,
+) {
+    let Some((_, &button)) = buttons.iter().find(|(i, _)| **i == Interaction::Clicked) else { return; };
+    match button {
+        MenuButton::Continue | MenuButton::Resume => next_app_state.set(AppState::InGame),
+        MenuButton::NewGame | MenuButton::Preset(_) => {
+            if let MenuButton::Preset(difficulty) = button {
+                let (map_size, bomb_count) = difficulty.parameters();
+                board_options.map_size = map_size;
+                board_options.bomb_count = bomb_count;
+                board_options.max_bombs_per_tile = 1;
+            }
+            log::info!("Starting game");
+            start_game(&mut cmd, &mut board_options, false);
+            next_app_state.set(AppState::InGame);
+            next_board_state.set(BoardState::Active);
+        }
+        MenuButton::Puzzles => next_app_state.set(AppState::Puzzles),
+        MenuButton::Statistics => next_app_state.set(AppState::Statistics),
+        MenuButton::Settings => next_app_state.set(AppState::Settings),
+        MenuButton::MainMenu => next_app_state.set(AppState::MainMenu),
+        MenuButton::Quit => exit.send(AppExit),
+    }
+}
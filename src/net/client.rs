@@ -0,0 +1,399 @@
+//! Connection to a host, keeping a copy of the shared board up to date.
+
+use std::{
+    collections::{BTreeMap, HashMap, VecDeque},
+    io::{self, BufReader},
+    net::{Shutdown, SocketAddr, TcpStream, ToSocketAddrs},
+    sync::mpsc::{self, Receiver, TryRecvError},
+    thread,
+    time::{Duration, Instant},
+};
+
+use board_plugin::{
+    components::Coordinates,
+    minefield::{Cell, GameStatus},
+    player::BoardView,
+    resources::tile::Tile,
+};
+
+use super::protocol::{
+    read_message, write_message, BoardSnapshot, ClientMessage, MineTile, Mode, PlayerId, PlayerInfo, Rejoin, RoomRequest,
+    ServerMessage, TileUpdate, PROTOCOL_VERSION,
+};
+use crate::race::{Progress, RaceResult};
+
+/// The board of a host, as its messages describe it
+#[derive(Debug, Clone)]
+pub struct RemoteBoard {
+    width: u16,
+    height: u16,
+    bomb_count: u16,
+    max_bombs_per_tile: u8,
+    cells: Vec<Cell>,
+    /// Player who placed the flags of each flagged tile
+    owners: HashMap<Coordinates, PlayerId>,
+    status: GameStatus,
+    /// Every mine, known once the game is over
+    mines: HashMap<Coordinates, Tile>,
+}
+
+impl RemoteBoard {
+    pub fn new(snapshot: &BoardSnapshot) -> Self {
+        let mut board = Self {
+            width: snapshot.width,
+            height: snapshot.height,
+            bomb_count: snapshot.bomb_count,
+            max_bombs_per_tile: snapshot.max_bombs_per_tile,
+            cells: vec![Cell::Covered; snapshot.width as usize * snapshot.height as usize],
+            owners: HashMap::new(),
+            status: snapshot.status,
+            mines: HashMap::new(),
+        };
+        board.apply(&snapshot.tiles, snapshot.status, &snapshot.mines);
+        board
+    }
+
+    /// Applies tile changes sent by the host
+    pub fn apply(&mut self, tiles: &[TileUpdate], status: GameStatus, mines: &[MineTile]) {
+        for update in tiles {
+            let Some(index) = self.index(update.coords) else { continue; };
+            self.cells[index] = update.cell;
+            match update.owner {
+                Some(owner) => self.owners.insert(update.coords, owner),
+                None => self.owners.remove(&update.coords),
+            };
+        }
+        self.status = status;
+        self.mines.extend(mines.iter().map(|m| (m.coords, m.tile)));
+    }
+
+    pub fn width(&self) -> u16 {
+        self.width
+    }
+
+    pub fn height(&self) -> u16 {
+        self.height
+    }
+
+    pub fn bomb_count(&self) -> u16 {
+        self.bomb_count
+    }
+
+    pub fn max_bombs_per_tile(&self) -> u8 {
+        self.max_bombs_per_tile
+    }
+
+    pub fn status(&self) -> GameStatus {
+        self.status
+    }
+
+    pub fn cell(&self, coords: Coordinates) -> Option<Cell> {
+        self.index(coords).map(|i| self.cells[i])
+    }
+
+    pub fn flag_owner(&self, coords: Coordinates) -> Option<PlayerId> {
+        self.owners.get(&coords).copied()
+    }
+
+    /// Mine on a tile, only known once the game is over
+    pub fn mine(&self, coords: Coordinates) -> Option<Tile> {
+        self.mines.get(&coords).copied()
+    }
+
+    pub fn flag_count(&self) -> u32 {
+        self.cells.iter()
+            .map(|c| match c {
+                Cell::Flagged(n) => *n as u32,
+                _ => 0,
+            })
+            .sum()
+    }
+
+    pub fn view(&self) -> BoardView {
+        BoardView::new(self.width, self.height, self.bomb_count, self.max_bombs_per_tile, |c| {
+            self.cell(c).unwrap_or(Cell::Covered)
+        })
+    }
+
+    fn index(&self, coords: Coordinates) -> Option<usize> {
+        let in_bounds = (0..self.width as i32).contains(&coords.x) && (0..self.height as i32).contains(&coords.y);
+        in_bounds.then(|| coords.y as usize * self.width as usize + coords.x as usize)
+    }
+}
+
+/// How a client introduces itself to a host
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Greeting {
+    pub name: String,
+    /// Only watches the game
+    pub spectator: bool,
+    /// Room to create or join, on a server hosting rooms
+    pub room: Option<RoomRequest>,
+}
+
+impl Greeting {
+    fn hello(&self, room: Option<RoomRequest>, rejoin: Option<Rejoin>) -> ClientMessage {
+        ClientMessage::Hello {
+            version: PROTOCOL_VERSION,
+            name: self.name.clone(),
+            spectator: self.spectator,
+            room,
+            rejoin,
+        }
+    }
+}
+
+/// What the welcome of a host tells
+struct Welcome {
+    player: PlayerId,
+    token: u64,
+    room: Option<String>,
+    mode: Mode,
+    players: Vec<PlayerInfo>,
+    board: BoardSnapshot,
+}
+
+/// A player or spectator connected to a host
+pub struct Client {
+    stream: TcpStream,
+    /// Where the host is, to connect again after a drop
+    address: SocketAddr,
+    greeting: Greeting,
+    /// Messages with the time they arrived
+    incoming: Receiver<(Instant, ServerMessage)>,
+    /// Messages received but held back by `delay`
+    pending: VecDeque<(Instant, ServerMessage)>,
+    /// Time messages are held back before being applied
+    delay: Duration,
+    player: PlayerId,
+    /// Secret given by the host to take the seat back after a drop
+    token: u64,
+    room: Option<String>,
+    mode: Mode,
+    players: BTreeMap<PlayerId, PlayerInfo>,
+    board: RemoteBoard,
+    connected: bool,
+    /// End of the countdown of the current race, in versus
+    race_start: Option<Instant>,
+    progress: BTreeMap<PlayerId, Progress>,
+    /// Standings of the last race, once it is over
+    results: Vec<RaceResult>,
+}
+
+impl Client {
+    /// Connects and says hello, returning once the host welcomed the player
+    pub fn connect(address: impl ToSocketAddrs, name: &str) -> io::Result<Self> {
+        Self::open(address, Greeting { name: name.to_owned(), ..Greeting::default() })
+    }
+
+    /// Connects to watch the game, from a snapshot of the board as it is
+    pub fn spectate(address: impl ToSocketAddrs) -> io::Result<Self> {
+        Self::open(address, Greeting { spectator: true, ..Greeting::default() })
+    }
+
+    /// Connects and introduces itself with `greeting`, returning once the host welcomed it
+    pub fn open(address: impl ToSocketAddrs, greeting: Greeting) -> io::Result<Self> {
+        let stream = TcpStream::connect(address)?;
+        let address = stream.peer_addr()?;
+        let (welcome, incoming) = handshake(&stream, &greeting.hello(greeting.room.clone(), None))?;
+        let mut client = Self {
+            stream,
+            address,
+            greeting,
+            incoming,
+            pending: VecDeque::new(),
+            delay: Duration::ZERO,
+            player: welcome.player,
+            token: welcome.token,
+            room: None,
+            mode: welcome.mode,
+            players: BTreeMap::new(),
+            board: RemoteBoard::new(&welcome.board),
+            connected: true,
+            race_start: None,
+            progress: BTreeMap::new(),
+            results: Vec::new(),
+        };
+        client.welcomed(welcome);
+        Ok(client)
+    }
+
+    /// Connects again after the connection dropped, taking the player's seat back as long
+    /// as the host keeps it
+    pub fn reconnect(&mut self) -> io::Result<()> {
+        let stream = TcpStream::connect(self.address)?;
+        let room = self.room.clone().map(|room| RoomRequest::Join { room });
+        let rejoin = (!self.greeting.spectator).then_some(Rejoin { player: self.player, token: self.token });
+        let (welcome, incoming) = handshake(&stream, &self.greeting.hello(room, rejoin))?;
+        self.disconnect();
+        self.stream = stream;
+        self.incoming = incoming;
+        self.pending.clear();
+        self.connected = true;
+        self.welcomed(welcome);
+        Ok(())
+    }
+
+    fn welcomed(&mut self, welcome: Welcome) {
+        self.player = welcome.player;
+        self.token = welcome.token;
+        self.room = welcome.room;
+        self.mode = welcome.mode;
+        self.players = welcome.players.into_iter().map(|p| (p.id, p)).collect();
+        self.board = RemoteBoard::new(&welcome.board);
+    }
+
+    /// Holds every message back for `delay` before applying it, so spectators can watch
+    /// a game without giving its players any live information
+    pub fn set_delay(&mut self, delay: Duration) {
+        self.delay = delay;
+    }
+
+    /// Id the host gave to this player
+    pub fn player(&self) -> PlayerId {
+        self.player
+    }
+
+    /// Everyone playing, this player included unless spectating
+    pub fn players(&self) -> impl Iterator<Item = &PlayerInfo> {
+        self.players.values()
+    }
+
+    pub fn mode(&self) -> Mode {
+        self.mode
+    }
+
+    /// Code of the room joined, on a server hosting rooms
+    pub fn room(&self) -> Option<&str> {
+        self.room.as_deref()
+    }
+
+    pub fn board(&self) -> &RemoteBoard {
+        &self.board
+    }
+
+    /// When the current race starts or started, in versus
+    pub fn race_start(&self) -> Option<Instant> {
+        self.race_start
+    }
+
+    /// How far a player got in the current race
+    pub fn progress(&self, player: PlayerId) -> Option<Progress> {
+        self.progress.get(&player).copied()
+    }
+
+    /// Standings of the last race, empty while it runs
+    pub fn results(&self) -> &[RaceResult] {
+        &self.results
+    }
+
+    pub fn is_connected(&self) -> bool {
+        self.connected
+    }
+
+    pub fn send(&mut self, message: &ClientMessage) -> io::Result<()> {
+        let result = write_message(&mut self.stream, message);
+        if result.is_err() {
+            self.disconnect();
+        }
+        result
+    }
+
+    /// Applies the messages received since the last call, once they are older than the
+    /// delay, and returns them
+    pub fn poll(&mut self) -> Vec<ServerMessage> {
+        loop {
+            match self.incoming.try_recv() {
+                Ok(message) => self.pending.push_back(message),
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => {
+                    self.connected = false;
+                    break;
+                }
+            }
+        }
+        let mut received = Vec::new();
+        while self.pending.front().is_some_and(|(time, _)| time.elapsed() >= self.delay) {
+            let Some((_, message)) = self.pending.pop_front() else { break; };
+            self.apply(&message);
+            received.push(message);
+        }
+        received
+    }
+
+    pub fn disconnect(&mut self) {
+        let _ = self.stream.shutdown(Shutdown::Both);
+        self.connected = false;
+    }
+
+    fn apply(&mut self, message: &ServerMessage) {
+        match message {
+            ServerMessage::PlayerJoined { player } => {
+                self.players.insert(player.id, player.clone());
+            }
+            ServerMessage::PlayerLeft { player } => {
+                self.players.remove(player);
+            }
+            ServerMessage::PlayerAway { player } => {
+                if let Some(info) = self.players.get_mut(player) {
+                    info.away = true;
+                }
+            }
+            ServerMessage::Cursor { player, coords } => {
+                if let Some(info) = self.players.get_mut(player) {
+                    info.cursor = Some(*coords);
+                }
+            }
+            ServerMessage::Update { tiles, status, mines, .. } => self.board.apply(tiles, *status, mines),
+            ServerMessage::NewBoard { board } => self.board = RemoteBoard::new(board),
+            ServerMessage::Countdown { millis } => {
+                self.race_start = Some(Instant::now() + Duration::from_millis(*millis));
+                self.progress.clear();
+                self.results.clear();
+            }
+            ServerMessage::Progress { player, progress } => {
+                self.progress.insert(*player, *progress);
+            }
+            ServerMessage::Results { results } => self.results = results.clone(),
+            ServerMessage::Welcome { .. } | ServerMessage::Rejected { .. } | ServerMessage::Invalid { .. } => (),
+        }
+    }
+}
+
+impl Drop for Client {
+    /// Closes the connection, which the reader thread would otherwise keep open
+    fn drop(&mut self) {
+        self.disconnect();
+    }
+}
+
+/// Says hello on a new connection and waits for the welcome, then reads every following
+/// message in the background
+fn handshake(stream: &TcpStream, hello: &ClientMessage) -> io::Result<(Welcome, Receiver<(Instant, ServerMessage)>)> {
+    stream.set_nodelay(true)?;
+    let mut writer = stream;
+    write_message(&mut writer, hello)?;
+
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let welcome = match read_message(&mut reader)? {
+        Some(ServerMessage::Welcome { version, player, token, room, mode, players, board }) if version == PROTOCOL_VERSION => {
+            Welcome { player, token, room, mode, players, board }
+        }
+        Some(ServerMessage::Welcome { version, .. }) => {
+            return Err(io::Error::new(io::ErrorKind::Unsupported, format!("the host speaks protocol version {}", version)));
+        }
+        Some(ServerMessage::Rejected { reason }) => return Err(io::Error::new(io::ErrorKind::ConnectionRefused, reason)),
+        Some(_) => return Err(io::Error::new(io::ErrorKind::InvalidData, "expected a welcome message")),
+        None => return Err(io::ErrorKind::UnexpectedEof.into()),
+    };
+
+    let (messages, incoming) = mpsc::channel();
+    thread::spawn(move || {
+        while let Ok(Some(message)) = read_message(&mut reader) {
+            if messages.send((Instant::now(), message)).is_err() {
+                return;
+            }
+        }
+    });
+    Ok((welcome, incoming))
+}
@@ -0,0 +1,181 @@
+//! TCP host running a `Session` for the clients connecting to it.
+
+use std::{
+    collections::HashMap,
+    io::BufReader,
+    net::{Shutdown, TcpListener, TcpStream},
+    sync::{
+        mpsc::{self, RecvTimeoutError, Sender, SyncSender},
+        Arc,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use super::{
+    protocol::{read_message, write_message, ClientMessage, PlayerId, ServerMessage},
+    session::{Outgoing, Session},
+};
+
+/// Time a client gets to take a message before it is dropped
+const WRITE_TIMEOUT: Duration = Duration::from_secs(5);
+/// Messages waiting for a client before it is dropped as too slow
+const QUEUE_LENGTH: usize = 256;
+
+/// Time between checks for seats kept too long
+pub(super) const TICK: Duration = Duration::from_secs(1);
+
+pub(super) type ConnectionId = u64;
+
+pub(super) enum Event {
+    Connected(ConnectionId, Writer),
+    Message(ConnectionId, ClientMessage),
+    Closed(ConnectionId),
+}
+
+/// Sending side of a connection. Messages are written by a thread of their own, so a
+/// stalled client only holds up itself
+pub(super) struct Writer {
+    /// Dropped once the connection is closing
+    queue: Option<SyncSender<Arc<ServerMessage>>>,
+    stream: TcpStream,
+}
+
+impl Writer {
+    fn spawn(stream: &TcpStream) -> std::io::Result<Self> {
+        let (mut writer, stream) = (stream.try_clone()?, stream.try_clone()?);
+        let (queue, messages) = mpsc::sync_channel::<Arc<ServerMessage>>(QUEUE_LENGTH);
+        thread::spawn(move || {
+            for message in messages {
+                if write_message(&mut writer, &*message).is_err() {
+                    break;
+                }
+            }
+            // The reader then sees the connection close and reports the player gone
+            let _ = writer.shutdown(Shutdown::Both);
+        });
+        Ok(Self { queue: Some(queue), stream })
+    }
+
+    /// Queues a message, closing a connection that can't keep up
+    pub(super) fn send(&mut self, message: impl Into<Arc<ServerMessage>>) {
+        let Some(queue) = &self.queue else { return; };
+        if queue.try_send(message.into()).is_err() {
+            self.shutdown();
+        }
+    }
+
+    /// Closes the connection once the messages queued so far are written
+    pub(super) fn close(&mut self) {
+        self.queue = None;
+    }
+
+    /// Closes the connection right away
+    pub(super) fn shutdown(&mut self) {
+        self.queue = None;
+        let _ = self.stream.shutdown(Shutdown::Both);
+    }
+}
+
+struct Connection {
+    writer: Writer,
+    /// Set once the client said hello
+    player: Option<PlayerId>,
+}
+
+/// Accepts clients on `listener` and plays `session` with them. Never returns
+pub fn serve(listener: TcpListener, mut session: Session) {
+    let (events, incoming) = mpsc::channel();
+    thread::spawn(move || accept(listener, events));
+
+    let mut connections: HashMap<ConnectionId, Connection> = HashMap::new();
+    let mut next_check = Instant::now() + TICK;
+    loop {
+        let event = incoming.recv_timeout(next_check.saturating_duration_since(Instant::now()));
+        let now = Instant::now();
+        if now >= next_check {
+            deliver(&mut connections, session.expire(now));
+            next_check = now + TICK;
+        }
+        let event = match event {
+            Ok(event) => event,
+            Err(RecvTimeoutError::Timeout) => continue,
+            Err(RecvTimeoutError::Disconnected) => return,
+        };
+        let outgoing = match event {
+            Event::Connected(id, writer) => {
+                connections.insert(id, Connection { writer, player: None });
+                continue;
+            }
+            Event::Message(id, message) => {
+                let Some(connection) = connections.get_mut(&id) else { continue; };
+                match connection.player {
+                    Some(player) => session.handle(player, message),
+                    None => match session.join(message) {
+                        Ok((player, outgoing)) => {
+                            seat(&mut connections, id, player);
+                            outgoing
+                        }
+                        Err(reason) => {
+                            connection.writer.send(ServerMessage::Rejected { reason });
+                            connection.writer.close();
+                            continue;
+                        }
+                    },
+                }
+            }
+            Event::Closed(id) => match connections.remove(&id).and_then(|c| c.player) {
+                Some(player) => session.disconnected(player, Instant::now()),
+                None => continue,
+            },
+        };
+        deliver(&mut connections, outgoing);
+    }
+}
+
+/// Gives `player` to connection `id`, closing any older connection of a player rejoining
+/// before it timed out
+fn seat(connections: &mut HashMap<ConnectionId, Connection>, id: ConnectionId, player: PlayerId) {
+    for (other, connection) in connections.iter_mut() {
+        if *other == id {
+            connection.player = Some(player);
+        } else if connection.player == Some(player) {
+            connection.player = None;
+            connection.writer.shutdown();
+        }
+    }
+}
+
+fn deliver(connections: &mut HashMap<ConnectionId, Connection>, outgoing: Vec<Outgoing>) {
+    for Outgoing { to, message } in outgoing {
+        let message = Arc::new(message);
+        for connection in connections.values_mut() {
+            if connection.player.is_some_and(|p| to.includes(p)) {
+                connection.writer.send(message.clone());
+            }
+        }
+    }
+}
+
+/// Accepts connections, reporting them and their messages as events
+pub(super) fn accept(listener: TcpListener, events: Sender<Event>) {
+    for (id, stream) in listener.incoming().enumerate() {
+        let Ok(stream) = stream else { continue; };
+        let id = id as ConnectionId;
+        let (Ok(()), Ok(writer)) = (stream.set_write_timeout(Some(WRITE_TIMEOUT)), Writer::spawn(&stream)) else { continue; };
+        let _ = stream.set_nodelay(true);
+        if events.send(Event::Connected(id, writer)).is_err() {
+            return;
+        }
+        let events = events.clone();
+        thread::spawn(move || {
+            let mut reader = BufReader::new(stream);
+            while let Ok(Some(message)) = read_message(&mut reader) {
+                if events.send(Event::Message(id, message)).is_err() {
+                    return;
+                }
+            }
+            let _ = events.send(Event::Closed(id));
+        });
+    }
+}
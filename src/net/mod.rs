@@ -0,0 +1,12 @@
+//! Several players sharing one board over TCP.
+//!
+//! The host owns the only real `Minefield`: clients send it their moves, it checks and
+//! applies them, then sends every client the tiles that changed. Clients never know
+//! where the mines are before the game ends. A server runs many such hosts at once, one
+//! per room.
+
+pub mod client;
+pub mod host;
+pub mod protocol;
+pub mod server;
+pub mod session;
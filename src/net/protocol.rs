@@ -0,0 +1,291 @@
+//! Messages exchanged between a host and its clients.
+//!
+//! Every message is a single line of JSON. A client opens with `ClientMessage::Hello`
+//! carrying `PROTOCOL_VERSION`, and the host answers with `ServerMessage::Welcome` or,
+//! when the versions differ, `ServerMessage::Rejected` before closing the connection.
+//!
+//! Spectators say hello the same way and then only listen: the welcome snapshot shows the
+//! board as it is, and the following messages form the stream of everything played.
+//!
+//! The welcome carries a token: a player whose connection dropped says hello again with it
+//! to take their seat back, as long as the host still keeps it. A server hosting several
+//! rooms also needs to know which room the hello is for.
+
+use std::{
+    fmt::{self, Display, Formatter},
+    io::{self, BufRead, Read, Write},
+    str::FromStr,
+};
+
+use board_plugin::{
+    components::Coordinates,
+    minefield::{Cell, GameStatus, Minefield, MinefieldOptions, Move},
+    resources::tile::Tile,
+};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use crate::race::{Progress, RaceResult};
+
+/// Version of the messages below, bumped on any incompatible change
+pub const PROTOCOL_VERSION: u32 = 4;
+/// Longest message line read, in bytes. The snapshot of a board of `server::MAX_BOARD_SIDE`,
+/// the largest one hosted, fits well under it, and a peer can't make the other side buffer more
+pub const MAX_LINE: u64 = 4 * 1024 * 1024;
+
+/// Player number given by the host, unique for the lifetime of the host
+pub type PlayerId = u32;
+
+/// How the players of a host share the board
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Mode {
+    /// Everyone plays on the same board
+    #[default]
+    Coop,
+    /// Everyone races on their own copy of the same board, see `race::Race`
+    Versus,
+}
+
+impl Mode {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Coop => "coop",
+            Self::Versus => "versus",
+        }
+    }
+}
+
+impl Display for Mode {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(self.label())
+    }
+}
+
+impl FromStr for Mode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        [Self::Coop, Self::Versus].into_iter()
+            .find(|m| m.label() == s)
+            .ok_or_else(|| format!("unknown mode {}, expected coop or versus", s))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ClientMessage {
+    /// First message of a connection
+    Hello {
+        version: u32,
+        name: String,
+        /// Only watches the game
+        #[serde(default)]
+        spectator: bool,
+        /// Room to create or join, on a server hosting rooms
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        room: Option<RoomRequest>,
+        /// Seat to take back after a dropped connection
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        rejoin: Option<Rejoin>,
+    },
+    /// Move to validate and apply on the host board
+    Play { action: Move },
+    /// Tile under the player's cursor, shown to the other players
+    Cursor { coords: Coordinates },
+    /// Asks for a new board once the game is over. In versus, starts the next race
+    NewGame,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ServerMessage {
+    /// Accepts a connection, with everything needed to show the board as it is
+    Welcome {
+        version: u32,
+        /// Id given to the new player, or spectator
+        player: PlayerId,
+        /// Secret proving who the player is when rejoining
+        token: u64,
+        /// Code of the room joined, on a server hosting rooms
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        room: Option<String>,
+        mode: Mode,
+        /// Everyone playing, spectators aside
+        players: Vec<PlayerInfo>,
+        /// The shared board in co-op, the player's own copy in versus. Spectators of a
+        /// race watch the copy of its first racer
+        board: BoardSnapshot,
+    },
+    /// Refuses a connection, which the host then closes
+    Rejected { reason: String },
+    PlayerJoined { player: PlayerInfo },
+    PlayerLeft { player: PlayerId },
+    /// The connection of a player dropped, their seat is kept for a while
+    PlayerAway { player: PlayerId },
+    Cursor { player: PlayerId, coords: Coordinates },
+    /// Tiles changed by a move of `player`. In versus, only sent to the player whose copy changed
+    Update {
+        player: PlayerId,
+        /// Move that changed the tiles
+        action: Move,
+        tiles: Vec<TileUpdate>,
+        status: GameStatus,
+        /// Every mine of the board, once the game is over
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        mines: Vec<MineTile>,
+    },
+    /// A new board replacing the finished one
+    NewBoard { board: BoardSnapshot },
+    /// Answers a move the host refused to apply
+    Invalid { reason: String },
+    /// A race starts after the countdown, on the board of the last `NewBoard`
+    Countdown { millis: u64 },
+    /// How far a racer got on their copy of the board
+    Progress { player: PlayerId, progress: Progress },
+    /// Standings once every racer finished
+    Results { results: Vec<RaceResult> },
+}
+
+/// Room a hello is for, on a server hosting rooms
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum RoomRequest {
+    /// Opens a new room. The server picks the seed of the board
+    Create { mode: Mode, options: MinefieldOptions },
+    Join { room: String },
+}
+
+/// Seat of a player whose connection dropped
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Rejoin {
+    pub player: PlayerId,
+    pub token: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PlayerInfo {
+    pub id: PlayerId,
+    pub name: String,
+    pub cursor: Option<Coordinates>,
+    /// Connection dropped, the player may still come back
+    #[serde(default)]
+    pub away: bool,
+}
+
+/// New state of a tile as the players see it
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TileUpdate {
+    pub coords: Coordinates,
+    pub cell: Cell,
+    /// Player who placed the flags of a flagged tile
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub owner: Option<PlayerId>,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MineTile {
+    pub coords: Coordinates,
+    pub tile: Tile,
+}
+
+/// Visible state of a whole board: its parameters and every tile that isn't plainly covered
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BoardSnapshot {
+    pub width: u16,
+    pub height: u16,
+    pub bomb_count: u16,
+    pub max_bombs_per_tile: u8,
+    pub tiles: Vec<TileUpdate>,
+    pub status: GameStatus,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub mines: Vec<MineTile>,
+}
+
+impl BoardSnapshot {
+    /// Snapshot of a minefield, `owner` giving who flagged each flagged tile
+    pub fn new(field: &Minefield, owner: impl Fn(Coordinates) -> Option<PlayerId>) -> Self {
+        let tile_map = field.tile_map();
+        let tiles = coordinates(field)
+            .filter_map(|coords| match field.cell(coords)? {
+                Cell::Covered => None,
+                cell => Some(TileUpdate { coords, cell, owner: matches!(cell, Cell::Flagged(_)).then(|| owner(coords)).flatten() }),
+            })
+            .collect();
+        Self {
+            width: tile_map.width(),
+            height: tile_map.height(),
+            bomb_count: tile_map.bomb_count(),
+            max_bombs_per_tile: tile_map.max_bombs_per_tile(),
+            tiles,
+            status: field.status(),
+            mines: revealed_mines(field),
+        }
+    }
+}
+
+/// Every tile of a minefield, row by row from the bottom
+pub fn coordinates(field: &Minefield) -> impl Iterator<Item = Coordinates> {
+    let (width, height) = (field.width() as i32, field.height() as i32);
+    (0..height).flat_map(move |y| (0..width).map(move |x| Coordinates { x, y }))
+}
+
+/// Mines of a finished game, nothing while it is being played
+pub fn revealed_mines(field: &Minefield) -> Vec<MineTile> {
+    if field.status() == GameStatus::Playing {
+        return Vec::new();
+    }
+    coordinates(field)
+        .filter_map(|coords| {
+            let tile = *field.tile_map().get(coords)?;
+            tile.is_bomb().then_some(MineTile { coords, tile })
+        })
+        .collect()
+}
+
+/// Writes a message as a line of JSON
+pub fn write_message(out: &mut impl Write, message: &impl Serialize) -> io::Result<()> {
+    let mut line = serde_json::to_vec(message)?;
+    line.push(b'\n');
+    out.write_all(&line)?;
+    out.flush()
+}
+
+/// Reads the next message, `None` once the other side closed the connection. A line longer
+/// than `MAX_LINE` is an error
+pub fn read_message<T: DeserializeOwned>(input: &mut impl BufRead) -> io::Result<Option<T>> {
+    let mut line = String::new();
+    let read = input.by_ref().take(MAX_LINE).read_line(&mut line)?;
+    if read == 0 {
+        return Ok(None);
+    }
+    if read as u64 == MAX_LINE && !line.ends_with('\n') {
+        let message = format!("message longer than {} bytes", MAX_LINE);
+        return Err(io::Error::new(io::ErrorKind::InvalidData, message));
+    }
+    serde_json::from_str(&line)
+        .map(Some)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hello(name: String) -> ClientMessage {
+        ClientMessage::Hello { version: PROTOCOL_VERSION, name, spectator: false, room: None, rejoin: None }
+    }
+
+    #[test]
+    fn oversized_lines_are_rejected() {
+        let mut lines = Vec::new();
+        write_message(&mut lines, &hello("a".repeat(MAX_LINE as usize))).unwrap();
+        write_message(&mut lines, &hello("b".to_owned())).unwrap();
+        let error = read_message::<ClientMessage>(&mut lines.as_slice()).unwrap_err();
+        assert!(error.to_string().starts_with("message longer than"));
+
+        let mut line = Vec::new();
+        write_message(&mut line, &hello("b".to_owned())).unwrap();
+        let read = read_message::<ClientMessage>(&mut line.as_slice()).unwrap();
+        assert!(matches!(read, Some(ClientMessage::Hello { name, .. }) if name == "b"));
+    }
+}
@@ -0,0 +1,337 @@
+//! Server hosting many independent rooms, each playing its own `Session`.
+//!
+//! A client opens with a hello creating a room or joining one by its code, and the room
+//! then runs exactly like a single game host. Rooms close once everyone left and no seat
+//! is kept for anyone. Nothing here draws anything: the server only enforces the rules,
+//! so it runs without a display.
+
+use std::{
+    collections::HashMap,
+    net::TcpListener,
+    sync::{
+        mpsc::{self, RecvTimeoutError},
+        Arc,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use board_plugin::{
+    minefield::{Generation, MinefieldOptions},
+    resources::tilemap::MAX_BOMBS_PER_TILE,
+};
+
+use super::{
+    host::{accept, ConnectionId, Event, Writer, TICK},
+    protocol::{ClientMessage, PlayerId, RoomRequest, ServerMessage, PROTOCOL_VERSION},
+    session::{secret, Outgoing, Session, RECONNECT_GRACE},
+};
+
+/// Letters of room codes, leaving out the ones easily mistaken for others
+const ROOM_CODE_LETTERS: &[u8] = b"ABCDEFGHJKLMNPQRSTUVWXYZ23456789";
+const ROOM_CODE_LENGTH: usize = 5;
+
+/// Widest and highest board a room can be created with
+pub const MAX_BOARD_SIDE: u16 = 100;
+
+#[derive(Debug, Copy, Clone)]
+pub struct ServerOptions {
+    /// Most rooms open at once
+    pub max_rooms: usize,
+    /// Time the seat of a player whose connection dropped is kept
+    pub reconnect_grace: Duration,
+    /// Prints rooms opening and closing on stderr
+    pub log: bool,
+}
+
+impl Default for ServerOptions {
+    fn default() -> Self {
+        Self {
+            max_rooms: 100,
+            reconnect_grace: RECONNECT_GRACE,
+            log: false,
+        }
+    }
+}
+
+struct Connection {
+    writer: Writer,
+    /// Room and player, set once the client said hello
+    seat: Option<(String, PlayerId)>,
+}
+
+struct Server {
+    options: ServerOptions,
+    rooms: HashMap<String, Session>,
+    connections: HashMap<ConnectionId, Connection>,
+}
+
+/// Accepts clients on `listener` and runs the rooms they create. Never returns
+pub fn serve(listener: TcpListener, options: ServerOptions) {
+    let (events, incoming) = mpsc::channel();
+    thread::spawn(move || accept(listener, events));
+
+    let mut server = Server {
+        options,
+        rooms: HashMap::new(),
+        connections: HashMap::new(),
+    };
+    let mut next_check = Instant::now() + TICK;
+    loop {
+        let event = incoming.recv_timeout(next_check.saturating_duration_since(Instant::now()));
+        let now = Instant::now();
+        if now >= next_check {
+            server.expire(now);
+            next_check = now + TICK;
+        }
+        match event {
+            Ok(Event::Connected(id, writer)) => {
+                server.connections.insert(id, Connection { writer, seat: None });
+            }
+            Ok(Event::Message(id, message)) => server.handle(id, message),
+            Ok(Event::Closed(id)) => server.closed(id),
+            Err(RecvTimeoutError::Timeout) => (),
+            Err(RecvTimeoutError::Disconnected) => return,
+        }
+    }
+}
+
+impl Server {
+    fn handle(&mut self, id: ConnectionId, message: ClientMessage) {
+        let Some(connection) = self.connections.get_mut(&id) else { return; };
+        let Some((room, player)) = connection.seat.clone() else {
+            if let Err(reason) = self.hello(id, message) {
+                self.reject(id, reason);
+            }
+            return;
+        };
+        let Some(session) = self.rooms.get_mut(&room) else { return; };
+        let outgoing = session.handle(player, message);
+        self.deliver(&room, outgoing);
+    }
+
+    /// Seats a new client in the room its hello asks for, creating the room if needed
+    fn hello(&mut self, id: ConnectionId, message: ClientMessage) -> Result<(), String> {
+        let ClientMessage::Hello { version, name, spectator, room, rejoin } = message else {
+            return Err("expected a hello message".to_owned());
+        };
+        if version != PROTOCOL_VERSION {
+            return Err(format!("protocol version {} is not supported, the server speaks version {}", version, PROTOCOL_VERSION));
+        }
+        let code = match room {
+            None => return Err("this server hosts rooms, create or join one".to_owned()),
+            Some(RoomRequest::Join { room }) => room.trim().to_uppercase(),
+            Some(RoomRequest::Create { mode, options }) => {
+                check_options(&options)?;
+                if self.rooms.len() >= self.options.max_rooms {
+                    return Err("the server is full, try again later".to_owned());
+                }
+                let code = self.new_code();
+                // The board stays secret from its creator too
+                let options = MinefieldOptions { seed: secret(), ..options };
+                let session = Session::new(mode, options).in_room(code.clone()).reconnect_grace(self.options.reconnect_grace);
+                self.rooms.insert(code.clone(), session);
+                if self.options.log {
+                    let (width, height) = options.map_size;
+                    eprintln!("Room {} opened: {}, {}x{} with {} mines", code, mode, width, height, options.bomb_count);
+                }
+                code
+            }
+        };
+        let Some(session) = self.rooms.get_mut(&code) else { return Err(format!("there is no room {}", code)); };
+        let hello = ClientMessage::Hello { version, name, spectator, room: Some(RoomRequest::Join { room: code.clone() }), rejoin };
+        let joined = session.join(hello);
+        let (player, outgoing) = match joined {
+            Ok(joined) => joined,
+            Err(reason) => {
+                self.close_if_empty(&code);
+                return Err(reason);
+            }
+        };
+        for (other, connection) in self.connections.iter_mut() {
+            if *other == id {
+                connection.seat = Some((code.clone(), player));
+            } else if connection.seat.as_ref() == Some(&(code.clone(), player)) {
+                // The player rejoined before their old connection timed out
+                connection.seat = None;
+                connection.writer.shutdown();
+            }
+        }
+        self.deliver(&code, outgoing);
+        Ok(())
+    }
+
+    fn reject(&mut self, id: ConnectionId, reason: String) {
+        let Some(connection) = self.connections.get_mut(&id) else { return; };
+        connection.writer.send(ServerMessage::Rejected { reason });
+        connection.writer.close();
+    }
+
+    fn closed(&mut self, id: ConnectionId) {
+        let Some((room, player)) = self.connections.remove(&id).and_then(|c| c.seat) else { return; };
+        let Some(session) = self.rooms.get_mut(&room) else { return; };
+        let outgoing = session.disconnected(player, Instant::now());
+        self.deliver(&room, outgoing);
+        self.close_if_empty(&room);
+    }
+
+    /// Gives up the seats kept too long, closing the rooms left empty
+    fn expire(&mut self, now: Instant) {
+        let expired: Vec<_> = self.rooms.iter_mut().map(|(room, session)| (room.clone(), session.expire(now))).collect();
+        for (room, outgoing) in expired {
+            if !outgoing.is_empty() {
+                self.deliver(&room, outgoing);
+                self.close_if_empty(&room);
+            }
+        }
+    }
+
+    fn close_if_empty(&mut self, room: &str) {
+        if self.rooms.get(room).is_some_and(Session::is_empty) {
+            self.rooms.remove(room);
+            if self.options.log {
+                eprintln!("Room {} closed", room);
+            }
+        }
+    }
+
+    fn deliver(&mut self, room: &str, outgoing: Vec<Outgoing>) {
+        for Outgoing { to, message } in outgoing {
+            let message = Arc::new(message);
+            for connection in self.connections.values_mut() {
+                if connection.seat.as_ref().is_some_and(|(r, p)| r == room && to.includes(*p)) {
+                    connection.writer.send(message.clone());
+                }
+            }
+        }
+    }
+
+    /// Random code of no open room
+    fn new_code(&self) -> String {
+        loop {
+            let code: String = (0..ROOM_CODE_LENGTH)
+                .map(|_| ROOM_CODE_LETTERS[(secret() % ROOM_CODE_LETTERS.len() as u64) as usize] as char)
+                .collect();
+            if !self.rooms.contains_key(&code) {
+                return code;
+            }
+        }
+    }
+}
+
+/// Refuses boards too big to be generated and sent quickly, or that can't hold their mines
+fn check_options(options: &MinefieldOptions) -> Result<(), String> {
+    let (width, height) = options.map_size;
+    if !(1..=MAX_BOARD_SIDE).contains(&width) || !(1..=MAX_BOARD_SIDE).contains(&height) {
+        return Err(format!("boards are 1 to {} tiles wide and high", MAX_BOARD_SIDE));
+    }
+    if !(1..=MAX_BOMBS_PER_TILE).contains(&options.max_bombs_per_tile) {
+        return Err(format!("tiles hold 1 to {} mines", MAX_BOMBS_PER_TILE));
+    }
+    if options.bomb_count == 0 {
+        return Err("a board needs at least one mine".to_owned());
+    }
+    let capacity = width as u32 * height as u32 * options.max_bombs_per_tile as u32;
+    if options.bomb_count as u32 > capacity {
+        return Err(format!("a {}x{} board holds at most {} mines", width, height, capacity));
+    }
+    // Its solver runs on the loop serving every room, and may try many layouts on big boards
+    if options.generation == Generation::NoGuess {
+        return Err("no-guess boards are not available on this server".to_owned());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{io::BufReader, net::TcpStream};
+
+    use board_plugin::{components::Coordinates, minefield::Move};
+
+    use super::*;
+    use crate::net::protocol::{read_message, write_message, Mode};
+
+    /// Address of a server running in the background
+    fn start_server() -> std::net::SocketAddr {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        thread::spawn(move || serve(listener, ServerOptions::default()));
+        address
+    }
+
+    struct Client {
+        stream: TcpStream,
+        reader: BufReader<TcpStream>,
+    }
+
+    impl Client {
+        fn hello(address: std::net::SocketAddr, room: RoomRequest) -> Self {
+            let stream = TcpStream::connect(address).unwrap();
+            stream.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+            let reader = BufReader::new(stream.try_clone().unwrap());
+            let mut client = Self { stream, reader };
+            let name = "Player".to_owned();
+            client.send(ClientMessage::Hello { version: PROTOCOL_VERSION, name, spectator: false, room: Some(room), rejoin: None });
+            client
+        }
+
+        fn send(&mut self, message: ClientMessage) {
+            write_message(&mut self.stream, &message).unwrap();
+        }
+
+        fn receive(&mut self) -> ServerMessage {
+            read_message(&mut self.reader).unwrap().expect("connection closed")
+        }
+    }
+
+    fn create(options: MinefieldOptions) -> RoomRequest {
+        RoomRequest::Create { mode: Mode::Coop, options }
+    }
+
+    fn options(map_size: (u16, u16), bomb_count: u16, max_bombs_per_tile: u8) -> MinefieldOptions {
+        MinefieldOptions { map_size, bomb_count, max_bombs_per_tile, seed: 0, generation: Generation::Random }
+    }
+
+    #[test]
+    fn players_share_a_room() {
+        let address = start_server();
+        let mut host = Client::hello(address, create(options((9, 9), 10, 1)));
+        let ServerMessage::Welcome { player: first, room: Some(room), .. } = host.receive() else { panic!("not welcomed") };
+
+        let mut guest = Client::hello(address, RoomRequest::Join { room: room.to_lowercase() });
+        let ServerMessage::Welcome { player: second, room: joined, players, .. } = guest.receive() else { panic!("not welcomed") };
+        assert_eq!(joined, Some(room));
+        assert_ne!(first, second);
+        assert_eq!(players.len(), 2);
+        assert!(matches!(host.receive(), ServerMessage::PlayerJoined { player } if player.id == second));
+
+        let action = Move::Flag(Coordinates { x: 0, y: 0 });
+        host.send(ClientMessage::Play { action });
+        for client in [&mut host, &mut guest] {
+            let ServerMessage::Update { player, action: played, tiles, .. } = client.receive() else { panic!("no update") };
+            assert_eq!((player, played, tiles.len()), (first, action, 1));
+        }
+    }
+
+    #[test]
+    fn invalid_rooms_are_rejected() {
+        let address = start_server();
+        let invalid = [
+            options((9, 9), 10, MAX_BOMBS_PER_TILE + 1),
+            options((9, 9), 10, 0),
+            options((3, 3), 10, 1),
+            options((0, 9), 1, 1),
+            MinefieldOptions { generation: Generation::NoGuess, ..options((9, 9), 10, 1) },
+        ];
+        for options in invalid {
+            let mut client = Client::hello(address, create(options));
+            assert!(matches!(client.receive(), ServerMessage::Rejected { .. }), "{:?} accepted", options);
+        }
+        let mut client = Client::hello(address, RoomRequest::Join { room: "NONE".to_owned() });
+        assert!(matches!(client.receive(), ServerMessage::Rejected { .. }));
+
+        // The server still runs after refusing them
+        let mut client = Client::hello(address, create(options((3, 3), 9, 1)));
+        assert!(matches!(client.receive(), ServerMessage::Welcome { .. }));
+    }
+}
@@ -0,0 +1,419 @@
+//! Authoritative game shared by the players of a host, independent of any connection.
+
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    time::{Duration, Instant},
+};
+
+use board_plugin::{
+    components::Coordinates,
+    minefield::{Cell, GameStatus, Minefield, MinefieldOptions, Move},
+};
+
+use super::protocol::{
+    coordinates, revealed_mines, BoardSnapshot, ClientMessage, Mode, PlayerId, PlayerInfo, Rejoin, RoomRequest,
+    ServerMessage, TileUpdate, PROTOCOL_VERSION,
+};
+use crate::race::{Race, COUNTDOWN};
+
+/// Longest player name kept, in characters
+const MAX_NAME_LENGTH: usize = 24;
+
+/// Time the seat of a player whose connection dropped is kept by default
+pub const RECONNECT_GRACE: Duration = Duration::from_secs(30);
+
+/// Players a message is sent to
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Recipient {
+    All,
+    Player(PlayerId),
+    /// Everyone but the given player
+    Others(PlayerId),
+}
+
+impl Recipient {
+    pub fn includes(&self, player: PlayerId) -> bool {
+        match *self {
+            Self::All => true,
+            Self::Player(id) => id == player,
+            Self::Others(id) => id != player,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Outgoing {
+    pub to: Recipient,
+    pub message: ServerMessage,
+}
+
+impl Outgoing {
+    fn new(to: Recipient, message: ServerMessage) -> Self {
+        Self { to, message }
+    }
+}
+
+/// A minefield played together: every move is checked and applied here, and the
+/// resulting messages tell each player what changed
+#[derive(Debug)]
+pub struct Session {
+    mode: Mode,
+    options: MinefieldOptions,
+    /// Code of the room, on a server hosting rooms
+    room: Option<String>,
+    /// Board shared by everyone in co-op. In versus, the board shown until the first race
+    field: Minefield,
+    players: BTreeMap<PlayerId, PlayerInfo>,
+    spectators: BTreeSet<PlayerId>,
+    /// Most players allowed at once, everyone else can only watch
+    player_limit: Option<usize>,
+    /// Secret of each player, to take their seat back after a dropped connection
+    tokens: HashMap<PlayerId, u64>,
+    /// When the connection of each away player dropped
+    away: HashMap<PlayerId, Instant>,
+    reconnect_grace: Duration,
+    /// Player who last changed the flags of each flagged tile, in co-op
+    flag_owners: HashMap<Coordinates, PlayerId>,
+    /// Current or last race, in versus
+    race: Option<Race>,
+    next_player: PlayerId,
+}
+
+impl Session {
+    pub fn new(mode: Mode, options: MinefieldOptions) -> Self {
+        Self {
+            mode,
+            field: Minefield::generate(&options),
+            options,
+            room: None,
+            players: BTreeMap::new(),
+            spectators: BTreeSet::new(),
+            player_limit: None,
+            tokens: HashMap::new(),
+            away: HashMap::new(),
+            reconnect_grace: RECONNECT_GRACE,
+            flag_owners: HashMap::new(),
+            race: None,
+            next_player: 1,
+        }
+    }
+
+    /// Lets at most `limit` players in, turning the others away to spectate
+    pub fn limit_players(mut self, limit: usize) -> Self {
+        self.player_limit = Some(limit);
+        self
+    }
+
+    /// Names the session after the room of a server it plays in
+    pub fn in_room(mut self, room: String) -> Self {
+        self.room = Some(room);
+        self
+    }
+
+    /// Keeps the seat of a player whose connection dropped for `grace`, `RECONNECT_GRACE`
+    /// by default. Zero lets players go as soon as they drop
+    pub fn reconnect_grace(mut self, grace: Duration) -> Self {
+        self.reconnect_grace = grace;
+        self
+    }
+
+    pub fn mode(&self) -> Mode {
+        self.mode
+    }
+
+    pub fn options(&self) -> &MinefieldOptions {
+        &self.options
+    }
+
+    /// No one plays or watches, and no seat is kept for anyone
+    pub fn is_empty(&self) -> bool {
+        self.players.is_empty() && self.spectators.is_empty()
+    }
+
+    pub fn field(&self) -> &Minefield {
+        &self.field
+    }
+
+    pub fn players(&self) -> impl Iterator<Item = &PlayerInfo> {
+        self.players.values()
+    }
+
+    /// Board as `player` sees it: the shared one, or their copy in a race
+    pub fn snapshot(&self, player: PlayerId) -> BoardSnapshot {
+        let racer = match self.spectators.contains(&player) {
+            true => self.watched_racer(),
+            false => Some(player),
+        };
+        match self.race.as_ref().zip(racer).and_then(|(race, racer)| Some((race.field(racer)?, racer))) {
+            Some((field, racer)) => BoardSnapshot::new(field, |_| Some(racer)),
+            None => BoardSnapshot::new(&self.field, |c| self.flag_owners.get(&c).copied()),
+        }
+    }
+
+    /// Racer whose board the spectators of a race watch
+    fn watched_racer(&self) -> Option<PlayerId> {
+        self.race.as_ref()?.players().next()
+    }
+
+    /// Adds the player greeting the host with `hello`. Returns the new player id with the
+    /// messages to send, or why the player was refused
+    pub fn join(&mut self, hello: ClientMessage) -> Result<(PlayerId, Vec<Outgoing>), String> {
+        let ClientMessage::Hello { version, name, spectator, room, rejoin } = hello else {
+            return Err("expected a hello message".to_owned());
+        };
+        if version != PROTOCOL_VERSION {
+            return Err(format!("protocol version {} is not supported, the host speaks version {}", version, PROTOCOL_VERSION));
+        }
+        match (room, &self.room) {
+            (None, None) => (),
+            (Some(RoomRequest::Join { room }), Some(code)) if room == *code => (),
+            (Some(_), None) => return Err("this host plays a single game, join it without a room".to_owned()),
+            (_, Some(_)) => return Err("the hello is for another room".to_owned()),
+        }
+        if let Some(Rejoin { player, token }) = rejoin.filter(|_| !spectator) {
+            if self.tokens.get(&player) != Some(&token) {
+                return Err("your seat was given up".to_owned());
+            }
+            return Ok((player, self.rejoin(player)));
+        }
+        if !spectator && self.player_limit.is_some_and(|limit| self.players.len() >= limit) {
+            return Err("the game is full, join as a spectator".to_owned());
+        }
+        let id = self.next_player;
+        self.next_player += 1;
+        if spectator {
+            self.spectators.insert(id);
+            return Ok((id, vec![Outgoing::new(Recipient::Player(id), self.welcome(id))]));
+        }
+        let name: String = name.trim().chars().take(MAX_NAME_LENGTH).collect();
+        let info = PlayerInfo {
+            id,
+            name: if name.is_empty() { format!("player {}", id) } else { name },
+            cursor: None,
+            away: false,
+        };
+        self.players.insert(id, info.clone());
+        self.tokens.insert(id, secret());
+        Ok((id, vec![
+            Outgoing::new(Recipient::Player(id), self.welcome(id)),
+            Outgoing::new(Recipient::Others(id), ServerMessage::PlayerJoined { player: info }),
+        ]))
+    }
+
+    /// Gives an away player their seat back, on a new connection
+    fn rejoin(&mut self, player: PlayerId) -> Vec<Outgoing> {
+        self.away.remove(&player);
+        let Some(info) = self.players.get_mut(&player) else { return Vec::new(); };
+        info.away = false;
+        info.cursor = None;
+        let info = info.clone();
+        vec![
+            Outgoing::new(Recipient::Player(player), self.welcome(player)),
+            Outgoing::new(Recipient::Others(player), ServerMessage::PlayerJoined { player: info }),
+        ]
+    }
+
+    fn welcome(&self, player: PlayerId) -> ServerMessage {
+        ServerMessage::Welcome {
+            version: PROTOCOL_VERSION,
+            player,
+            token: self.tokens.get(&player).copied().unwrap_or_default(),
+            room: self.room.clone(),
+            mode: self.mode,
+            players: self.players.values().cloned().collect(),
+            board: self.snapshot(player),
+        }
+    }
+
+    /// Keeps the seat of a player whose connection dropped until they rejoin, or until
+    /// `expire` gives it up
+    pub fn disconnected(&mut self, player: PlayerId, now: Instant) -> Vec<Outgoing> {
+        if self.reconnect_grace.is_zero() || self.spectators.contains(&player) {
+            return self.leave(player);
+        }
+        let Some(info) = self.players.get_mut(&player) else { return Vec::new(); };
+        info.away = true;
+        self.away.insert(player, now);
+        vec![Outgoing::new(Recipient::Others(player), ServerMessage::PlayerAway { player })]
+    }
+
+    /// Gives up the seats of the players away for longer than the grace period
+    pub fn expire(&mut self, now: Instant) -> Vec<Outgoing> {
+        let expired: Vec<_> = self.away.iter()
+            .filter(|(_, since)| now.saturating_duration_since(**since) >= self.reconnect_grace)
+            .map(|(player, _)| *player)
+            .collect();
+        expired.into_iter().flat_map(|player| self.leave(player)).collect()
+    }
+
+    pub fn leave(&mut self, player: PlayerId) -> Vec<Outgoing> {
+        if self.spectators.remove(&player) || self.players.remove(&player).is_none() {
+            return Vec::new();
+        }
+        self.tokens.remove(&player);
+        self.away.remove(&player);
+        let mut outgoing = vec![Outgoing::new(Recipient::All, ServerMessage::PlayerLeft { player })];
+        if let Some(race) = self.race.as_mut().filter(|r| r.field(player).is_some()) {
+            let was_over = race.is_over();
+            race.remove(player);
+            // The others may all have finished already
+            if !was_over && race.is_over() && race.players().next().is_some() {
+                outgoing.push(Outgoing::new(Recipient::All, ServerMessage::Results { results: race.results(Instant::now()) }));
+            }
+        }
+        outgoing
+    }
+
+    /// Handles a message of a player who joined
+    pub fn handle(&mut self, player: PlayerId, message: ClientMessage) -> Vec<Outgoing> {
+        if self.spectators.contains(&player) {
+            return invalid(player, "spectators can't play");
+        }
+        if !self.players.contains_key(&player) {
+            return Vec::new();
+        }
+        match message {
+            ClientMessage::Hello { .. } => invalid(player, "already joined"),
+            ClientMessage::Play { action } => {
+                if !self.field.in_bounds(action.coordinates()) {
+                    return invalid(player, "the tile is outside of the board");
+                }
+                match self.mode {
+                    Mode::Coop => self.play(player, action),
+                    Mode::Versus => self.race_play(player, action),
+                }
+            }
+            ClientMessage::Cursor { coords } => {
+                if !self.field.in_bounds(coords) {
+                    return invalid(player, "the tile is outside of the board");
+                }
+                if let Some(info) = self.players.get_mut(&player) {
+                    info.cursor = Some(coords);
+                }
+                match self.mode {
+                    Mode::Coop => vec![Outgoing::new(Recipient::Others(player), ServerMessage::Cursor { player, coords })],
+                    // Cursors on other copies of the board mean nothing
+                    Mode::Versus => Vec::new(),
+                }
+            }
+            ClientMessage::NewGame => match self.mode {
+                Mode::Coop => self.new_game(player),
+                Mode::Versus => self.start_race(player),
+            },
+        }
+    }
+
+    /// Applies a move on the shared board and sends the tiles it changed to everyone
+    fn play(&mut self, player: PlayerId, action: Move) -> Vec<Outgoing> {
+        if self.field.status() != GameStatus::Playing {
+            return invalid(player, "the game is over");
+        }
+        let before = cells(&self.field);
+        if !self.field.play(action) {
+            return Vec::new();
+        }
+        let tiles = changes(&self.field, before, player);
+        for update in &tiles {
+            match update.owner {
+                Some(owner) => self.flag_owners.insert(update.coords, owner),
+                None => self.flag_owners.remove(&update.coords),
+            };
+        }
+        vec![Outgoing::new(Recipient::All, ServerMessage::Update {
+            player,
+            action,
+            tiles,
+            status: self.field.status(),
+            mines: revealed_mines(&self.field),
+        })]
+    }
+
+    fn new_game(&mut self, player: PlayerId) -> Vec<Outgoing> {
+        if self.field.status() == GameStatus::Playing {
+            return invalid(player, "the game is still being played");
+        }
+        self.options.seed = self.options.seed.wrapping_add(1);
+        self.field = Minefield::generate(&self.options);
+        self.flag_owners.clear();
+        vec![Outgoing::new(Recipient::All, ServerMessage::NewBoard { board: self.snapshot(player) })]
+    }
+
+    /// Applies a move on the player's copy of the race board. Only the player gets the
+    /// changed tiles, everyone gets their progress
+    fn race_play(&mut self, player: PlayerId, action: Move) -> Vec<Outgoing> {
+        let now = Instant::now();
+        let Some(race) = self.race.as_mut() else { return invalid(player, "the race hasn't started yet"); };
+        let Some(before) = race.field(player).map(cells) else { return invalid(player, "wait for the next race"); };
+        match race.play(player, action, now) {
+            Err(reason) => return invalid(player, reason),
+            Ok(false) => return Vec::new(),
+            Ok(true) => (),
+        }
+        let Some((field, progress)) = race.field(player).zip(race.progress(player)) else { return Vec::new(); };
+        let update = ServerMessage::Update {
+            player,
+            action,
+            tiles: changes(field, before, player),
+            status: field.status(),
+            mines: revealed_mines(field),
+        };
+        let watched = race.players().next() == Some(player);
+        let viewers = std::iter::once(player).chain(self.spectators.iter().copied().filter(|_| watched));
+        let mut outgoing: Vec<_> = viewers.map(|id| Outgoing::new(Recipient::Player(id), update.clone())).collect();
+        outgoing.push(Outgoing::new(Recipient::All, ServerMessage::Progress { player, progress }));
+        if race.is_over() {
+            outgoing.push(Outgoing::new(Recipient::All, ServerMessage::Results { results: race.results(now) }));
+        }
+        outgoing
+    }
+
+    /// Starts a race between everyone connected, on the next board once a race was run
+    fn start_race(&mut self, player: PlayerId) -> Vec<Outgoing> {
+        if self.race.as_ref().is_some_and(|r| !r.is_over()) {
+            return invalid(player, "the race is still running");
+        }
+        // Away players would hold the race up until they come back
+        if self.players.values().filter(|p| !p.away).count() < 2 {
+            return invalid(player, "waiting for another player");
+        }
+        if self.race.is_some() {
+            self.options.seed = self.options.seed.wrapping_add(1);
+        }
+        let racers = self.players.values().filter(|p| !p.away).map(|p| (p.id, p.name.clone()));
+        let race = Race::new(self.options, racers, Instant::now());
+        // Every copy is the same, and nothing is flagged yet
+        let board = race.field(player).map(|f| BoardSnapshot::new(f, |_| None));
+        self.race = Some(race);
+        let Some(board) = board else { return Vec::new(); };
+        vec![
+            Outgoing::new(Recipient::All, ServerMessage::NewBoard { board }),
+            Outgoing::new(Recipient::All, ServerMessage::Countdown { millis: COUNTDOWN.as_millis() as u64 }),
+        ]
+    }
+}
+
+/// Random number no one can guess from the outside
+pub(super) fn secret() -> u64 {
+    rand::random()
+}
+
+fn invalid(player: PlayerId, reason: &str) -> Vec<Outgoing> {
+    vec![Outgoing::new(Recipient::Player(player), ServerMessage::Invalid { reason: reason.to_owned() })]
+}
+
+/// Every tile as the players see it, to compare before and after a move
+fn cells(field: &Minefield) -> Vec<Option<Cell>> {
+    coordinates(field).map(|c| field.cell(c)).collect()
+}
+
+/// Tiles that differ from `before`, flags being owned by the player who moved
+fn changes(field: &Minefield, before: Vec<Option<Cell>>, player: PlayerId) -> Vec<TileUpdate> {
+    coordinates(field)
+        .zip(before)
+        .filter_map(|(coords, previous)| {
+            let cell = field.cell(coords)?;
+            let owner = matches!(cell, Cell::Flagged(_)).then_some(player);
+            (Some(cell) != previous).then_some(TileUpdate { coords, cell, owner })
+        })
+        .collect()
+}
@@ -0,0 +1,157 @@
+use bevy::{prelude::*, log};
+use board_plugin::{
+    BoardState,
+    events::GameEndedEvent,
+    puzzle::{PackEntry, PUZZLE_PACK},
+    resources::{ActionInput, BoardAssets, BoardOptions, BoardStart, InputAction, PuzzleChallenge, PuzzleResults},
+};
+
+use crate::{AppState, ResumeState};
+
+const BUTTON_COLOR: Color = Color::rgb(0.25, 0.25, 0.25);
+
+/// Puzzle pack screen, listing the bundled puzzles with the ones already cleared
+pub struct PuzzlesPlugin;
+
+impl Plugin for PuzzlesPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(spawn_puzzles.in_schedule(OnEnter(AppState::Puzzles)));
+        app.add_system(despawn_puzzles.in_schedule(OnExit(AppState::Puzzles)));
+        app.add_systems((puzzle_buttons, leave_puzzles).in_set(OnUpdate(AppState::Puzzles)));
+        app.add_system(record_puzzle);
+    }
+}
+
+/// Back to generated boards, leaving the puzzle being played if any
+pub fn leave_puzzle(cmd: &mut Commands) {
+    cmd.remove_resource::<BoardStart>();
+    cmd.remove_resource::<PuzzleChallenge>();
+}
+
+#[derive(Component)]
+struct PuzzlesRoot;
+
+#[derive(Component)]
+struct PuzzleButton(&'static PackEntry);
+
+fn spawn_puzzles(mut cmd: Commands, assets: Res<BoardAssets>, results: Res<PuzzleResults>) {
+    let text_style = |size: f32| TextStyle {
+        font: assets.bomb_counter_font.clone(),
+        font_size: size,
+        color: Color::WHITE,
+    };
+
+    cmd.spawn(NodeBundle {
+        style: Style {
+            size: Size::new(Val::Percent(100.), Val::Percent(100.)),
+            flex_direction: FlexDirection::Column,
+            align_items: AlignItems::Center,
+            justify_content: JustifyContent::Center,
+            ..default()
+        },
+        background_color: Color::rgb(0.1, 0.1, 0.1).into(),
+        ..default()
+    })
+    .insert(Name::new("Puzzles"))
+    .insert(PuzzlesRoot)
+    .with_children(|parent| {
+        let solved = PUZZLE_PACK.iter().filter(|e| results.completion(e.id).is_some()).count();
+        parent.spawn(TextBundle::from_section("Puzzles", text_style(24.)));
+        parent.spawn(TextBundle::from_section(format!("{}/{} solved\n", solved, PUZZLE_PACK.len()), text_style(10.)));
+        for entry in PUZZLE_PACK {
+            let puzzle = match entry.puzzle() {
+                Ok(puzzle) => puzzle,
+                Err(e) => {
+                    log::error!("{}", e);
+                    continue;
+                }
+            };
+            let completion = match results.completion(entry.id) {
+                Some(c) => format!("Solved in {:.2}s", c.best_time),
+                None => "Not solved".to_owned(),
+            };
+            parent.spawn(ButtonBundle {
+                style: Style {
+                    size: Size::width(Val::Px(420.)),
+                    justify_content: JustifyContent::SpaceBetween,
+                    padding: UiRect::all(Val::Px(4.)),
+                    margin: UiRect::all(Val::Px(2.)),
+                    ..default()
+                },
+                background_color: BUTTON_COLOR.into(),
+                ..default()
+            })
+            .insert(PuzzleButton(entry))
+            .with_children(|button| {
+                let size = format!("{}x{}, {} mines", puzzle.width(), puzzle.height(), puzzle.bomb_count());
+                button.spawn(TextBundle::from_section(puzzle.name, text_style(10.)));
+                button.spawn(TextBundle::from_section(format!("{}   {}", size, completion), text_style(8.)));
+            });
+        }
+        parent.spawn(TextBundle::from_section(
+            "\nEvery puzzle can be cleared without guessing. Escape to go back",
+            text_style(8.),
+        ));
+    });
+}
+
+fn despawn_puzzles(mut cmd: Commands, roots: Query<Entity, With<PuzzlesRoot>>) {
+    for root in roots.iter() {
+        cmd.entity(root).despawn_recursive();
+    }
+}
+
+fn puzzle_buttons(
+    mut cmd: Commands,
+    buttons: Query<(&Interaction, &PuzzleButton), Changed<Interaction>>,
+    mut board_options: ResMut<BoardOptions>,
+    mut next_app_state: ResMut<NextState<AppState>>,
+    mut next_board_state: ResMut<NextState<BoardState>>,
+) {
+    let Some((_, PuzzleButton(entry))) = buttons.iter().find(|(i, _)| **i == Interaction::Clicked) else { return; };
+    let (field, puzzle) = match entry.puzzle().and_then(|puzzle| Ok((puzzle.minefield()?, puzzle))) {
+        Ok(started) => started,
+        Err(e) => {
+            log::error!("{}", e);
+            return;
+        }
+    };
+    log::info!("Starting puzzle {}", entry.id);
+    board_options.map_size = (field.width(), field.height());
+    board_options.bomb_count = field.tile_map().bomb_count();
+    board_options.max_bombs_per_tile = field.tile_map().max_bombs_per_tile();
+    board_options.practice_mode = false;
+    board_options.seed = None;
+    cmd.insert_resource(BoardStart(field));
+    cmd.insert_resource(PuzzleChallenge { id: entry.id.to_owned(), name: puzzle.name });
+    next_app_state.set(AppState::InGame);
+    next_board_state.set(BoardState::Active);
+}
+
+fn leave_puzzles(
+    input: ActionInput,
+    resume: Res<ResumeState>,
+    mut next_app_state: ResMut<NextState<AppState>>,
+) {
+    if input.just_pressed(InputAction::Pause) || input.just_pressed(InputAction::Puzzles) {
+        next_app_state.set(resume.0);
+    }
+}
+
+/// Records a puzzle cleared and saves it
+pub fn record_puzzle(
+    mut ended_evr: EventReader<GameEndedEvent>,
+    challenge: Option<Res<PuzzleChallenge>>,
+    mut results: ResMut<PuzzleResults>,
+) {
+    let Some(GameEndedEvent(result)) = ended_evr.iter().last() else { return; };
+    let Some(challenge) = challenge else { return; };
+    // A bot clearing the board solves nothing
+    if !result.won || result.bot {
+        return;
+    }
+    results.complete(&challenge.id, result.time);
+    if let Err(e) = results.save() {
+        log::warn!("Failed to save puzzle results to {}: {}", results.path().display(), e);
+    }
+}
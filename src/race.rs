@@ -0,0 +1,176 @@
+//! Versus races: players clearing their own copy of the same seeded board, fastest wins.
+
+use std::{
+    collections::BTreeMap,
+    time::{Duration, Instant},
+};
+
+use board_plugin::{
+    metrics::{solved_bbbv, BoardMetrics},
+    minefield::{GameStatus, Generation, Minefield, MinefieldOptions, Move},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::net::protocol::PlayerId;
+
+/// Time between the start of a race and the first move allowed
+pub const COUNTDOWN: Duration = Duration::from_secs(3);
+
+/// How far a racer got
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Progress {
+    /// Share of the safe tiles uncovered, from 0 to 1
+    pub cleared: f32,
+    pub status: GameStatus,
+}
+
+/// Final standing of a racer
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RaceResult {
+    pub player: PlayerId,
+    pub name: String,
+    pub status: GameStatus,
+    /// Time from the start to the win or explosion
+    pub seconds: f32,
+    pub bbbv: u32,
+    pub solved_bbbv: u32,
+    pub bbbv_per_second: f32,
+}
+
+#[derive(Debug)]
+struct Racer {
+    name: String,
+    field: Minefield,
+    /// Time the racer took, once their game is over
+    time: Option<Duration>,
+}
+
+/// A race between players, each on their own copy of the board
+#[derive(Debug)]
+pub struct Race {
+    options: MinefieldOptions,
+    metrics: BoardMetrics,
+    racers: BTreeMap<PlayerId, Racer>,
+    /// End of the countdown
+    start: Instant,
+}
+
+impl Race {
+    /// Starts a race on the board of `options`, with a countdown from `now`. Boards placing
+    /// their mines on the first reveal start with an opening instead, so every copy stays
+    /// the same whatever the racers click first
+    pub fn new(options: MinefieldOptions, racers: impl IntoIterator<Item = (PlayerId, String)>, now: Instant) -> Self {
+        let options = MinefieldOptions { generation: race_generation(options.generation), ..options };
+        let field = Minefield::generate(&options);
+        Self {
+            metrics: BoardMetrics::compute(field.tile_map()),
+            racers: racers.into_iter()
+                .map(|(id, name)| (id, Racer { name, field: field.clone(), time: None }))
+                .collect(),
+            options,
+            start: now + COUNTDOWN,
+        }
+    }
+
+    pub fn options(&self) -> &MinefieldOptions {
+        &self.options
+    }
+
+    pub fn players(&self) -> impl Iterator<Item = PlayerId> + '_ {
+        self.racers.keys().copied()
+    }
+
+    pub fn field(&self, player: PlayerId) -> Option<&Minefield> {
+        self.racers.get(&player).map(|r| &r.field)
+    }
+
+    /// Time left before the start, `None` once the race started
+    pub fn countdown(&self, now: Instant) -> Option<Duration> {
+        (now < self.start).then(|| self.start - now)
+    }
+
+    /// Race time of a player, stopped when their game ended
+    pub fn elapsed(&self, player: PlayerId, now: Instant) -> Duration {
+        match self.racers.get(&player).and_then(|r| r.time) {
+            Some(time) => time,
+            None => now.saturating_duration_since(self.start),
+        }
+    }
+
+    /// Plays a move for a racer. Returns whether anything changed, or why the move is refused
+    pub fn play(&mut self, player: PlayerId, action: Move, now: Instant) -> Result<bool, &'static str> {
+        if now < self.start {
+            return Err("the race hasn't started yet");
+        }
+        let start = self.start;
+        let Some(racer) = self.racers.get_mut(&player) else { return Err("you are not in this race"); };
+        if racer.field.status() != GameStatus::Playing {
+            return Err("your game is over");
+        }
+        let changed = racer.field.play(action);
+        if racer.field.status() != GameStatus::Playing {
+            racer.time = Some(now - start);
+        }
+        Ok(changed)
+    }
+
+    /// Drops a player who left
+    pub fn remove(&mut self, player: PlayerId) {
+        self.racers.remove(&player);
+    }
+
+    pub fn progress(&self, player: PlayerId) -> Option<Progress> {
+        let field = &self.racers.get(&player)?.field;
+        let tile_map = field.tile_map();
+        let safe_tiles = tile_map.width() as usize * tile_map.height() as usize - tile_map.bomb_tile_count();
+        let uncovered = match field.status() {
+            // The exploded mine is uncovered too
+            GameStatus::Lost => field.uncovered_count().saturating_sub(1),
+            _ => field.uncovered_count(),
+        };
+        Some(Progress {
+            cleared: if safe_tiles == 0 { 1. } else { uncovered as f32 / safe_tiles as f32 },
+            status: field.status(),
+        })
+    }
+
+    /// Every racer finished, won or lost
+    pub fn is_over(&self) -> bool {
+        self.racers.values().all(|r| r.time.is_some())
+    }
+
+    /// Standings, winners by time then the others by how much they cleared
+    pub fn results(&self, now: Instant) -> Vec<RaceResult> {
+        let mut results: Vec<_> = self.racers.iter()
+            .map(|(&player, racer)| {
+                let seconds = self.elapsed(player, now).as_secs_f32();
+                let solved = solved_bbbv(racer.field.tile_map(), |c| racer.field.is_uncovered(c));
+                RaceResult {
+                    player,
+                    name: racer.name.clone(),
+                    status: racer.field.status(),
+                    seconds,
+                    bbbv: self.metrics.bbbv,
+                    solved_bbbv: solved,
+                    bbbv_per_second: if seconds > 0. { solved as f32 / seconds } else { 0. },
+                }
+            })
+            .collect();
+        results.sort_by(|a, b| {
+            let won = |r: &RaceResult| r.status == GameStatus::Won;
+            won(b).cmp(&won(a))
+                .then_with(|| match won(a) {
+                    true => a.seconds.total_cmp(&b.seconds),
+                    false => b.solved_bbbv.cmp(&a.solved_bbbv),
+                })
+        });
+        results
+    }
+}
+
+fn race_generation(generation: Generation) -> Generation {
+    match generation {
+        Generation::SafeFirstMove | Generation::NoGuess => Generation::SafeStart,
+        generation => generation,
+    }
+}
@@ -0,0 +1,214 @@
+use bevy::{prelude::*, log};
+use board_plugin::{
+    BoardState,
+    events::{EndlessRunEndedEvent, GameEndedEvent},
+    minefield::{Generation, Minefield},
+    resources::{
+        Board, BoardAssets, BoardOptions, BoardStart, DailyChallenge, DailyResults, EndlessBoard, EndlessOptions,
+        GameResult, PuzzleChallenge, PuzzleResults,
+    },
+};
+
+use crate::{
+    daily::current_challenge,
+    menu::{spawn_button, text_style},
+    start_game, AppState,
+};
+
+/// Win and loss screens over the finished board, showing the result and its metrics
+pub struct ResultsPlugin;
+
+impl Plugin for ResultsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(end_of_game);
+        app.add_system(spawn_results.in_schedule(OnEnter(AppState::GameOver)));
+        app.add_system(despawn_results.in_schedule(OnExit(AppState::GameOver)));
+        app.add_system(results_buttons.in_set(OnUpdate(AppState::GameOver)));
+    }
+}
+
+/// How the last game ended. Must be used as a resource
+#[derive(Debug, Resource)]
+enum GameOutcome {
+    Board(GameResult),
+    /// Endless run ended, with its count of tiles cleared
+    Endless(u64),
+}
+
+#[derive(Component)]
+struct ResultsPanel;
+
+#[derive(Component, Copy, Clone)]
+enum ResultsButton {
+    /// Same board again
+    Retry,
+    NewGame,
+    MainMenu,
+}
+
+fn end_of_game(
+    mut cmd: Commands,
+    mut ended_evr: EventReader<GameEndedEvent>,
+    mut run_ended_evr: EventReader<EndlessRunEndedEvent>,
+    mut next_app_state: ResMut<NextState<AppState>>,
+) {
+    let outcome = match (ended_evr.iter().last(), run_ended_evr.iter().last()) {
+        (Some(GameEndedEvent(result)), _) => GameOutcome::Board(result.clone()),
+        (None, Some(EndlessRunEndedEvent(cleared))) => GameOutcome::Endless(*cleared),
+        (None, None) => return,
+    };
+    cmd.insert_resource(outcome);
+    next_app_state.set(AppState::GameOver);
+}
+
+fn spawn_results(
+    mut cmd: Commands,
+    outcome: Res<GameOutcome>,
+    assets: Res<BoardAssets>,
+    board_options: Res<BoardOptions>,
+    (challenge, daily): (Option<Res<DailyChallenge>>, Res<DailyResults>),
+    (puzzle, puzzles): (Option<Res<PuzzleChallenge>>, Res<PuzzleResults>),
+) {
+    let challenge = current_challenge(challenge.as_deref(), &board_options);
+
+    cmd.spawn(NodeBundle {
+        style: Style {
+            position_type: PositionType::Absolute,
+            position: UiRect { left: Val::Px(10.), top: Val::Px(10.), ..default() },
+            padding: UiRect::all(Val::Px(8.)),
+            flex_direction: FlexDirection::Column,
+            ..default()
+        },
+        background_color: Color::rgba(0., 0., 0., 0.75).into(),
+        ..default()
+    })
+    .insert(Name::new("Results"))
+    .insert(ResultsPanel)
+    .with_children(|parent| {
+        match &*outcome {
+            GameOutcome::Board(result) => {
+                let title = if result.won { "Board cleared!" } else { "Boom!" };
+                parent.spawn(TextBundle::from_section(title, text_style(&assets, 16.)));
+                parent.spawn(TextBundle::from_section(summary(result), text_style(&assets, 8.)));
+                if let Some(challenge) = challenge {
+                    parent.spawn(TextBundle::from_section(daily_summary(&challenge, &daily), text_style(&assets, 8.)));
+                }
+                if let Some(puzzle) = puzzle {
+                    parent.spawn(TextBundle::from_section(puzzle_summary(&puzzle, &puzzles), text_style(&assets, 8.)));
+                }
+            }
+            GameOutcome::Endless(cleared) => {
+                parent.spawn(TextBundle::from_section("Run over", text_style(&assets, 16.)));
+                parent.spawn(TextBundle::from_section(format!("{} tiles cleared", cleared), text_style(&assets, 8.)));
+            }
+        }
+        parent.spawn(TextBundle::from_section(" ", text_style(&assets, 8.)));
+        spawn_button(parent, &assets, "Retry", ResultsButton::Retry);
+        spawn_button(parent, &assets, "New game", ResultsButton::NewGame);
+        spawn_button(parent, &assets, "Main menu", ResultsButton::MainMenu);
+    });
+}
+
+fn results_buttons(
+    mut cmd: Commands,
+    buttons: Query<(&Interaction, &ResultsButton), Changed<Interaction>>,
+    (board, endless): (Option<Res<Board>>, Option<Res<EndlessBoard>>),
+    (challenge, board_start): (Option<ResMut<DailyChallenge>>, Option<Res<BoardStart>>),
+    mut board_options: ResMut<BoardOptions>,
+    mut next_app_state: ResMut<NextState<AppState>>,
+    mut next_board_state: ResMut<NextState<BoardState>>,
+) {
+    let Some((_, &button)) = buttons.iter().find(|(i, _)| **i == Interaction::Clicked) else { return; };
+    match (button, endless) {
+        (ResultsButton::MainMenu, _) => {
+            next_app_state.set(AppState::MainMenu);
+            return;
+        }
+        (ResultsButton::Retry, Some(endless)) => {
+            log::info!("Retrying endless run");
+            cmd.insert_resource(endless.options.clone());
+            next_board_state.set(BoardState::Endless);
+        }
+        (ResultsButton::NewGame, Some(_)) => {
+            log::info!("Starting endless run");
+            cmd.remove_resource::<EndlessOptions>();
+            next_board_state.set(BoardState::Endless);
+        }
+        (ResultsButton::Retry, None) => {
+            log::info!("Retrying board");
+            let daily = current_challenge(challenge.as_deref(), &board_options).is_some();
+            // Puzzles and daily boards start the same way again by themselves
+            if daily {
+                if let Some(mut challenge) = challenge {
+                    challenge.official = false;
+                }
+            } else if board_start.is_none() {
+                if let Some(board) = board {
+                    let mut field = Minefield::new(board.tile_map.clone());
+                    if let Some(start) = field.safe_start().filter(|_| board_options.generation == Generation::SafeStart) {
+                        field.reveal(start);
+                    }
+                    cmd.insert_resource(BoardStart(field));
+                }
+            }
+            next_board_state.set(BoardState::Active);
+        }
+        (ResultsButton::NewGame, None) => {
+            log::info!("Starting game");
+            start_game(&mut cmd, &mut board_options, false);
+            next_board_state.set(BoardState::Active);
+        }
+    }
+    next_app_state.set(AppState::InGame);
+}
+
+fn summary(result: &GameResult) -> String {
+    let metrics = &result.metrics;
+    let mut text = format!(
+        "{}\nTime {:.2}s\n3BV {}/{}   3BV/s {:.2}\nIOE {:.2}   ZiNi {}\nClicks {} (left {}, right {}, chord {})",
+        result.difficulty,
+        result.time,
+        metrics.solved_bbbv,
+        metrics.bbbv,
+        metrics.bbbv_per_second,
+        metrics.ioe,
+        metrics.zini,
+        metrics.clicks.total(),
+        metrics.clicks.left,
+        metrics.clicks.right,
+        metrics.clicks.chord,
+    );
+    if result.won {
+        text += &format!("\nZiNi efficiency {:.0}%", metrics.zini_efficiency * 100.);
+    }
+    if result.bot {
+        text += "\nPlayed by a bot";
+    }
+    if result.undo_used {
+        text += "\nPractice game, undo used";
+    } else if result.practice {
+        text += "\nPractice game";
+    }
+    text
+}
+
+fn daily_summary(challenge: &DailyChallenge, daily: &DailyResults) -> String {
+    let title = format!("\nDaily challenge {} {}", challenge.difficulty.label(), challenge.date);
+    match challenge.official {
+        true => format!("{}\nStreak {}", title, daily.streak(challenge.difficulty, challenge.date)),
+        false => format!("{}\nReplay, only the first attempt counts", title),
+    }
+}
+
+fn puzzle_summary(puzzle: &PuzzleChallenge, puzzles: &PuzzleResults) -> String {
+    match puzzles.completion(&puzzle.id) {
+        Some(completion) => format!("\nPuzzle {}\nBest time {:.2}s", puzzle.name, completion.best_time),
+        None => format!("\nPuzzle {}\nNot solved yet", puzzle.name),
+    }
+}
+
+fn despawn_results(mut cmd: Commands, panels: Query<Entity, With<ResultsPanel>>) {
+    for panel in panels.iter() {
+        cmd.entity(panel).despawn_recursive();
+    }
+}
@@ -0,0 +1,298 @@
+use bevy::{prelude::*, log};
+use board_plugin::{
+    minefield::Generation,
+    resources::{
+        tilemap::MAX_BOMBS_PER_TILE, ActionInput, BoardAssets, BoardOptions, ChordMode, Difficulty, InputAction, Settings,
+        MAX_SIDE,
+    },
+    theme::Theme,
+};
+
+use crate::{
+    menu::{spawn_button, text_style, BUTTON_COLOR},
+    themes::theme_names,
+    AppState, ResumeState,
+};
+
+/// Settings screen. Settings are saved when leaving it, and apply as soon as they change:
+/// board defaults to the next game, rules, theme and sound to the current one
+pub struct SettingsPlugin;
+
+impl Plugin for SettingsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SettingsReturn>();
+        app.add_system(spawn_settings.in_schedule(OnEnter(AppState::Settings)));
+        app.add_system(despawn_settings.in_schedule(OnExit(AppState::Settings)));
+        app.add_systems(
+            (settings_buttons, update_setting_texts, leave_settings)
+                .chain()
+                .in_set(OnUpdate(AppState::Settings)),
+        );
+        app.add_system(apply_settings);
+    }
+}
+
+/// State the settings screen goes back to, kept while the controls screen is open from it
+#[derive(Debug, Default, Resource)]
+struct SettingsReturn(AppState);
+
+#[derive(Component)]
+struct SettingsRoot;
+
+/// A line of the settings screen
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Setting {
+    Preset,
+    Width,
+    Height,
+    Bombs,
+    BombsPerTile,
+    Generation,
+    QuestionMarks,
+    Chord,
+    Theme,
+    Volume,
+}
+
+impl Setting {
+    const ALL: [Setting; 10] = [
+        Self::Preset,
+        Self::Width,
+        Self::Height,
+        Self::Bombs,
+        Self::BombsPerTile,
+        Self::Generation,
+        Self::QuestionMarks,
+        Self::Chord,
+        Self::Theme,
+        Self::Volume,
+    ];
+
+    fn label(&self) -> &'static str {
+        match self {
+            Self::Preset => "Board",
+            Self::Width => "Width",
+            Self::Height => "Height",
+            Self::Bombs => "Mines",
+            Self::BombsPerTile => "Mines per tile",
+            Self::Generation => "Start",
+            Self::QuestionMarks => "Question marks",
+            Self::Chord => "Chording",
+            Self::Theme => "Theme",
+            Self::Volume => "Sound volume",
+        }
+    }
+
+    fn value(&self, settings: &Settings) -> String {
+        match self {
+            Self::Preset => preset(settings).map_or("Custom", |d| d.label()).to_owned(),
+            Self::Width => settings.map_size.0.to_string(),
+            Self::Height => settings.map_size.1.to_string(),
+            Self::Bombs => settings.bomb_count.to_string(),
+            Self::BombsPerTile => settings.max_bombs_per_tile.to_string(),
+            Self::Generation => settings.generation.label().to_owned(),
+            Self::QuestionMarks => if settings.question_marks { "On" } else { "Off" }.to_owned(),
+            Self::Chord => settings.chord.label().to_owned(),
+            Self::Theme => settings.theme.clone(),
+            Self::Volume => format!("{:.0}%", settings.volume * 100.),
+        }
+    }
+
+    /// Moves the setting to its next value, or its previous one with a negative `step`.
+    /// Themes go through the loaded ones
+    fn change(&self, settings: &mut Settings, step: i32, themes: &[String]) {
+        let add = |value: u16, min: u16, max: u16| (value as i32 + step).clamp(min as i32, max as i32) as u16;
+        match self {
+            Self::Preset => {
+                let index = preset(settings).and_then(|d| Difficulty::ALL.iter().position(|&p| p == d));
+                let next = match index {
+                    Some(i) => cycle(i, step, Difficulty::ALL.len()),
+                    None => 0,
+                };
+                let (map_size, bomb_count) = Difficulty::ALL[next].parameters();
+                settings.map_size = map_size;
+                settings.bomb_count = bomb_count;
+                settings.max_bombs_per_tile = 1;
+            }
+            Self::Width => settings.map_size.0 = add(settings.map_size.0, 2, MAX_SIDE),
+            Self::Height => settings.map_size.1 = add(settings.map_size.1, 2, MAX_SIDE),
+            Self::Bombs => settings.bomb_count = add(settings.bomb_count, 1, settings.max_bomb_count()),
+            Self::BombsPerTile => {
+                settings.max_bombs_per_tile = add(settings.max_bombs_per_tile as u16, 1, MAX_BOMBS_PER_TILE as u16) as u8;
+            }
+            Self::Generation => {
+                let index = Generation::ALL.iter().position(|&g| g == settings.generation).unwrap_or(0);
+                settings.generation = Generation::ALL[cycle(index, step, Generation::ALL.len())];
+            }
+            Self::QuestionMarks => settings.question_marks = !settings.question_marks,
+            Self::Chord => {
+                let index = ChordMode::ALL.iter().position(|&c| c == settings.chord).unwrap_or(0);
+                settings.chord = ChordMode::ALL[cycle(index, step, ChordMode::ALL.len())];
+            }
+            Self::Theme if themes.is_empty() => (),
+            Self::Theme => {
+                let index = themes.iter().position(|t| *t == settings.theme).unwrap_or(0);
+                settings.theme = themes[cycle(index, step, themes.len())].clone();
+            }
+            Self::Volume => settings.volume = ((settings.volume * 10.).round() + step as f32).clamp(0., 10.) / 10.,
+        }
+        // A smaller board may not hold as many mines
+        settings.bomb_count = settings.bomb_count.min(settings.max_bomb_count());
+    }
+}
+
+fn preset(settings: &Settings) -> Option<Difficulty> {
+    if settings.max_bombs_per_tile > 1 {
+        return None;
+    }
+    Difficulty::ALL.into_iter().find(|d| d.parameters() == (settings.map_size, settings.bomb_count))
+}
+
+fn cycle(index: usize, step: i32, len: usize) -> usize {
+    (index as i32 + step).rem_euclid(len as i32) as usize
+}
+
+#[derive(Component, Copy, Clone)]
+enum SettingsButton {
+    Change(Setting, i32),
+    Controls,
+    Reset,
+    Back,
+}
+
+#[derive(Component)]
+struct SettingText(Setting);
+
+fn spawn_settings(
+    mut cmd: Commands,
+    assets: Res<BoardAssets>,
+    resume: Res<ResumeState>,
+    mut settings_return: ResMut<SettingsReturn>,
+) {
+    // Coming back from the controls screen keeps where settings were opened from
+    if resume.0 != AppState::Settings {
+        settings_return.0 = resume.0;
+    }
+
+    cmd.spawn(NodeBundle {
+        style: Style {
+            size: Size::new(Val::Percent(100.), Val::Percent(100.)),
+            flex_direction: FlexDirection::Column,
+            align_items: AlignItems::Center,
+            justify_content: JustifyContent::Center,
+            ..default()
+        },
+        background_color: Color::rgb(0.1, 0.1, 0.1).into(),
+        ..default()
+    })
+    .insert(Name::new("Settings"))
+    .insert(SettingsRoot)
+    .with_children(|parent| {
+        parent.spawn(TextBundle::from_section("Settings\n", text_style(&assets, 24.)));
+        for setting in Setting::ALL {
+            parent.spawn(NodeBundle {
+                style: Style {
+                    flex_direction: FlexDirection::Row,
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                ..default()
+            })
+            .with_children(|row| {
+                row.spawn(TextBundle::from_section(setting.label(), text_style(&assets, 10.)).with_style(Style {
+                    size: Size::width(Val::Px(160.)),
+                    ..default()
+                }));
+                spawn_arrow(row, &assets, "<", SettingsButton::Change(setting, -1));
+                row.spawn(TextBundle::from_section("", text_style(&assets, 10.)).with_style(Style {
+                    size: Size::width(Val::Px(240.)),
+                    justify_content: JustifyContent::Center,
+                    ..default()
+                }))
+                .insert(SettingText(setting));
+                spawn_arrow(row, &assets, ">", SettingsButton::Change(setting, 1));
+            });
+        }
+        parent.spawn(TextBundle::from_section(" ", text_style(&assets, 8.)));
+        spawn_button(parent, &assets, "Input bindings", SettingsButton::Controls);
+        spawn_button(parent, &assets, "Reset to defaults", SettingsButton::Reset);
+        spawn_button(parent, &assets, "Back", SettingsButton::Back);
+        parent.spawn(TextBundle::from_section(
+            "Board settings apply to the next game. Escape to go back",
+            text_style(&assets, 8.),
+        ));
+    });
+}
+
+fn spawn_arrow(parent: &mut ChildBuilder, assets: &BoardAssets, label: &str, action: SettingsButton) {
+    parent.spawn(ButtonBundle {
+        style: Style {
+            padding: UiRect::all(Val::Px(4.)),
+            margin: UiRect::all(Val::Px(2.)),
+            ..default()
+        },
+        background_color: BUTTON_COLOR.into(),
+        ..default()
+    })
+    .insert(action)
+    .with_children(|button| {
+        button.spawn(TextBundle::from_section(label, text_style(assets, 10.)));
+    });
+}
+
+fn despawn_settings(mut cmd: Commands, roots: Query<Entity, With<SettingsRoot>>, settings: Res<Settings>) {
+    for root in roots.iter() {
+        cmd.entity(root).despawn_recursive();
+    }
+    match settings.save() {
+        Ok(()) => log::info!("Saved settings to {}", settings.path().display()),
+        Err(e) => log::warn!("Failed to save settings to {}: {}", settings.path().display(), e),
+    }
+}
+
+fn settings_buttons(
+    buttons: Query<(&Interaction, &SettingsButton), Changed<Interaction>>,
+    mut settings: ResMut<Settings>,
+    themes: Res<Assets<Theme>>,
+    settings_return: Res<SettingsReturn>,
+    mut next_app_state: ResMut<NextState<AppState>>,
+) {
+    let Some((_, &button)) = buttons.iter().find(|(i, _)| **i == Interaction::Clicked) else { return; };
+    match button {
+        SettingsButton::Change(setting, step) => setting.change(&mut settings, step, &theme_names(&themes)),
+        SettingsButton::Controls => next_app_state.set(AppState::Controls),
+        SettingsButton::Reset => settings.reset(),
+        SettingsButton::Back => next_app_state.set(settings_return.0),
+    }
+}
+
+fn update_setting_texts(
+    settings: Res<Settings>,
+    mut texts: Query<(&mut Text, &SettingText)>,
+    spawned: Query<(), Added<SettingText>>,
+) {
+    if !settings.is_changed() && spawned.is_empty() {
+        return;
+    }
+    for (mut text, SettingText(setting)) in texts.iter_mut() {
+        text.sections[0].value = setting.value(&settings);
+    }
+}
+
+fn leave_settings(
+    input: ActionInput,
+    settings_return: Res<SettingsReturn>,
+    mut next_app_state: ResMut<NextState<AppState>>,
+) {
+    if input.just_pressed(InputAction::Pause) {
+        next_app_state.set(settings_return.0);
+    }
+}
+
+/// Applies changed settings to the board options. The theme is applied by `themes::ThemesPlugin`
+fn apply_settings(settings: Res<Settings>, mut board_options: ResMut<BoardOptions>) {
+    if !settings.is_changed() || settings.is_added() {
+        return;
+    }
+    settings.apply(&mut board_options);
+}
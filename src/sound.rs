@@ -0,0 +1,160 @@
+use std::time::Duration;
+
+use bevy::{
+    audio::{AddAudioSource, Source},
+    prelude::*,
+    reflect::TypeUuid,
+};
+use board_plugin::{
+    events::{BoardCompletedEvent, BombExplosionEvent, EndlessRunEndedEvent, TileMarkEvent, TileTriggerEvent},
+    resources::Settings,
+};
+
+const SAMPLE_RATE: u32 = 44_100;
+
+/// Sound effects of the board, synthesized so the game ships without audio files, played at
+/// the volume of the settings
+pub struct SoundPlugin;
+
+impl Plugin for SoundPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_audio_source::<Effect>();
+        app.add_startup_system(create_sound_effects);
+        app.add_system(play_sound_effects);
+    }
+}
+
+/// Sound effect made of notes played one after the other. Must be used as an asset
+#[derive(Debug, Clone, TypeUuid)]
+#[uuid = "5b0e4a5e-2f8c-4b43-9d6e-7c1a1f3e8b21"]
+pub struct Effect {
+    notes: Vec<Note>,
+}
+
+#[derive(Debug, Copy, Clone)]
+struct Note {
+    /// Pitch in hertz, noise when missing
+    frequency: Option<f32>,
+    /// Length in seconds
+    duration: f32,
+}
+
+impl Effect {
+    fn tone(frequency: f32, duration: f32) -> Self {
+        Self::melody(&[(frequency, duration)])
+    }
+
+    fn melody(notes: &[(f32, f32)]) -> Self {
+        let notes = notes.iter().map(|&(frequency, duration)| Note { frequency: Some(frequency), duration });
+        Self { notes: notes.collect() }
+    }
+
+    fn noise(duration: f32) -> Self {
+        Self { notes: vec![Note { frequency: None, duration }] }
+    }
+}
+
+impl Decodable for Effect {
+    type Decoder = EffectDecoder;
+    type DecoderItem = f32;
+
+    fn decoder(&self) -> Self::Decoder {
+        EffectDecoder { notes: self.notes.clone(), note: 0, sample: 0, noise: 0x2545_f491 }
+    }
+}
+
+/// Samples of an `Effect`, each note fading out until the next
+pub struct EffectDecoder {
+    notes: Vec<Note>,
+    note: usize,
+    /// Sample of the current note
+    sample: u32,
+    /// Xorshift state of the noise
+    noise: u32,
+}
+
+impl Iterator for EffectDecoder {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        let note = *self.notes.get(self.note)?;
+        let length = (note.duration * SAMPLE_RATE as f32) as u32;
+        if self.sample >= length {
+            self.note += 1;
+            self.sample = 0;
+            return self.next();
+        }
+        let t = self.sample as f32 / SAMPLE_RATE as f32;
+        let fade = (1. - self.sample as f32 / length as f32).powi(2);
+        self.sample += 1;
+        let wave = match note.frequency {
+            Some(frequency) => (t * frequency * std::f32::consts::TAU).sin(),
+            None => {
+                self.noise ^= self.noise << 13;
+                self.noise ^= self.noise >> 17;
+                self.noise ^= self.noise << 5;
+                self.noise as f32 / u32::MAX as f32 * 2. - 1.
+            }
+        };
+        Some(wave * fade * 0.3)
+    }
+}
+
+impl Source for EffectDecoder {
+    fn current_frame_len(&self) -> Option<usize> {
+        None
+    }
+
+    fn channels(&self) -> u16 {
+        1
+    }
+
+    fn sample_rate(&self) -> u32 {
+        SAMPLE_RATE
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        Some(Duration::from_secs_f32(self.notes.iter().map(|n| n.duration).sum()))
+    }
+}
+
+/// Must be used as a resource
+#[derive(Debug, Resource)]
+struct SoundEffects {
+    reveal: Handle<Effect>,
+    flag: Handle<Effect>,
+    explosion: Handle<Effect>,
+    win: Handle<Effect>,
+}
+
+fn create_sound_effects(mut cmd: Commands, mut effects: ResMut<Assets<Effect>>) {
+    cmd.insert_resource(SoundEffects {
+        reveal: effects.add(Effect::tone(880., 0.04)),
+        flag: effects.add(Effect::tone(440., 0.08)),
+        explosion: effects.add(Effect::noise(0.6)),
+        win: effects.add(Effect::melody(&[(523.25, 0.12), (659.25, 0.12), (783.99, 0.12), (1046.5, 0.3)])),
+    });
+}
+
+fn play_sound_effects(
+    audio: Res<Audio<Effect>>,
+    sounds: Res<SoundEffects>,
+    settings: Res<Settings>,
+    mut trigger_evr: EventReader<TileTriggerEvent>,
+    mut mark_evr: EventReader<TileMarkEvent>,
+    mut explosion_evr: EventReader<BombExplosionEvent>,
+    mut ended_evr: (EventReader<BoardCompletedEvent>, EventReader<EndlessRunEndedEvent>),
+) {
+    let played = [
+        (&sounds.reveal, trigger_evr.iter().count() > 0),
+        (&sounds.flag, mark_evr.iter().count() > 0),
+        (&sounds.explosion, explosion_evr.iter().count() + ended_evr.1.iter().count() > 0),
+        (&sounds.win, ended_evr.0.iter().count() > 0),
+    ];
+    if settings.volume <= 0. {
+        return;
+    }
+    for (sound, _) in played.into_iter().filter(|(_, play)| *play) {
+        audio.play_with_settings(sound.clone(), PlaybackSettings::ONCE.with_volume(settings.volume));
+    }
+}
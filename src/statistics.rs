@@ -0,0 +1,132 @@
+use bevy::prelude::*;
+use board_plugin::{
+    daily::Date,
+    resources::{ActionInput, BoardAssets, DailyResults, DifficultyStats, Difficulty, InputAction, Stats, TIME_BUCKET},
+};
+
+use crate::{AppState, ResumeState};
+
+/// Longest bar of the win time distribution, in characters
+const BAR_LENGTH: u32 = 30;
+
+/// Statistics screen, listing the games played on each difficulty
+pub struct StatisticsPlugin;
+
+impl Plugin for StatisticsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(spawn_statistics.in_schedule(OnEnter(AppState::Statistics)));
+        app.add_system(despawn_statistics.in_schedule(OnExit(AppState::Statistics)));
+        app.add_system(leave_statistics.in_set(OnUpdate(AppState::Statistics)));
+    }
+}
+
+#[derive(Component)]
+struct StatisticsRoot;
+
+fn spawn_statistics(
+    mut cmd: Commands,
+    assets: Res<BoardAssets>,
+    stats: Option<Res<Stats>>,
+    daily: Option<Res<DailyResults>>,
+) {
+    let text_style = |size: f32| TextStyle {
+        font: assets.bomb_counter_font.clone(),
+        font_size: size,
+        color: Color::WHITE,
+    };
+
+    cmd.spawn(NodeBundle {
+        style: Style {
+            size: Size::new(Val::Percent(100.), Val::Percent(100.)),
+            flex_direction: FlexDirection::Column,
+            align_items: AlignItems::Center,
+            justify_content: JustifyContent::Center,
+            ..default()
+        },
+        background_color: Color::rgb(0.1, 0.1, 0.1).into(),
+        ..default()
+    })
+    .insert(Name::new("Statistics"))
+    .insert(StatisticsRoot)
+    .with_children(|parent| {
+        parent.spawn(TextBundle::from_section("Statistics", text_style(24.)));
+        let difficulties = stats.as_ref().map(|s| &s.difficulties).into_iter().flatten();
+        let mut empty = true;
+        for (difficulty, stats) in difficulties {
+            empty = false;
+            parent.spawn(TextBundle::from_sections([
+                TextSection::new(format!("\n{}\n", difficulty), text_style(14.)),
+                TextSection::new(summary(stats), text_style(10.)),
+                TextSection::new(distribution(stats), text_style(8.)),
+            ]));
+        }
+        if empty {
+            parent.spawn(TextBundle::from_section("\nNo games played yet", text_style(10.)));
+        }
+        if let Some(daily) = daily.as_deref().map(daily_summary).filter(|s| !s.is_empty()) {
+            parent.spawn(TextBundle::from_sections([
+                TextSection::new("\nDaily challenge\n", text_style(14.)),
+                TextSection::new(daily, text_style(10.)),
+            ]));
+        }
+    });
+}
+
+fn summary(stats: &DifficultyStats) -> String {
+    let best_times: Vec<String> = stats.best_times.iter().take(3).map(|t| format!("{:.2}s", t)).collect();
+    format!(
+        "Played {}   Won {}   Win rate {:.0}%\nStreak {}   Best streak {}\nBest times {}\n",
+        stats.played,
+        stats.won,
+        stats.win_rate() * 100.,
+        stats.current_streak,
+        stats.best_streak,
+        if best_times.is_empty() { "-".to_owned() } else { best_times.join("  ") },
+    )
+}
+
+/// Streaks of the daily challenges played, by difficulty
+fn daily_summary(daily: &DailyResults) -> String {
+    let today = Date::today();
+    Difficulty::ALL.into_iter()
+        .filter_map(|difficulty| {
+            let history = daily.history(difficulty).filter(|h| !h.attempts.is_empty())?;
+            let played = match daily.attempt(difficulty, today) {
+                Some(attempt) if attempt.won() => "won today",
+                Some(_) => "played today",
+                None => "not played today",
+            };
+            Some(format!(
+                "{}   Streak {}   Best streak {}   {}\n",
+                difficulty.label(), history.streak(today), history.best_streak, played,
+            ))
+        })
+        .collect()
+}
+
+/// Win times as a text histogram
+fn distribution(stats: &DifficultyStats) -> String {
+    let Some(&max) = stats.time_distribution.values().max() else { return String::new(); };
+    stats.time_distribution.iter()
+        .map(|(start, count)| {
+            let bar = "#".repeat((count * BAR_LENGTH).div_ceil(max) as usize);
+            format!("{:>4}-{:<4}s {} {}\n", start, start + TIME_BUCKET, bar, count)
+        })
+        .collect()
+}
+
+fn despawn_statistics(mut cmd: Commands, roots: Query<Entity, With<StatisticsRoot>>) {
+    for root in roots.iter() {
+        cmd.entity(root).despawn_recursive();
+    }
+}
+
+fn leave_statistics(
+    input: ActionInput,
+    resume: Res<ResumeState>,
+    mut next_app_state: ResMut<NextState<AppState>>,
+) {
+    if input.just_pressed(InputAction::Pause) || input.just_pressed(InputAction::Statistics) {
+        next_app_state.set(resume.0);
+    }
+}
@@ -0,0 +1,92 @@
+use bevy::{asset::LoadState, prelude::*, log};
+use board_plugin::{
+    resources::{BoardAssets, Settings},
+    theme::Theme,
+};
+
+use crate::AppState;
+
+/// Theme used when the one of the settings is missing
+const DEFAULT_THEME: &str = "Default";
+
+/// Board themes of the `themes` asset folder. The game waits for them before showing the main
+/// menu, then follows the theme of the settings, reloaded theme files included
+pub struct ThemesPlugin;
+
+impl Plugin for ThemesPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_startup_system(load_themes);
+        app.add_system(finish_loading.in_set(OnUpdate(AppState::Loading)));
+        app.add_system(apply_theme);
+    }
+}
+
+/// Must be used as a resource
+#[derive(Debug, Resource)]
+struct ThemeHandles(Vec<HandleUntyped>);
+
+/// Names of the loaded themes, sorted
+pub fn theme_names(themes: &Assets<Theme>) -> Vec<String> {
+    let mut names: Vec<String> = themes.iter().map(|(_, Theme(assets))| assets.label.clone()).collect();
+    names.sort();
+    names.dedup();
+    names
+}
+
+/// Theme named `name`, the default one otherwise
+fn find_theme<'a>(themes: &'a Assets<Theme>, name: &str) -> Option<&'a BoardAssets> {
+    let named = |name: &str| themes.iter().map(|(_, Theme(assets))| assets).find(|a| a.label == name);
+    named(name).or_else(|| named(DEFAULT_THEME))
+}
+
+fn load_themes(mut cmd: Commands, asset_server: Res<AssetServer>) {
+    let handles = asset_server.load_folder("themes").unwrap_or_else(|e| {
+        log::error!("Failed to load themes: {}", e);
+        Vec::new()
+    });
+    cmd.insert_resource(ThemeHandles(handles));
+}
+
+fn finish_loading(
+    handles: Res<ThemeHandles>,
+    asset_server: Res<AssetServer>,
+    themes: Res<Assets<Theme>>,
+    settings: Res<Settings>,
+    mut next_app_state: ResMut<NextState<AppState>>,
+) {
+    // A broken theme file is logged by the asset server, the other themes are still used
+    match asset_server.get_group_load_state(handles.0.iter().map(|h| h.id())) {
+        LoadState::Loaded | LoadState::Failed => (),
+        _ => return,
+    }
+    if !theme_names(&themes).contains(&settings.theme) {
+        log::warn!("Unknown theme {}, using the {} theme", settings.theme, DEFAULT_THEME);
+    }
+    if find_theme(&themes, &settings.theme).is_none() {
+        log::error!("No {} theme, the board is drawn with plain colors", DEFAULT_THEME);
+    }
+    next_app_state.set(AppState::MainMenu);
+}
+
+/// Switches the board assets to the theme of the settings when it is chosen, loaded or reloaded.
+/// Spawned tiles follow, see `board_plugin::components::Themed`
+fn apply_theme(
+    settings: Res<Settings>,
+    themes: Res<Assets<Theme>>,
+    mut theme_evr: EventReader<AssetEvent<Theme>>,
+    mut board_assets: ResMut<BoardAssets>,
+) {
+    let updated = theme_evr.iter().filter_map(|e| match e {
+        AssetEvent::Created { handle } | AssetEvent::Modified { handle } => themes.get(handle),
+        AssetEvent::Removed { .. } => None,
+    });
+    let updated: Vec<&str> = updated.map(|Theme(assets)| assets.label.as_str()).collect();
+    if !settings.is_changed() && updated.is_empty() {
+        return;
+    }
+    let Some(theme) = find_theme(&themes, &settings.theme) else { return; };
+    if theme.label != board_assets.label || updated.contains(&theme.label.as_str()) {
+        log::info!("Using the {} theme", theme.label);
+        *board_assets = theme.clone();
+    }
+}